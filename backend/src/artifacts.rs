@@ -0,0 +1,56 @@
+//! Placing detected Artifacts (HTTP endpoints and requests, FFI exports and
+//! imports) under the Room or Building of the parsed file that contains them.
+
+use crate::models::GameEntity;
+
+/// Where an Artifact belongs: the entity starting at a byte (an annotated
+/// definition) or the deepest one containing a byte range.
+pub enum Anchor {
+    StartsAt(usize),
+    Contains(usize, usize),
+}
+
+/// Places the artifact under the deepest Room or Building matching `anchor`.
+pub fn attach(entities: &mut [GameEntity], anchor: &Anchor, artifact_for: &dyn Fn(&str) -> GameEntity) -> bool {
+    for entity in entities.iter_mut() {
+        let (id, children, span) = match entity {
+            GameEntity::Room { id, children, span, .. } | GameEntity::Building { id, children, span, .. } => {
+                (id, children, span)
+            }
+            _ => continue,
+        };
+        let Some(span) = span else { continue };
+        let (start, end) = (span.start_byte as usize, span.end_byte as usize);
+        let matches = match anchor {
+            Anchor::StartsAt(byte) => start == *byte,
+            Anchor::Contains(from, to) => start <= *from && *to <= end,
+        };
+        let within = match anchor {
+            Anchor::StartsAt(byte) => start <= *byte && *byte < end,
+            Anchor::Contains(..) => matches,
+        };
+
+        if within && attach(children, anchor, artifact_for) {
+            return true;
+        }
+        if matches {
+            let artifact = artifact_for(id);
+            push_unique(children, artifact);
+            return true;
+        }
+    }
+    false
+}
+
+/// Adds `artifact`, suffixing its id (`#2`, `#3`, ...) if a sibling already uses it.
+pub fn push_unique(siblings: &mut Vec<GameEntity>, mut artifact: GameEntity) {
+    if let GameEntity::Artifact { id, .. } = &mut artifact {
+        let base = id.clone();
+        let mut n = 2;
+        while siblings.iter().any(|s| matches!(s, GameEntity::Artifact { id: existing, .. } if existing == id)) {
+            *id = format!("{base}#{n}");
+            n += 1;
+        }
+    }
+    siblings.push(artifact);
+}
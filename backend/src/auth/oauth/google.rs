@@ -7,9 +7,9 @@ use super::super::config::AuthConfig;
 /// Build the Google OAuth authorization URL.
 pub fn build_google_authorize_url(config: &AuthConfig) -> String {
     format!(
-        "https://accounts.google.com/o/oauth2/v2/auth?client_id={}&redirect_uri={}&response_type=code&scope=openid%20email%20profile",
+        "https://accounts.google.com/o/oauth2/v2/auth?client_id={}&redirect_uri={}/auth/google/callback&response_type=code&scope=openid%20email%20profile",
         config.google_client_id,
-        format!("{}/auth/google/callback", config.frontend_url),
+        config.frontend_url,
     )
 }
 
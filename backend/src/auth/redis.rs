@@ -156,3 +156,46 @@ pub async fn get_github_token(pool: &RedisPool, github_id: i64) -> Result<Option
 
     Ok(value)
 }
+
+/// How long a repository access check is reused before asking GitHub again.
+pub const REPO_ACCESS_TTL_SECS: u64 = 5 * 60;
+
+/// Cache what a user may do with a repository (`read` or `push`).
+pub async fn store_repo_access(
+    pool: &RedisPool,
+    github_id: i64,
+    repository_id: &str,
+    access: &str,
+) -> Result<(), String> {
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|e| format!("Redis conn error: {}", e))?;
+    let key = format!("repo_access:{}:{}", github_id, repository_id);
+
+    conn.set_ex::<_, _, ()>(&key, access, REPO_ACCESS_TTL_SECS)
+        .await
+        .map_err(|e| format!("Redis SETEX error: {}", e))?;
+
+    Ok(())
+}
+
+/// Retrieve a cached repository access check, if it has not expired.
+pub async fn get_repo_access(
+    pool: &RedisPool,
+    github_id: i64,
+    repository_id: &str,
+) -> Result<Option<String>, String> {
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|e| format!("Redis conn error: {}", e))?;
+    let key = format!("repo_access:{}:{}", github_id, repository_id);
+
+    let value: Option<String> = conn
+        .get(&key)
+        .await
+        .map_err(|e| format!("Redis GET error: {}", e))?;
+
+    Ok(value)
+}
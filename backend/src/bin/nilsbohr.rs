@@ -0,0 +1,193 @@
+//! Command-line tools for CI.
+//!
+//! ```text
+//! nilsbohr check [REPO]    parse REPO (default: .) and report architecture rule violations
+//! nilsbohr coverage REPORT --world ID [--commit SHA] [--format lcov|cobertura|llvm_json] [--server URL]
+//!                          upload a coverage report onto a parsed world
+//! ```
+//!
+//! `coverage` authenticates with the `NILSBOHR_TOKEN` environment variable and
+//! talks to `NILSBOHR_SERVER` (default: http://localhost:5000) unless
+//! `--server` is given.
+//!
+//! Exit codes: 0 when clean, 1 when violations were found or the upload
+//! failed, 2 on usage or configuration errors.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use backend::coverage::CoverageReport;
+use backend::models::{CoverageFormat, CoverageSummary};
+use backend::{parser, rules};
+
+const USAGE: &str = "usage: nilsbohr check [REPO]
+       nilsbohr coverage REPORT --world ID [--commit SHA] [--format lcov|cobertura|llvm_json] [--server URL]";
+
+const DEFAULT_SERVER: &str = "http://localhost:5000";
+
+const FORMATS: [CoverageFormat; 3] = [CoverageFormat::Lcov, CoverageFormat::Cobertura, CoverageFormat::LlvmJson];
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("check") => check(args.get(1).map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."))),
+        Some("coverage") => match CoverageArgs::parse(&args[1..]) {
+            Some(coverage_args) => coverage(coverage_args),
+            None => usage(),
+        },
+        _ => usage(),
+    }
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::from(2)
+}
+
+fn check(repo: PathBuf) -> ExitCode {
+    match rules::load(&repo) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            println!("No {} in {}; nothing to check", rules::RULES_PATH, repo.display());
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    }
+
+    let seed = parser::generate_world(&repo);
+    let violations = rules::violations(&seed.highways);
+    for v in &violations {
+        let reason = v.reason.as_deref().map(|r| format!(" ({r})")).unwrap_or_default();
+        println!("{}: {} -> {} [{:?}]{reason}", v.rule, v.from_id, v.to_id, v.route_type);
+    }
+
+    if violations.is_empty() {
+        println!("No architecture rule violations");
+        ExitCode::SUCCESS
+    } else {
+        println!("{} architecture rule violation(s)", violations.len());
+        ExitCode::from(1)
+    }
+}
+
+struct CoverageArgs {
+    report: PathBuf,
+    world: String,
+    commit: Option<String>,
+    format: Option<CoverageFormat>,
+    server: String,
+}
+
+impl CoverageArgs {
+    fn parse(args: &[String]) -> Option<Self> {
+        let mut report = None;
+        let (mut world, mut commit, mut format, mut server) = (None, None, None, None);
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--world" => world = Some(args.next()?.clone()),
+                "--commit" => commit = Some(args.next()?.clone()),
+                "--server" => server = Some(args.next()?.trim_end_matches('/').to_string()),
+                "--format" => {
+                    let name = args.next()?;
+                    format = Some(*FORMATS.iter().find(|f| format_name(**f) == name)?);
+                }
+                path if report.is_none() && !path.starts_with("--") => report = Some(PathBuf::from(path)),
+                _ => return None,
+            }
+        }
+        Some(Self {
+            report: report?,
+            world: world?,
+            commit,
+            format,
+            server: server
+                .or_else(|| env::var("NILSBOHR_SERVER").ok())
+                .unwrap_or_else(|| DEFAULT_SERVER.to_string()),
+        })
+    }
+}
+
+fn coverage(args: CoverageArgs) -> ExitCode {
+    let Ok(token) = env::var("NILSBOHR_TOKEN") else {
+        eprintln!("NILSBOHR_TOKEN is not set");
+        return ExitCode::from(2);
+    };
+    let report = match std::fs::read_to_string(&args.report) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}: {e}", args.report.display());
+            return ExitCode::from(2);
+        }
+    };
+    // Catch malformed reports before uploading them
+    let format = match CoverageReport::parse(&report, args.format) {
+        Ok((format, _)) => format,
+        Err(e) => {
+            eprintln!("{}: {e}", args.report.display());
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut query = vec![("format", format_name(format))];
+    if let Some(commit) = &args.commit {
+        query.push(("commit", commit));
+    }
+    let url = format!("{}/worlds/{}/coverage", args.server, args.world);
+    let upload = async {
+        let response = reqwest::Client::new()
+            .post(&url)
+            .query(&query)
+            .bearer_auth(token)
+            .body(report)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("{status}: {}", response.text().await.unwrap_or_default()));
+        }
+        response.json::<CoverageSummary>().await.map_err(|e| e.to_string())
+    };
+    let result = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime.block_on(upload),
+        Err(e) => Err(e.to_string()),
+    };
+
+    match result {
+        Ok(summary) => {
+            let percent = |p: Option<f32>| p.map(|p| format!("{p:.1}%")).unwrap_or_else(|| "n/a".into());
+            println!(
+                "Imported {:?} coverage onto world {} ({}): {} file(s), {} entities, lines {}, branches {}",
+                summary.format,
+                summary.world_id,
+                summary.commit_hash,
+                summary.files_matched,
+                summary.entities_updated,
+                percent(summary.line_coverage),
+                percent(summary.branch_coverage),
+            );
+            for path in &summary.unmatched_files {
+                println!("not in world: {path}");
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Upload failed: {e}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// The `format` query value the server expects.
+fn format_name(format: CoverageFormat) -> &'static str {
+    match format {
+        CoverageFormat::Lcov => "lcov",
+        CoverageFormat::Cobertura => "cobertura",
+        CoverageFormat::LlvmJson => "llvm_json",
+    }
+}
@@ -0,0 +1,340 @@
+//! Duplicate code detection over Room bodies.
+//!
+//! Each Room's tree-sitter subtree is flattened into a token stream in which
+//! identifiers become `$id` and literals `$lit`, so renamed copies still match.
+//! Rooms with the same stream are exact clones. Near clones share most of
+//! their 5-token shingles: the Jaccard similarity is estimated from MinHash
+//! signatures, and locality-sensitive hashing over signature bands keeps the
+//! comparison close to linear in the number of Rooms.
+//!
+//! Clones are grouped into classes per City, each member a near clone of
+//! every other, and linked by `CodeClone` routes from the class
+//! representative (its lowest id) to every other member.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+use serde_json::{Value, json};
+use tracing::debug;
+use tree_sitter::{Node, Tree};
+
+use crate::models::{CloneClass, CloneKind, GameEntity, Route, RouteType};
+
+/// Rooms with fewer normalised tokens are too small to be worth reporting.
+const MIN_TOKENS: usize = 50;
+const SHINGLE: usize = 5;
+const SIGNATURE_LEN: usize = 32;
+const BAND_ROWS: usize = 4;
+/// Estimated Jaccard similarity above which two Rooms are near clones.
+const NEAR_SIMILARITY: f32 = 0.8;
+
+/// Token kinds normalised to `$lit`, besides anything named `*literal*`.
+const LITERAL_KINDS: &[&str] = &[
+    "number", "integer", "float", "string", "char", "true", "false", "null", "none", "None",
+    "template_string", "raw_string_literal", "concatenated_string",
+];
+
+/// The normalised shape of one Room body.
+#[derive(Debug, Clone)]
+pub struct Fingerprint {
+    pub room_id: String,
+    pub tokens: usize,
+    /// Hash of the whole normalised token stream
+    pub hash: u64,
+    /// MinHash signature over the stream's shingles
+    pub signature: [u64; SIGNATURE_LEN],
+}
+
+impl Fingerprint {
+    fn similarity(&self, other: &Fingerprint) -> f32 {
+        if self.hash == other.hash {
+            return 1.0;
+        }
+        let same = self.signature.iter().zip(&other.signature).filter(|(a, b)| a == b).count();
+        same as f32 / SIGNATURE_LEN as f32
+    }
+}
+
+/// Fingerprint every Room of a parsed file that is large enough to matter.
+pub fn fingerprint_rooms(tree: &Tree, entities: &[GameEntity]) -> Vec<Fingerprint> {
+    let mut out = Vec::new();
+    collect(entities, tree.root_node(), &mut out);
+    out
+}
+
+fn collect(entities: &[GameEntity], root: Node, out: &mut Vec<Fingerprint>) {
+    for entity in entities {
+        match entity {
+            // Copied fixtures and setup in tests are not duplication worth reporting
+            GameEntity::Room { is_test: true, .. } | GameEntity::Building { is_test: true, .. } => {}
+            GameEntity::Room { id, span: Some(span), children, .. } => {
+                if let Some(node) = root.descendant_for_byte_range(span.start_byte as usize, span.end_byte as usize) {
+                    let mut tokens = Vec::new();
+                    normalise(node, &mut tokens);
+                    if tokens.len() >= MIN_TOKENS {
+                        out.push(fingerprint(id, &tokens));
+                    }
+                }
+                collect(children, root, out);
+            }
+            GameEntity::City { children, .. }
+            | GameEntity::District { children, .. }
+            | GameEntity::Building { children, .. }
+            | GameEntity::Room { children, .. } => collect(children, root, out),
+            GameEntity::Artifact { .. } => {}
+        }
+    }
+}
+
+fn normalise<'a>(node: Node<'a>, out: &mut Vec<&'a str>) {
+    let kind = node.kind();
+    if kind.contains("comment") {
+        return;
+    }
+    if kind.contains("literal") || LITERAL_KINDS.contains(&kind) {
+        out.push("$lit");
+    } else if kind.contains("identifier") {
+        out.push("$id");
+    } else if node.child_count() == 0 {
+        out.push(kind);
+    } else {
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            normalise(child, out);
+        }
+    }
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// splitmix64's finaliser, deriving the signature's hash functions from one shingle hash.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+fn fingerprint(room_id: &str, tokens: &[&str]) -> Fingerprint {
+    let mut signature = [u64::MAX; SIGNATURE_LEN];
+    for shingle in tokens.windows(SHINGLE) {
+        let base = hash_of(shingle);
+        for (i, slot) in signature.iter_mut().enumerate() {
+            *slot = (*slot).min(mix(base.wrapping_add((i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15))));
+        }
+    }
+    Fingerprint { room_id: room_id.to_string(), tokens: tokens.len(), hash: hash_of(tokens), signature }
+}
+
+/// Group the fingerprints of one City into clone classes. Candidate pairs are
+/// merged from the most similar down, and a Room only joins a class when it
+/// is a near clone of every member, so a chain of small edits does not link
+/// Rooms that no longer resemble each other.
+pub fn find_clones(city_id: &str, fingerprints: &[Fingerprint]) -> Vec<CloneClass> {
+    let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+    for (i, fp) in fingerprints.iter().enumerate() {
+        buckets.entry((usize::MAX, fp.hash)).or_default().push(i);
+        for (band, rows) in fp.signature.chunks(BAND_ROWS).enumerate() {
+            buckets.entry((band, hash_of(rows))).or_default().push(i);
+        }
+    }
+    let near = |a: usize, b: usize| {
+        let (fa, fb) = (&fingerprints[a], &fingerprints[b]);
+        !nested(&fa.room_id, &fb.room_id) && fa.similarity(fb) >= NEAR_SIMILARITY
+    };
+    let candidates: BTreeSet<(usize, usize)> = buckets
+        .values()
+        .flat_map(|members| members.iter().enumerate().flat_map(move |(n, &a)| members[n + 1..].iter().map(move |&b| (a.min(b), a.max(b)))))
+        .filter(|&(a, b)| a != b && near(a, b))
+        .collect();
+    let mut pairs: Vec<(usize, usize)> = candidates.into_iter().collect();
+    pairs.sort_by(|&(a, b), &(c, d)| fingerprints[c].similarity(&fingerprints[d]).total_cmp(&fingerprints[a].similarity(&fingerprints[b])));
+
+    let mut class_of: Vec<usize> = (0..fingerprints.len()).collect();
+    let mut groups: Vec<Vec<usize>> = (0..fingerprints.len()).map(|i| vec![i]).collect();
+    for (a, b) in pairs {
+        let (ca, cb) = (class_of[a], class_of[b]);
+        if ca == cb || !groups[ca].iter().all(|&x| groups[cb].iter().all(|&y| near(x, y))) {
+            continue;
+        }
+        let moved = std::mem::take(&mut groups[cb]);
+        moved.iter().for_each(|&i| class_of[i] = ca);
+        groups[ca].extend(moved);
+    }
+
+    let mut classes: Vec<CloneClass> = groups
+        .into_iter()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let mut members: Vec<&Fingerprint> = members.into_iter().map(|i| &fingerprints[i]).collect();
+            members.sort_by(|a, b| a.room_id.cmp(&b.room_id));
+            let kind = if members.iter().all(|m| m.hash == members[0].hash) { CloneKind::Exact } else { CloneKind::Near };
+            let similarity = members
+                .iter()
+                .enumerate()
+                .flat_map(|(n, a)| members[n + 1..].iter().map(move |b| a.similarity(b)))
+                .fold(1.0, f32::min);
+            CloneClass {
+                id: String::new(),
+                kind,
+                similarity,
+                tokens: members[0].tokens as u32,
+                members: members.iter().map(|m| m.room_id.clone()).collect(),
+            }
+        })
+        .collect();
+    classes.sort_by(|a, b| a.members[0].cmp(&b.members[0]));
+    for (n, class) in classes.iter_mut().enumerate() {
+        class.id = format!("{city_id}_clone_{n}");
+    }
+    debug!(city = city_id, rooms = fingerprints.len(), classes = classes.len(), "Detected clones");
+    classes
+}
+
+/// A Room and a closure or function nested in it are not copies of each other.
+fn nested(a: &str, b: &str) -> bool {
+    let inside = |outer: &str, inner: &str| inner.strip_prefix(outer).is_some_and(|rest| rest.starts_with("::"));
+    inside(a, b) || inside(b, a)
+}
+
+/// `CodeClone` routes linking each class representative to the other members.
+pub fn clone_routes(classes: &[CloneClass]) -> Vec<Route> {
+    classes
+        .iter()
+        .flat_map(|class| {
+            class.members[1..].iter().map(move |member| Route {
+                id: String::new(),
+                from_id: class.members[0].clone(),
+                to_id: member.clone(),
+                route_type: RouteType::CodeClone,
+                bidirectional: true,
+                metadata: Some(json!({ "clone_class": class.id, "kind": class.kind, "similarity": class.similarity, "tokens": class.tokens })),
+            })
+        })
+        .collect()
+}
+
+/// Record each member's clone class in its metadata.
+pub fn annotate(entities: &mut [GameEntity], classes: &[CloneClass]) {
+    let class_of: HashMap<&str, &CloneClass> = classes.iter().flat_map(|c| c.members.iter().map(move |m| (m.as_str(), c))).collect();
+    if !class_of.is_empty() {
+        annotate_entities(entities, &class_of);
+    }
+}
+
+fn annotate_entities(entities: &mut [GameEntity], class_of: &HashMap<&str, &CloneClass>) {
+    for entity in entities {
+        match entity {
+            GameEntity::Room { id, metadata, children, .. } => {
+                if let Some(class) = class_of.get(id.as_str()) {
+                    let metadata = metadata.get_or_insert_with(HashMap::new);
+                    metadata.insert("clone_class".into(), class.id.clone());
+                    metadata.insert("clone_count".into(), (class.members.len() - 1).to_string());
+                }
+                annotate_entities(children, class_of);
+            }
+            GameEntity::City { children, .. } | GameEntity::District { children, .. } | GameEntity::Building { children, .. } => {
+                annotate_entities(children, class_of)
+            }
+            GameEntity::Artifact { .. } => {}
+        }
+    }
+}
+
+/// Rebuild clone classes from stored `CodeClone` routes.
+pub fn classes_from_routes(routes: &[Route]) -> Vec<CloneClass> {
+    let mut classes: BTreeMap<String, CloneClass> = BTreeMap::new();
+    for route in routes.iter().filter(|r| matches!(r.route_type, RouteType::CodeClone)) {
+        let Some(metadata) = &route.metadata else { continue };
+        let Some(id) = metadata.get("clone_class").and_then(Value::as_str) else { continue };
+        let class = classes.entry(id.to_string()).or_insert_with(|| CloneClass {
+            id: id.to_string(),
+            kind: serde_json::from_value(metadata["kind"].clone()).unwrap_or(CloneKind::Near),
+            similarity: metadata.get("similarity").and_then(Value::as_f64).unwrap_or_default() as f32,
+            tokens: metadata.get("tokens").and_then(Value::as_u64).unwrap_or_default() as u32,
+            members: vec![route.from_id.clone()],
+        });
+        class.members.push(route.to_id.clone());
+    }
+    classes.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::registry;
+    use crate::testing;
+
+    fn rooms(source: &str, file: &str) -> Vec<Fingerprint> {
+        let tree = registry::parse_tree("py", source).unwrap();
+        let (mut entities, _) = registry::parse_with_tree("py", &tree, source, file).unwrap();
+        testing::annotate_tests("py", &tree, source, file, &mut entities);
+        fingerprint_rooms(&tree, &entities)
+    }
+
+    #[test]
+    fn test_exact_and_near_clones() {
+        let original = r#"
+def total_price(items, discount):
+    total = 0
+    for item in items:
+        if item.quantity > 0:
+            total += item.price * item.quantity
+        else:
+            print("skipping", item.name)
+    if discount:
+        total = total * (1 - discount)
+    return round(total, 2)
+"#;
+        // Renamed identifiers and changed literals: still an exact clone
+        let renamed = original.replace("total_price", "sum_cost").replace("items", "lines").replace("\"skipping\"", "\"ignored\"");
+        // One extra statement: a near clone
+        let edited = original.replace("    return round", "    log(total)\n    return round");
+        let other = "def greet(name):\n    return 'hi ' + name\n";
+
+        let mut fingerprints = rooms(original, "a.py");
+        fingerprints.extend(rooms(&renamed, "b.py"));
+        fingerprints.extend(rooms(&edited, "c.py"));
+        fingerprints.extend(rooms(other, "d.py"));
+        fingerprints.extend(rooms(original, "tests/test_a.py"));
+        assert_eq!(fingerprints.len(), 3, "tiny functions and tests are not fingerprinted");
+        assert_eq!(fingerprints[0].hash, fingerprints[1].hash);
+        assert_ne!(fingerprints[0].hash, fingerprints[2].hash);
+
+        let classes = find_clones("city_py", &fingerprints);
+        assert_eq!(classes.len(), 1);
+        assert_eq!(classes[0].members, ["a.py::total_price", "b.py::sum_cost", "c.py::total_price"]);
+        assert_eq!(classes[0].kind, CloneKind::Near);
+        assert!(classes[0].similarity >= NEAR_SIMILARITY && classes[0].similarity < 1.0);
+
+        let routes = clone_routes(&classes);
+        assert_eq!(routes.len(), 2);
+        let rebuilt = classes_from_routes(&routes);
+        assert_eq!(rebuilt[0].members, classes[0].members);
+    }
+
+    #[test]
+    fn test_near_clones_do_not_chain() {
+        let signature = |changed: &[u64]| {
+            let mut signature: [u64; SIGNATURE_LEN] = std::array::from_fn(|i| i as u64);
+            changed.iter().for_each(|&i| signature[i as usize] += 100);
+            signature
+        };
+        let fingerprint = |room_id: &str, hash, changed: &[u64]| Fingerprint { room_id: room_id.into(), tokens: 60, hash, signature: signature(changed) };
+        // b shares 26 of 32 slots with a and with c, but a and c only share 20
+        let fingerprints = [
+            fingerprint("a.py::f", 1, &[]),
+            fingerprint("b.py::f", 2, &[0, 1, 2, 3, 4, 5]),
+            fingerprint("c.py::f", 3, &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]),
+        ];
+
+        let classes = find_clones("city_py", &fingerprints);
+        assert_eq!(classes.len(), 1);
+        assert_eq!(classes[0].members, ["a.py::f", "b.py::f"]);
+        assert_eq!(classes[0].similarity, 26.0 / 32.0);
+    }
+}
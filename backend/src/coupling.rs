@@ -0,0 +1,258 @@
+//! Robert Martin's package coupling metrics per file Building and District,
+//! plus the modularity of the file graph.
+
+use std::collections::{HashMap, HashSet};
+
+use tracing::debug;
+
+use crate::hierarchy::{self, Owners};
+use crate::models::{GameEntity, Route, RouteType};
+
+/// Repo-level architecture numbers, aggregated into `WorldMeta`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CouplingSummary {
+    /// Newman modularity of the file dependency graph, partitioned by District (-0.5..1).
+    pub modularity: f32,
+    /// Mean distance from the main sequence over Districts (0 is ideal).
+    pub main_sequence_distance: f32,
+}
+
+/// Dependencies and types of one file or District.
+#[derive(Default)]
+struct Unit {
+    afferent: HashSet<String>,
+    efferent: HashSet<String>,
+    types: u32,
+    abstract_types: u32,
+}
+
+impl Unit {
+    fn instability(&self) -> f32 {
+        let total = self.afferent.len() + self.efferent.len();
+        if total == 0 { 0.0 } else { self.efferent.len() as f32 / total as f32 }
+    }
+
+    fn abstractness(&self) -> f32 {
+        if self.types == 0 { 0.0 } else { self.abstract_types as f32 / self.types as f32 }
+    }
+
+    fn distance(&self) -> f32 {
+        (self.abstractness() + self.instability() - 1.0).abs()
+    }
+}
+
+/// Compute Robert Martin's package metrics for every file Building and
+/// District: afferent/efferent coupling (distinct units depending on it / it
+/// depends on), instability, abstractness and distance from the main
+/// sequence. They are written to the entity's metadata; the repo-wide
+/// summary is returned. Dependencies from or to test code are left out.
+pub fn annotate_coupling(cities: &mut [GameEntity], routes: &[Route]) -> CouplingSummary {
+    let owners = hierarchy::index_owners(cities);
+    let mut tests = HashSet::new();
+    cities.iter().for_each(|city| collect_tests(city, false, &mut tests));
+    let mut units: HashMap<String, Unit> = HashMap::new();
+    let mut file_edges: HashSet<(&str, &str)> = HashSet::new();
+
+    let dependencies = routes
+        .iter()
+        .filter(|r| is_dependency(&r.route_type) && !tests.contains(r.from_id.as_str()) && !tests.contains(r.to_id.as_str()));
+    for route in dependencies {
+        let (Some(from), Some(to)) = (owners.get(&route.from_id), owners.get(&route.to_id)) else { continue };
+        for (a, b) in [(&from.file, &to.file), (&from.district, &to.district)] {
+            if let (Some(a), Some(b)) = (a, b)
+                && a != b
+            {
+                units.entry(a.clone()).or_default().efferent.insert(b.clone());
+                units.entry(b.clone()).or_default().afferent.insert(a.clone());
+            }
+        }
+        if let (Some(a), Some(b)) = (from.file.as_deref(), to.file.as_deref())
+            && a != b
+        {
+            file_edges.insert(if a < b { (a, b) } else { (b, a) });
+        }
+    }
+
+    for city in cities.iter() {
+        count_types(city, &owners, &mut units);
+    }
+
+    let districts: Vec<&Unit> = owners
+        .iter()
+        .filter(|(id, o)| o.district.as_deref() == Some(id.as_str()))
+        .filter_map(|(id, _)| units.get(id))
+        .collect();
+    let main_sequence_distance =
+        if districts.is_empty() { 0.0 } else { districts.iter().map(|u| u.distance()).sum::<f32>() / districts.len() as f32 };
+    let summary = CouplingSummary { modularity: modularity(&file_edges, &owners), main_sequence_distance };
+
+    for city in cities.iter_mut() {
+        annotate(city, &owners, &units);
+    }
+    debug!(units = units.len(), modularity = summary.modularity, "Computed coupling metrics");
+    summary
+}
+
+/// Route types through which one module depends on another.
+fn is_dependency(route_type: &RouteType) -> bool {
+    matches!(route_type, RouteType::Import | RouteType::FunctionCall | RouteType::Inheritance | RouteType::TypeReference)
+}
+
+/// Ids of test Buildings and Rooms, including everything inside them.
+fn collect_tests<'a>(entity: &'a GameEntity, in_test: bool, tests: &mut HashSet<&'a str>) {
+    let (children, in_test) = match entity {
+        GameEntity::Building { id, is_test, children, .. } | GameEntity::Room { id, is_test, children, .. } => {
+            let in_test = in_test || *is_test;
+            if in_test {
+                tests.insert(id);
+            }
+            (children, in_test)
+        }
+        GameEntity::City { children, .. } | GameEntity::District { children, .. } => (children, in_test),
+        GameEntity::Artifact { .. } => return,
+    };
+    children.iter().for_each(|child| collect_tests(child, in_test, tests));
+}
+
+fn is_abstract(building_type: &str, supertypes: &[String], metadata: Option<&HashMap<String, String>>) -> bool {
+    matches!(building_type, "trait" | "interface")
+        || metadata.and_then(|m| m.get("abstract")).is_some_and(|v| v == "true")
+        // Python abstract base classes and protocols
+        || supertypes.iter().any(|s| matches!(s.rsplit('.').next(), Some("ABC" | "Protocol")) || s.contains("ABCMeta"))
+}
+
+fn count_types(entity: &GameEntity, owners: &HashMap<String, Owners>, units: &mut HashMap<String, Unit>) {
+    let children = match entity {
+        GameEntity::Building { is_test: true, .. } | GameEntity::Room { is_test: true, .. } => return,
+        GameEntity::Building { id, building_type, supertypes, metadata, children, .. } => {
+            if !matches!(building_type.as_str(), "file" | "impl")
+                && let Some(owner) = owners.get(id)
+            {
+                let is_abstract = is_abstract(building_type, supertypes, metadata.as_ref());
+                for unit in owner.file.iter().chain(&owner.district) {
+                    let unit = units.entry(unit.clone()).or_default();
+                    unit.types += 1;
+                    unit.abstract_types += u32::from(is_abstract);
+                }
+            }
+            children
+        }
+        GameEntity::City { children, .. } | GameEntity::District { children, .. } | GameEntity::Room { children, .. } => children,
+        GameEntity::Artifact { .. } => return,
+    };
+    for child in children {
+        count_types(child, owners, units);
+    }
+}
+
+/// Newman's Q over the undirected file graph, with each District a community.
+fn modularity(edges: &HashSet<(&str, &str)>, owners: &HashMap<String, Owners>) -> f32 {
+    if edges.is_empty() {
+        return 0.0;
+    }
+    let community = |file: &str| owners.get(file).and_then(|o| o.district.as_deref());
+    let mut internal: HashMap<Option<&str>, f32> = HashMap::new();
+    let mut degree: HashMap<Option<&str>, f32> = HashMap::new();
+    for &(a, b) in edges {
+        let (ca, cb) = (community(a), community(b));
+        *degree.entry(ca).or_default() += 1.0;
+        *degree.entry(cb).or_default() += 1.0;
+        if ca == cb {
+            *internal.entry(ca).or_default() += 1.0;
+        }
+    }
+    let m = edges.len() as f32;
+    degree
+        .iter()
+        .map(|(c, d)| internal.get(c).copied().unwrap_or(0.0) / m - (d / (2.0 * m)).powi(2))
+        .sum()
+}
+
+fn annotate(entity: &mut GameEntity, owners: &HashMap<String, Owners>, units: &HashMap<String, Unit>) {
+    let (id, metadata, children) = match entity {
+        GameEntity::District { id, metadata, children, .. } => (id, metadata, children),
+        GameEntity::Building { id, building_type, metadata, children, .. } if building_type == "file" => (id, metadata, children),
+        GameEntity::City { children, .. } => {
+            for child in children {
+                annotate(child, owners, units);
+            }
+            return;
+        }
+        _ => return,
+    };
+    let is_unit = owners.get(id.as_str()).is_some_and(|o| o.file.as_ref() == Some(id) || o.district.as_ref() == Some(id));
+    if is_unit {
+        let empty = Unit::default();
+        let unit = units.get(id.as_str()).unwrap_or(&empty);
+        let metadata = metadata.get_or_insert_with(HashMap::new);
+        metadata.insert("afferent_coupling".into(), unit.afferent.len().to_string());
+        metadata.insert("efferent_coupling".into(), unit.efferent.len().to_string());
+        metadata.insert("instability".into(), format!("{:.2}", unit.instability()));
+        metadata.insert("abstractness".into(), format!("{:.2}", unit.abstractness()));
+        metadata.insert("main_sequence_distance".into(), format!("{:.2}", unit.distance()));
+    }
+    for child in children {
+        annotate(child, owners, units);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{city, district};
+
+    fn building(id: &str, building_type: &str, children: Vec<GameEntity>) -> GameEntity {
+        GameEntity::Building { id: id.into(), name: id.into(), building_type: building_type.into(), is_public: true, is_test: false, loc: 1, imports: vec![], supertypes: vec![], children, span: None, metadata: None }
+    }
+
+    fn test_file(id: &str, children: Vec<GameEntity>) -> GameEntity {
+        GameEntity::Building { id: id.into(), name: id.into(), building_type: "file".into(), is_public: true, is_test: true, loc: 1, imports: vec![], supertypes: vec![], children, span: None, metadata: None }
+    }
+
+    fn route(from: &str, to: &str, route_type: RouteType) -> Route {
+        Route { id: format!("{from}->{to}"), from_id: from.into(), to_id: to.into(), route_type, bidirectional: false, metadata: None }
+    }
+
+    fn metric<'a>(entity: &'a GameEntity, key: &str) -> &'a str {
+        match entity {
+            GameEntity::District { metadata, .. } | GameEntity::Building { metadata, .. } => metadata.as_ref().unwrap()[key].as_str(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_metrics_per_file_and_district() {
+        let mut cities = vec![city("rs", vec![
+            district("api", vec![building("api/a.rs", "file", vec![building("api/a.rs::Store", "trait", vec![])])]),
+            district("app", vec![
+                building("app/b.rs", "file", vec![building("app/b.rs::Db", "struct", vec![])]),
+                building("app/c.rs", "file", vec![]),
+                test_file("app/c_test.rs", vec![building("app/c_test.rs::Fake", "struct", vec![])]),
+            ]),
+        ])];
+        let routes = vec![
+            route("app/b.rs::Db", "api/a.rs::Store", RouteType::Inheritance),
+            route("app/c.rs", "app/b.rs::Db", RouteType::Import),
+            route("app/c.rs", "api/a.rs", RouteType::Import),
+            route("api/a.rs", "std::fmt", RouteType::Import),
+            // Test code depends on what it covers without coupling it
+            route("app/c_test.rs", "api/a.rs", RouteType::Import),
+            route("app/c_test.rs::Fake", "api/a.rs::Store", RouteType::Inheritance),
+        ];
+
+        let summary = annotate_coupling(&mut cities, &routes);
+        let GameEntity::City { children, .. } = &cities[0] else { unreachable!() };
+        let (api, app) = (&children[0], &children[1]);
+        assert_eq!([metric(api, "afferent_coupling"), metric(api, "efferent_coupling"), metric(api, "instability"), metric(api, "abstractness")], ["1", "0", "0.00", "1.00"]);
+        assert_eq!(metric(api, "main_sequence_distance"), "0.00");
+        let GameEntity::District { children: api_files, .. } = api else { unreachable!() };
+        assert_eq!(metric(&api_files[0], "afferent_coupling"), "2");
+        assert_eq!([metric(app, "efferent_coupling"), metric(app, "instability"), metric(app, "main_sequence_distance")], ["1", "1.00", "0.00"]);
+
+        let GameEntity::District { children: app_files, .. } = app else { unreachable!() };
+        assert_eq!([metric(&app_files[1], "efferent_coupling"), metric(&app_files[1], "afferent_coupling")], ["2", "0"]);
+        assert_eq!(summary.main_sequence_distance, 0.0);
+        // Edges a-b, a-c, b-c: one of three internal to district_app
+        assert!((summary.modularity - (1.0 / 3.0 - (4.0f32 / 6.0).powi(2) - (2.0f32 / 6.0).powi(2))).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,522 @@
+//! Code coverage reports mapped onto the world.
+//!
+//! Reads lcov (`lcov.info`), Cobertura XML and `llvm-cov export` JSON, matches
+//! the files they cover to the world's file Buildings by path suffix, and
+//! writes line and branch coverage into the metadata of every Room and
+//! Building with instrumented lines, and of every District and City:
+//! `line_coverage` (percent), `lines_covered`, `lines_total`, and when the
+//! report has branch data `branch_coverage`, `branches_covered` and
+//! `branches_total`. Entities in files the report does not mention get no
+//! coverage keys: unknown is not the same as untested.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::models::{CoverageFormat, GameEntity, SourceSpan};
+
+/// Metadata keys written by [`annotate`].
+pub const METADATA_KEYS: &[&str] =
+    &["line_coverage", "lines_covered", "lines_total", "branch_coverage", "branches_covered", "branches_total"];
+
+/// Most branches a single line may report. Cobertura gives only a count per
+/// line, so a larger one is treated as a malformed report rather than expanded.
+const MAX_BRANCHES_PER_LINE: u32 = 1024;
+
+/// Guess a report's format from its contents.
+pub fn detect_format(report: &str) -> Option<CoverageFormat> {
+    let head = report.trim_start();
+    if head.starts_with('{') {
+        Some(CoverageFormat::LlvmJson)
+    } else if head.starts_with('<') && report.contains("<coverage") {
+        Some(CoverageFormat::Cobertura)
+    } else if report.lines().any(|l| l.starts_with("SF:")) {
+        Some(CoverageFormat::Lcov)
+    } else {
+        None
+    }
+}
+
+/// Coverage of one source file.
+#[derive(Debug, Default, Clone)]
+pub struct FileCoverage {
+    /// Hit count per instrumented line
+    pub lines: BTreeMap<u32, u64>,
+    /// Whether each branch was taken, keyed by line and a per-line branch id
+    pub branches: BTreeMap<(u32, u32), bool>,
+}
+
+impl FileCoverage {
+    fn hit_line(&mut self, line: u32, hits: u64) {
+        *self.lines.entry(line).or_default() += hits;
+    }
+
+    fn branch(&mut self, line: u32, id: u32, taken: bool) {
+        *self.branches.entry((line, id)).or_default() |= taken;
+    }
+
+    /// Adds the hits and taken branches of another report entry for the same file.
+    fn merge(&mut self, other: &FileCoverage) {
+        for (&line, &hits) in &other.lines {
+            self.hit_line(line, hits);
+        }
+        for (&(line, id), &taken) in &other.branches {
+            self.branch(line, id, taken);
+        }
+    }
+
+    /// Counts over the lines of `span`.
+    fn within(&self, span: &SourceSpan) -> Counts {
+        let mut counts = Counts::default();
+        for (_, hits) in self.lines.range(span.start_line..=span.end_line) {
+            counts.lines_total += 1;
+            counts.lines_covered += u32::from(*hits > 0);
+        }
+        for (_, taken) in self.branches.range((span.start_line, 0)..=(span.end_line, u32::MAX)) {
+            counts.branches_total += 1;
+            counts.branches_covered += u32::from(*taken);
+        }
+        counts
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct Counts {
+    pub lines_covered: u32,
+    pub lines_total: u32,
+    pub branches_covered: u32,
+    pub branches_total: u32,
+}
+
+impl Counts {
+    fn add(&mut self, other: Counts) {
+        self.lines_covered += other.lines_covered;
+        self.lines_total += other.lines_total;
+        self.branches_covered += other.branches_covered;
+        self.branches_total += other.branches_total;
+    }
+
+    pub fn line_coverage(&self) -> Option<f32> {
+        (self.lines_total > 0).then(|| 100.0 * self.lines_covered as f32 / self.lines_total as f32)
+    }
+
+    pub fn branch_coverage(&self) -> Option<f32> {
+        (self.branches_total > 0).then(|| 100.0 * self.branches_covered as f32 / self.branches_total as f32)
+    }
+
+    fn write(&self, metadata: &mut Option<HashMap<String, String>>) {
+        let Some(line_coverage) = self.line_coverage() else { return };
+        let metadata = metadata.get_or_insert_with(HashMap::new);
+        metadata.insert("line_coverage".into(), format!("{line_coverage:.1}"));
+        metadata.insert("lines_covered".into(), self.lines_covered.to_string());
+        metadata.insert("lines_total".into(), self.lines_total.to_string());
+        if let Some(branch_coverage) = self.branch_coverage() {
+            metadata.insert("branch_coverage".into(), format!("{branch_coverage:.1}"));
+            metadata.insert("branches_covered".into(), self.branches_covered.to_string());
+            metadata.insert("branches_total".into(), self.branches_total.to_string());
+        }
+    }
+}
+
+/// A parsed coverage report, keyed by file path as written in the report.
+#[derive(Debug, Default)]
+pub struct CoverageReport {
+    pub files: HashMap<String, FileCoverage>,
+}
+
+impl CoverageReport {
+    /// Parse a report, detecting its format unless given.
+    pub fn parse(report: &str, format: Option<CoverageFormat>) -> Result<(CoverageFormat, Self), String> {
+        let format = format
+            .or_else(|| detect_format(report))
+            .ok_or("Unrecognised coverage report: expected lcov, Cobertura XML or llvm-cov JSON")?;
+        let parsed = match format {
+            CoverageFormat::Lcov => parse_lcov(report),
+            CoverageFormat::Cobertura => parse_cobertura(report),
+            CoverageFormat::LlvmJson => parse_llvm_json(report),
+        }?;
+        if parsed.files.is_empty() {
+            return Err("Coverage report covers no files".into());
+        }
+        Ok((format, parsed))
+    }
+
+    fn file(&mut self, path: &str) -> &mut FileCoverage {
+        let path = path.trim().replace('\\', "/");
+        let path = path.trim_start_matches("./").to_string();
+        self.files.entry(path).or_default()
+    }
+}
+
+// --- lcov ---
+
+fn parse_lcov(report: &str) -> Result<CoverageReport, String> {
+    let mut parsed = CoverageReport::default();
+    let mut current: Option<String> = None;
+    for (n, line) in report.lines().enumerate() {
+        let line = line.trim();
+        let bad = || format!("lcov line {}: malformed `{line}`", n + 1);
+        if let Some(path) = line.strip_prefix("SF:") {
+            current = Some(path.to_string());
+        } else if line == "end_of_record" {
+            current = None;
+        } else if let Some(path) = &current {
+            if let Some(data) = line.strip_prefix("DA:") {
+                let mut fields = data.split(',');
+                let line_no = fields.next().and_then(|f| f.parse().ok()).ok_or_else(bad)?;
+                let hits = fields.next().and_then(|f| f.parse().ok()).ok_or_else(bad)?;
+                parsed.file(path).hit_line(line_no, hits);
+            } else if let Some(data) = line.strip_prefix("BRDA:") {
+                let fields: Vec<&str> = data.split(',').collect();
+                let [line_no, block, branch, taken] = fields[..] else { return Err(bad()) };
+                let line_no = line_no.parse().map_err(|_| bad())?;
+                // Branch ids only need to be unique within a line
+                let id = block.parse::<u32>().unwrap_or(0).wrapping_mul(MAX_BRANCHES_PER_LINE).wrapping_add(branch.parse().unwrap_or(0));
+                parsed.file(path).branch(line_no, id, taken != "-" && taken != "0");
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+// --- Cobertura ---
+
+/// `(name, attributes)` of every start or empty-element tag, in order.
+fn xml_tags(xml: &str) -> impl Iterator<Item = (&str, HashMap<&str, &str>)> {
+    xml.split('<').skip(1).filter_map(|chunk| {
+        let tag = chunk.split('>').next()?;
+        if tag.starts_with(['/', '?', '!']) {
+            return None;
+        }
+        let tag = tag.trim_end_matches('/');
+        let (name, mut rest) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        let mut attributes = HashMap::new();
+        while let Some((key, after)) = rest.split_once('=') {
+            let after = after.trim_start();
+            let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else { break };
+            let Some((value, tail)) = after[1..].split_once(quote) else { break };
+            attributes.insert(key.trim(), value);
+            rest = tail;
+        }
+        Some((name, attributes))
+    })
+}
+
+fn parse_cobertura(report: &str) -> Result<CoverageReport, String> {
+    let mut parsed = CoverageReport::default();
+    let mut current: Option<String> = None;
+    for (name, attributes) in xml_tags(report) {
+        match name {
+            "class" => current = attributes.get("filename").map(|f| f.to_string()),
+            "line" => {
+                let Some(path) = &current else { continue };
+                let number = attributes.get("number").and_then(|n| n.parse::<u32>().ok());
+                let hits = attributes.get("hits").and_then(|h| h.parse::<u64>().ok());
+                let (Some(number), Some(hits)) = (number, hits) else {
+                    return Err(format!("Cobertura line in {path} has no number or hits"));
+                };
+                let file = parsed.file(path);
+                // Lines are listed under both the class and its methods
+                let entry = file.lines.entry(number).or_default();
+                *entry = (*entry).max(hits);
+                // condition-coverage="50% (1/2)"
+                if let Some((taken, total)) = attributes
+                    .get("condition-coverage")
+                    .and_then(|c| c.split_once('('))
+                    .and_then(|(_, counts)| counts.trim_end_matches(')').split_once('/'))
+                {
+                    let (taken, total) = (taken.trim().parse().unwrap_or(0), total.trim().parse().unwrap_or(0));
+                    if total > MAX_BRANCHES_PER_LINE {
+                        return Err(format!("Cobertura line {number} in {path} claims {total} branches"));
+                    }
+                    for id in 0..total {
+                        file.branch(number, id, id < taken);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(parsed)
+}
+
+// --- llvm-cov export ---
+
+fn parse_llvm_json(report: &str) -> Result<CoverageReport, String> {
+    let json: Value = serde_json::from_str(report).map_err(|e| format!("llvm-cov JSON: {e}"))?;
+    let data = json.get("data").and_then(Value::as_array).ok_or("llvm-cov JSON has no `data`")?;
+    let mut parsed = CoverageReport::default();
+    for file in data.iter().filter_map(|d| d.get("files")?.as_array()).flatten() {
+        let Some(path) = file.get("filename").and_then(Value::as_str) else { continue };
+        let coverage = parsed.file(path);
+
+        let segments: Vec<Segment> = file.get("segments").and_then(Value::as_array).into_iter().flatten().filter_map(Segment::from_json).collect();
+        for (line, hits) in line_hits(&segments) {
+            coverage.hit_line(line, hits);
+        }
+
+        // [line_start, col_start, line_end, col_end, true_count, false_count, ...]
+        for branch in file.get("branches").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_array) {
+            let number = |i: usize| branch.get(i).and_then(Value::as_u64).unwrap_or(0);
+            let (line, column) = (number(0) as u32, number(1) as u32);
+            coverage.branch(line, column * 2, number(4) > 0);
+            coverage.branch(line, column * 2 + 1, number(5) > 0);
+        }
+    }
+    Ok(parsed)
+}
+
+/// `[line, column, count, has_count, is_region_entry, is_gap_region]`
+struct Segment {
+    line: u32,
+    count: u64,
+    has_count: bool,
+    is_region_entry: bool,
+    is_gap: bool,
+}
+
+impl Segment {
+    fn from_json(value: &Value) -> Option<Self> {
+        let fields = value.as_array()?;
+        Some(Self {
+            line: fields.first()?.as_u64()? as u32,
+            count: fields.get(2)?.as_u64()?,
+            has_count: fields.get(3)?.as_bool()?,
+            is_region_entry: fields.get(4)?.as_bool()?,
+            is_gap: fields.get(5).and_then(Value::as_bool).unwrap_or(false),
+        })
+    }
+}
+
+/// Per-line hit counts from code region segments, following llvm-cov: a
+/// line is instrumented when a counted region starts on it or continues
+/// into it, and its count is the highest of those regions.
+fn line_hits(segments: &[Segment]) -> Vec<(u32, u64)> {
+    let (Some(first), Some(last)) = (segments.first(), segments.last()) else { return Vec::new() };
+    let mut hits = Vec::new();
+    let mut wrapped: Option<&Segment> = None;
+    let mut next = 0;
+    for line in first.line..=last.line {
+        let start = next;
+        while next < segments.len() && segments[next].line == line {
+            next += 1;
+        }
+        let on_line = &segments[start..next];
+        let counts = wrapped
+            .filter(|w| w.has_count && !w.is_gap)
+            .into_iter()
+            .chain(on_line.iter().filter(|s| s.has_count && s.is_region_entry))
+            .map(|s| s.count);
+        if let Some(count) = counts.max() {
+            hits.push((line, count));
+        }
+        if let Some(last_on_line) = on_line.last() {
+            wrapped = Some(last_on_line);
+        }
+    }
+    hits
+}
+
+// --- Mapping onto the world ---
+
+/// Pairs each file Building id with the report entries covering it. A report
+/// path matches the id equal to it; failing that, the longest id it ends with
+/// as `/<id>` (absolute paths); failing that, the one id ending with `/<path>`
+/// (paths relative to a subdirectory). A relative path that several ids end
+/// with is ambiguous and left unmatched. Entries matching the same file are
+/// merged. Returns the matches and the report paths no file matched.
+pub fn match_files(report: &CoverageReport, file_ids: &[String]) -> (HashMap<String, FileCoverage>, Vec<String>) {
+    let mut matched: HashMap<String, FileCoverage> = HashMap::new();
+    let mut unmatched = Vec::new();
+    for (path, coverage) in &report.files {
+        match match_file(path, file_ids) {
+            Some(id) => matched.entry(id.clone()).or_default().merge(coverage),
+            None => unmatched.push(path.clone()),
+        }
+    }
+    unmatched.sort();
+    (matched, unmatched)
+}
+
+fn match_file<'a>(path: &str, file_ids: &'a [String]) -> Option<&'a String> {
+    if let Some(id) = file_ids.iter().find(|id| *id == path) {
+        return Some(id);
+    }
+    if let Some(id) = file_ids.iter().filter(|id| path.ends_with(&format!("/{id}"))).max_by_key(|id| id.len()) {
+        return Some(id);
+    }
+    let mut within = file_ids.iter().filter(|id| id.ends_with(&format!("/{path}")));
+    match (within.next(), within.next()) {
+        (Some(id), None) => Some(id),
+        _ => None,
+    }
+}
+
+/// Ids of every file Building in the world.
+pub fn file_ids(cities: &[GameEntity]) -> Vec<String> {
+    fn collect(entity: &GameEntity, out: &mut Vec<String>) {
+        match entity {
+            GameEntity::Building { id, building_type, .. } if building_type == "file" => out.push(id.clone()),
+            GameEntity::City { children, .. } | GameEntity::District { children, .. } => children.iter().for_each(|c| collect(c, out)),
+            _ => {}
+        }
+    }
+    let mut out = Vec::new();
+    cities.iter().for_each(|c| collect(c, &mut out));
+    out
+}
+
+/// Write coverage into the metadata of the world's entities and return the
+/// totals over all matched files.
+pub fn annotate(cities: &mut [GameEntity], files: &HashMap<String, FileCoverage>) -> Counts {
+    let mut total = Counts::default();
+    for city in cities {
+        total.add(annotate_entity(city, files));
+    }
+    total
+}
+
+/// Coverage metadata of every annotated entity, by entity id.
+pub fn collect(cities: &[GameEntity]) -> Vec<(String, HashMap<String, String>)> {
+    fn walk(entity: &GameEntity, out: &mut Vec<(String, HashMap<String, String>)>) {
+        let (id, children, metadata) = match entity {
+            GameEntity::City { id, children, metadata, .. }
+            | GameEntity::District { id, children, metadata, .. }
+            | GameEntity::Building { id, children, metadata, .. }
+            | GameEntity::Room { id, children, metadata, .. } => (id, children, metadata),
+            GameEntity::Artifact { .. } => return,
+        };
+        let coverage: HashMap<String, String> = metadata
+            .iter()
+            .flatten()
+            .filter(|(key, _)| METADATA_KEYS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        if !coverage.is_empty() {
+            out.push((id.clone(), coverage));
+        }
+        children.iter().for_each(|c| walk(c, out));
+    }
+    let mut out = Vec::new();
+    cities.iter().for_each(|c| walk(c, &mut out));
+    out
+}
+
+/// Annotates `entity` and its children; returns what it adds to its District and City totals.
+fn annotate_entity(entity: &mut GameEntity, files: &HashMap<String, FileCoverage>) -> Counts {
+    match entity {
+        GameEntity::City { children, metadata, .. } | GameEntity::District { children, metadata, .. } => {
+            let mut counts = Counts::default();
+            for child in children {
+                counts.add(annotate_entity(child, files));
+            }
+            counts.write(metadata);
+            counts
+        }
+        GameEntity::Building { building_type, span, children, metadata, .. } | GameEntity::Room { room_type: building_type, span, children, metadata, .. } => {
+            let coverage = span.as_ref().and_then(|s| Some((s, files.get(&s.file)?)));
+            let Some((span, coverage)) = coverage else { return Counts::default() };
+            let counts = coverage.within(span);
+            counts.write(metadata);
+            for child in children {
+                annotate_entity(child, files);
+            }
+            if building_type == "file" { counts } else { Counts::default() }
+        }
+        GameEntity::Artifact { .. } => Counts::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::registry;
+    use crate::models::CoverageFormat;
+
+    const SOURCE: &str = "def check(x):\n    if x > 0:\n        return 1\n    return 0\n\n\ndef unused():\n    return 2\n";
+
+    fn world() -> Vec<GameEntity> {
+        let (children, _) = registry::parse_by_extension("py", SOURCE, "pkg/check.py").unwrap();
+        let file = GameEntity::Building {
+            id: "pkg/check.py".into(), name: "check.py".into(), building_type: "file".into(), is_public: true, is_test: false, loc: 8,
+            imports: vec![], supertypes: vec![], children, span: Some(SourceSpan::whole_file("pkg/check.py", SOURCE)), metadata: None,
+        };
+        vec![GameEntity::District { id: "district_pkg".into(), name: "pkg".into(), path: "pkg".into(), children: vec![file], metadata: None }]
+    }
+
+    fn metadata<'a>(entity: &'a GameEntity, key: &str) -> Option<&'a str> {
+        match entity {
+            GameEntity::District { metadata, .. } | GameEntity::Building { metadata, .. } | GameEntity::Room { metadata, .. } => {
+                metadata.as_ref()?.get(key).map(String::as_str)
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_reports_in_each_format_map_onto_rooms() {
+        let lcov = "TN:\nSF:/ci/build/pkg/check.py\nDA:1,1\nDA:2,1\nDA:3,1\nDA:4,0\nDA:7,1\nDA:8,0\nBRDA:2,0,0,1\nBRDA:2,0,1,-\nend_of_record\n";
+        let cobertura = r#"<?xml version="1.0" ?>
+<coverage line-rate="0.66"><packages><package name="pkg"><classes>
+<class name="check.py" filename="pkg/check.py"><lines>
+<line number="1" hits="1"/><line number="2" hits="1" branch="true" condition-coverage="50% (1/2)"/>
+<line number="3" hits="1"/><line number="4" hits="0"/><line number="7" hits="1"/><line number="8" hits="0"/>
+</lines></class></classes></package></packages></coverage>"#;
+        let llvm = r#"{"data": [{"files": [{"filename": "pkg/check.py",
+            "segments": [[1, 1, 1, true, true, false], [3, 9, 1, true, true, false], [3, 17, 0, false, false, false], [4, 5, 0, true, true, false], [4, 13, 0, false, false, false],
+                         [7, 1, 1, true, true, false], [7, 16, 0, false, false, false], [8, 5, 0, true, true, false], [8, 13, 0, false, false, false]],
+            "branches": [[2, 8, 2, 13, 1, 0, 0, 0, 4]]}]}]}"#;
+
+        for (report, expected) in [(lcov, CoverageFormat::Lcov), (cobertura, CoverageFormat::Cobertura), (llvm, CoverageFormat::LlvmJson)] {
+            let (format, parsed) = CoverageReport::parse(report, None).unwrap();
+            assert_eq!(format, expected);
+
+            let mut cities = world();
+            let (files, unmatched) = match_files(&parsed, &file_ids(&cities));
+            assert!(unmatched.is_empty(), "{format:?}: {unmatched:?}");
+            let total = annotate(&mut cities, &files);
+            assert_eq!((total.lines_covered, total.lines_total, total.branches_covered, total.branches_total), (4, 6, 1, 2), "{format:?}");
+
+            let GameEntity::District { children, .. } = &cities[0] else { unreachable!() };
+            let GameEntity::Building { children: rooms, .. } = &children[0] else { unreachable!() };
+            assert_eq!(metadata(&cities[0], "line_coverage"), Some("66.7"));
+            assert_eq!(metadata(&rooms[0], "line_coverage"), Some("75.0"), "{format:?}");
+            assert_eq!(metadata(&rooms[0], "branch_coverage"), Some("50.0"), "{format:?}");
+            assert_eq!(metadata(&rooms[1], "lines_covered"), Some("1"), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_rejects_implausible_cobertura_branch_counts() {
+        let cobertura = r#"<coverage><class filename="pkg/check.py"><line number="2" hits="1" condition-coverage="0% (0/4000000000)"/></class></coverage>"#;
+        assert!(CoverageReport::parse(cobertura, None).is_err());
+    }
+
+    #[test]
+    fn test_report_paths_prefer_exact_matches_and_skip_ambiguous_ones() {
+        let ids: Vec<String> = ["src/lib.rs", "crates/a/src/lib.rs", "crates/b/src/main.rs", "crates/c/src/main.rs"].map(String::from).to_vec();
+        let report = CoverageReport {
+            files: ["src/lib.rs", "/ci/repo/crates/a/src/lib.rs", "src/main.rs"].map(|path| (path.to_string(), FileCoverage::default())).into(),
+        };
+
+        let (files, unmatched) = match_files(&report, &ids);
+        let mut matched: Vec<&str> = files.keys().map(String::as_str).collect();
+        matched.sort();
+        assert_eq!(matched, ["crates/a/src/lib.rs", "src/lib.rs"]);
+        // Both crates have a src/main.rs
+        assert_eq!(unmatched, ["src/main.rs"]);
+    }
+
+    #[test]
+    fn test_report_entries_for_the_same_file_are_merged() {
+        let lcov = "SF:pkg/check.py\nDA:1,1\nDA:4,0\nBRDA:2,0,0,0\nend_of_record\nSF:/ci/build/pkg/check.py\nDA:4,2\nDA:8,0\nBRDA:2,0,0,1\nend_of_record\n";
+        let (_, parsed) = CoverageReport::parse(lcov, None).unwrap();
+
+        let (files, unmatched) = match_files(&parsed, &file_ids(&world()));
+        assert!(unmatched.is_empty());
+        let coverage = &files["pkg/check.py"];
+        assert_eq!(coverage.lines, BTreeMap::from([(1, 1), (4, 2), (8, 0)]));
+        assert_eq!(coverage.branches, BTreeMap::from([((2, 0), true)]));
+    }
+}
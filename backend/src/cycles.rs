@@ -0,0 +1,160 @@
+//! Import cycle detection over the resolved route graph, reported at file,
+//! District and City level.
+
+use std::collections::{BTreeMap, HashMap};
+
+use tracing::debug;
+
+use crate::hierarchy::{self, Owners};
+use crate::models::{Cycle, CycleEdge, CycleLevel, GameEntity, Route, RouteType};
+
+fn owner_at(owners: &Owners, level: CycleLevel) -> Option<&str> {
+    match level {
+        CycleLevel::File => owners.file.as_deref(),
+        CycleLevel::District => owners.district.as_deref(),
+        CycleLevel::City => owners.city.as_deref(),
+    }
+}
+
+/// Import cycles between files, Districts and Cities.
+///
+/// Each strongly connected component of the import graph, lifted to a level,
+/// is one cycle; imports between two members of the same file (or District,
+/// or City) do not count towards that level.
+pub fn find_cycles(cities: &[GameEntity], routes: &[Route]) -> Vec<Cycle> {
+    let owners = hierarchy::index_owners(cities);
+
+    let cycles: Vec<Cycle> = [CycleLevel::File, CycleLevel::District, CycleLevel::City]
+        .into_iter()
+        .flat_map(|level| cycles_at(level, routes, &owners))
+        .collect();
+    debug!(cycles = cycles.len(), "Detected import cycles");
+    cycles
+}
+
+fn cycles_at(level: CycleLevel, routes: &[Route], owners: &HashMap<String, Owners>) -> Vec<Cycle> {
+    // (from, to) -> ids of the import routes behind that edge
+    let mut edges: BTreeMap<(&str, &str), Vec<String>> = BTreeMap::new();
+    for route in routes.iter().filter(|r| matches!(r.route_type, RouteType::Import)) {
+        let from = owners.get(&route.from_id).and_then(|o| owner_at(o, level));
+        let to = owners.get(&route.to_id).and_then(|o| owner_at(o, level));
+        if let (Some(from), Some(to)) = (from, to)
+            && from != to
+        {
+            edges.entry((from, to)).or_default().push(route.id.clone());
+        }
+    }
+
+    let mut adjacency: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for &(from, to) in edges.keys() {
+        adjacency.entry(from).or_default().push(to);
+        adjacency.entry(to).or_default();
+    }
+
+    strongly_connected(&adjacency)
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .map(|mut members| {
+            members.sort_unstable();
+            let cycle_edges = edges
+                .iter()
+                .filter(|((from, to), _)| members.binary_search(from).is_ok() && members.binary_search(to).is_ok())
+                .map(|((from, to), route_ids)| CycleEdge { from_id: from.to_string(), to_id: to.to_string(), route_ids: route_ids.clone() })
+                .collect();
+            Cycle { level, members: members.into_iter().map(str::to_string).collect(), edges: cycle_edges }
+        })
+        .collect()
+}
+
+/// Tarjan's algorithm, iterative so deep import chains cannot overflow the stack.
+fn strongly_connected<'a>(adjacency: &BTreeMap<&'a str, Vec<&'a str>>) -> Vec<Vec<&'a str>> {
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut lowlink: HashMap<&str, usize> = HashMap::new();
+    let mut on_stack: HashMap<&str, bool> = HashMap::new();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut components = Vec::new();
+
+    for &root in adjacency.keys() {
+        if index.contains_key(root) {
+            continue;
+        }
+        // (node, next neighbour to visit)
+        let mut work: Vec<(&str, usize)> = vec![(root, 0)];
+        while let Some(&(node, next)) = work.last() {
+            if next == 0 && !index.contains_key(node) {
+                let i = index.len();
+                index.insert(node, i);
+                lowlink.insert(node, i);
+                stack.push(node);
+                on_stack.insert(node, true);
+            }
+            if let Some(&to) = adjacency[node].get(next) {
+                if let Some(top) = work.last_mut() {
+                    top.1 += 1;
+                }
+                if !index.contains_key(to) {
+                    work.push((to, 0));
+                } else if on_stack.get(to).copied().unwrap_or(false) {
+                    let low = lowlink[node].min(index[to]);
+                    lowlink.insert(node, low);
+                }
+                continue;
+            }
+
+            work.pop();
+            if let Some(&(parent, _)) = work.last() {
+                let low = lowlink[parent].min(lowlink[node]);
+                lowlink.insert(parent, low);
+            }
+            if lowlink[node] == index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack.insert(member, false);
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{city, district, file_with_functions};
+
+    fn import(id: &str, from: &str, to: &str) -> Route {
+        Route { id: id.into(), from_id: from.into(), to_id: to.into(), route_type: RouteType::Import, bidirectional: false, metadata: None }
+    }
+
+    #[test]
+    fn test_cycles_are_found_at_each_level() {
+        let cities = vec![city("rs", vec![
+            district("a", vec![file_with_functions("a/x.rs", &["f"]), file_with_functions("a/y.rs", &["g"])]),
+            district("b", vec![file_with_functions("b/z.rs", &["h"])]),
+        ])];
+        let routes = vec![
+            import("r0", "a/x.rs", "a/y.rs::g"),
+            import("r1", "a/y.rs", "a/x.rs"),
+            import("r2", "a/y.rs", "b/z.rs::h"),
+            import("r3", "b/z.rs", "a/x.rs::f"),
+            import("r4", "a/x.rs", "a/x.rs::f"),
+        ];
+
+        let cycles = find_cycles(&cities, &routes);
+        let summary: Vec<(CycleLevel, Vec<&str>)> =
+            cycles.iter().map(|c| (c.level, c.members.iter().map(String::as_str).collect())).collect();
+        assert_eq!(summary, [
+            (CycleLevel::File, vec!["a/x.rs", "a/y.rs", "b/z.rs"]),
+            (CycleLevel::District, vec!["district_a", "district_b"]),
+        ]);
+
+        let district_edges: Vec<(&str, &str, &[String])> =
+            cycles[1].edges.iter().map(|e| (e.from_id.as_str(), e.to_id.as_str(), e.route_ids.as_slice())).collect();
+        assert_eq!(district_edges, [("district_a", "district_b", &["r2".to_string()][..]), ("district_b", "district_a", &["r3".to_string()][..])]);
+    }
+}
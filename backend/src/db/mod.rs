@@ -1,6 +1,7 @@
 pub mod models;
 pub mod mysql;
 pub mod repository;
+pub mod sources;
 pub mod world;
 
 use mongodb::{Database, IndexModel};
@@ -65,12 +66,66 @@ async fn create_indexes(db: &Database) {
         )
         .await;
 
+    let _ = db
+        .collection::<mongodb::bson::Document>("entities")
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "world_id": 1, "entity_id": 1 })
+                .build(),
+        )
+        .await;
+
+    let _ = db
+        .collection::<mongodb::bson::Document>("sources")
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "world_id": 1, "path": 1 })
+                .build(),
+        )
+        .await;
+
+    let _ = db
+        .collection::<mongodb::bson::Document>("routes")
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "world_id": 1 })
+                .build(),
+        )
+        .await;
+
+    let _ = db
+        .collection::<mongodb::bson::Document>("routes")
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "world_id": 1, "route.from_id": 1 })
+                .build(),
+        )
+        .await;
+
     let _ = db
         .collection::<mongodb::bson::Document>("routes")
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "world_id": 1, "route.to_id": 1 })
+                .build(),
+        )
+        .await;
+
+    let _ = db
+        .collection::<mongodb::bson::Document>("unresolved")
         .create_index(
             IndexModel::builder()
                 .keys(doc! { "world_id": 1 })
                 .build(),
         )
         .await;
+
+    let _ = db
+        .collection::<mongodb::bson::Document>("cycles")
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "world_id": 1, "cycle.level": 1 })
+                .build(),
+        )
+        .await;
 }
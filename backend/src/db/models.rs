@@ -62,6 +62,16 @@ pub struct ParsedWorldDoc {
     pub world_meta_total_rooms: u32,
     pub world_meta_total_artifacts: u32,
     pub world_meta_complexity_score: f32,
+    #[serde(default)]
+    pub world_meta_complexity_score_version: u32,
+    #[serde(default)]
+    pub world_meta_cycle_count: u32,
+    #[serde(default)]
+    pub world_meta_modularity: f32,
+    #[serde(default)]
+    pub world_meta_main_sequence_distance: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub world_meta_rules_error: Option<String>,
     pub entity_count: u32,
     pub route_count: u32,
 }
@@ -78,9 +88,23 @@ pub struct EntityDoc {
     pub entity_type: String,
     pub sort_order: i32,
     pub loc: u32,
+    // Denormalised from `entity` so spans can be queried by file and line range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<crate::models::SourceSpan>,
     pub entity: crate::models::GameEntity,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceDoc {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub world_id: ObjectId,
+    pub path: String,
+    pub language: String,
+    /// Gzip-compressed file contents.
+    pub content: mongodb::bson::Binary,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RouteDoc {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
@@ -89,6 +113,23 @@ pub struct RouteDoc {
     pub route: crate::models::Route,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnresolvedDoc {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub world_id: ObjectId,
+    #[serde(rename = "ref")]
+    pub reference: crate::models::UnresolvedRef,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleDoc {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub world_id: ObjectId,
+    pub cycle: crate::models::Cycle,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GitHubRepoResponse {
     pub description: Option<String>,
@@ -105,4 +146,14 @@ pub struct GitHubRepoResponse {
     pub default_branch: Option<String>,
     #[serde(rename = "pushed_at")]
     pub pushed_at: Option<String>,
+    /// What the token's user may do; absent for anonymous requests.
+    pub permissions: Option<GitHubRepoPermissions>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubRepoPermissions {
+    #[serde(default)]
+    pub pull: bool,
+    #[serde(default)]
+    pub push: bool,
 }
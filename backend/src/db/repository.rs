@@ -79,3 +79,8 @@ pub async fn update_repo_after_parse(
 
     Ok(())
 }
+
+pub async fn find_repo_by_id(db: &Database, repo_id: ObjectId) -> Result<Option<RepoDoc>, AppError> {
+    let collection = db.collection::<RepoDoc>("repositories");
+    Ok(collection.find_one(doc! { "_id": repo_id }).await?)
+}
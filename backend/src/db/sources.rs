@@ -0,0 +1,47 @@
+use mongodb::Database;
+use mongodb::bson::spec::BinarySubtype;
+use mongodb::bson::{Binary, doc, oid::ObjectId};
+
+use super::models::SourceDoc;
+use crate::error::AppError;
+use crate::sources::{self, SourceFile};
+
+pub async fn store_sources(
+    db: &Database,
+    world_id: ObjectId,
+    files: Vec<SourceFile>,
+) -> Result<usize, AppError> {
+    let docs: Vec<SourceDoc> = files
+        .into_iter()
+        .map(|file| SourceDoc {
+            id: None,
+            world_id,
+            path: file.path,
+            language: file.language,
+            content: Binary { subtype: BinarySubtype::Generic, bytes: file.compressed },
+        })
+        .collect();
+
+    let count = docs.len();
+    let collection = db.collection::<SourceDoc>("sources");
+    for chunk in docs.chunks(100) {
+        collection.insert_many(chunk.to_vec()).await?;
+    }
+    Ok(count)
+}
+
+/// Returns the decompressed contents and language tag of `path` in `world_id`.
+pub async fn get_source(
+    db: &Database,
+    world_id: ObjectId,
+    path: &str,
+) -> Result<Option<(String, String)>, AppError> {
+    let collection = db.collection::<SourceDoc>("sources");
+    let Some(source_doc) = collection.find_one(doc! { "world_id": world_id, "path": path }).await? else {
+        return Ok(None);
+    };
+
+    let content = sources::decompress(&source_doc.content.bytes)
+        .map_err(|e| AppError::Internal(format!("Corrupt source blob for {path}: {e}")))?;
+    Ok(Some((content, source_doc.language)))
+}
@@ -1,19 +1,20 @@
 use chrono::Utc;
 use futures::TryStreamExt;
+use std::collections::HashMap;
 use mongodb::Database;
-use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::bson::{Bson, Document, doc, oid::ObjectId};
 use tracing::{info, warn};
 
-use super::models::{EntityDoc, ParsedWorldDoc, RouteDoc};
+use super::models::{CycleDoc, EntityDoc, ParsedWorldDoc, RouteDoc, UnresolvedDoc};
 use crate::domain::entity_tree;
 use crate::error::AppError;
-use crate::models::{Route, WorldMeta, WorldSeed};
+use crate::models::{Cycle, CycleLevel, GameEntity, Route, UnresolvedRef, WorldMeta, WorldSeed};
 
 pub async fn get_cached_world(
     db: &Database,
     repository_id: ObjectId,
     commit_hash: &str,
-) -> Result<Option<WorldSeed>, AppError> {
+) -> Result<Option<(ObjectId, WorldSeed)>, AppError> {
     let worlds_collection = db.collection::<ParsedWorldDoc>("parsed_worlds");
     let world = worlds_collection
         .find_one(doc! { "repository_id": repository_id, "commit_hash": commit_hash })
@@ -32,32 +33,49 @@ pub async fn get_cached_world(
             let _ = worlds_collection.delete_one(doc! { "_id": world_id }).await;
             let _ = db.collection::<mongodb::bson::Document>("entities").delete_many(doc! { "world_id": world_id }).await;
             let _ = db.collection::<mongodb::bson::Document>("routes").delete_many(doc! { "world_id": world_id }).await;
+            let _ = db.collection::<mongodb::bson::Document>("sources").delete_many(doc! { "world_id": world_id }).await;
+            let _ = db.collection::<mongodb::bson::Document>("unresolved").delete_many(doc! { "world_id": world_id }).await;
+            let _ = db.collection::<mongodb::bson::Document>("cycles").delete_many(doc! { "world_id": world_id }).await;
             return Ok(None);
         }
     };
     let routes = fetch_routes(db, world_id).await?;
+    let unresolved = get_unresolved(db, world_id).await?;
+    let cycles = get_cycles(db, world_id, None).await?;
 
     let cities = entity_tree::reconstruct_tree(&entities);
 
     let world_seed = WorldSeed {
-        world_meta: WorldMeta {
-            total_cities: world_doc.world_meta_total_cities,
-            total_buildings: world_doc.world_meta_total_buildings,
-            total_rooms: world_doc.world_meta_total_rooms,
-            total_artifacts: world_doc.world_meta_total_artifacts,
-            dominant_language: world_doc.world_meta_dominant_language,
-            complexity_score: world_doc.world_meta_complexity_score,
-        },
+        world_meta: world_meta_from_doc(&world_doc),
         cities,
         highways: routes,
+        unresolved,
+        cycles,
     };
 
     info!(commit = %commit_hash, "Returning cached world from MongoDB");
-    Ok(Some(world_seed))
+    Ok(Some((world_id, world_seed)))
+}
+
+pub fn world_meta_from_doc(world_doc: &ParsedWorldDoc) -> WorldMeta {
+    WorldMeta {
+        total_cities: world_doc.world_meta_total_cities,
+        total_buildings: world_doc.world_meta_total_buildings,
+        total_rooms: world_doc.world_meta_total_rooms,
+        total_artifacts: world_doc.world_meta_total_artifacts,
+        dominant_language: world_doc.world_meta_dominant_language.clone(),
+        complexity_score: world_doc.world_meta_complexity_score,
+        complexity_score_version: world_doc.world_meta_complexity_score_version,
+        cycle_count: world_doc.world_meta_cycle_count,
+        modularity: world_doc.world_meta_modularity,
+        main_sequence_distance: world_doc.world_meta_main_sequence_distance,
+        rules_error: world_doc.world_meta_rules_error.clone(),
+    }
 }
 
 pub async fn store_world(
     db: &Database,
+    world_id: ObjectId,
     repository_id: ObjectId,
     commit_hash: &str,
     world_seed: &WorldSeed,
@@ -66,7 +84,7 @@ pub async fn store_world(
     let parsed_at = Utc::now().to_rfc3339();
 
     let world_doc = ParsedWorldDoc {
-        id: None,
+        id: Some(world_id),
         repository_id,
         commit_hash: commit_hash.to_string(),
         parsed_at: parsed_at.clone(),
@@ -76,16 +94,17 @@ pub async fn store_world(
         world_meta_total_rooms: world_seed.world_meta.total_rooms,
         world_meta_total_artifacts: world_seed.world_meta.total_artifacts,
         world_meta_complexity_score: world_seed.world_meta.complexity_score,
+        world_meta_complexity_score_version: world_seed.world_meta.complexity_score_version,
+        world_meta_cycle_count: world_seed.world_meta.cycle_count,
+        world_meta_modularity: world_seed.world_meta.modularity,
+        world_meta_main_sequence_distance: world_seed.world_meta.main_sequence_distance,
+        world_meta_rules_error: world_seed.world_meta.rules_error.clone(),
         entity_count: 0,
         route_count: world_seed.highways.len() as u32,
     };
 
     let worlds_collection = db.collection::<ParsedWorldDoc>("parsed_worlds");
-    let insert_result = worlds_collection.insert_one(&world_doc).await?;
-    let world_id = insert_result
-        .inserted_id
-        .as_object_id()
-        .ok_or_else(|| AppError::Internal("Failed to get world ID".into()))?;
+    worlds_collection.insert_one(&world_doc).await?;
 
     let (entities, entity_count) = entity_tree::flatten_entities(&world_seed.cities, world_id);
     let routes = build_route_docs(&world_seed.highways, world_id);
@@ -104,6 +123,30 @@ pub async fn store_world(
         }
     }
 
+    if !world_seed.unresolved.is_empty() {
+        let unresolved_docs: Vec<UnresolvedDoc> = world_seed
+            .unresolved
+            .iter()
+            .map(|r| UnresolvedDoc { id: None, world_id, reference: r.clone() })
+            .collect();
+        let unresolved_collection = db.collection::<UnresolvedDoc>("unresolved");
+        for chunk in unresolved_docs.chunks(500) {
+            unresolved_collection.insert_many(chunk.to_vec()).await?;
+        }
+    }
+
+    if !world_seed.cycles.is_empty() {
+        let cycle_docs: Vec<CycleDoc> = world_seed
+            .cycles
+            .iter()
+            .map(|c| CycleDoc { id: None, world_id, cycle: c.clone() })
+            .collect();
+        let cycles_collection = db.collection::<CycleDoc>("cycles");
+        for chunk in cycle_docs.chunks(500) {
+            cycles_collection.insert_many(chunk.to_vec()).await?;
+        }
+    }
+
     worlds_collection
         .update_one(
             doc! { "_id": world_id },
@@ -115,6 +158,201 @@ pub async fn store_world(
     Ok(world_id)
 }
 
+pub async fn get_world_doc(db: &Database, world_id: ObjectId) -> Result<Option<ParsedWorldDoc>, AppError> {
+    let collection = db.collection::<ParsedWorldDoc>("parsed_worlds");
+    Ok(collection.find_one(doc! { "_id": world_id }).await?)
+}
+
+pub async fn get_entity(
+    db: &Database,
+    world_id: ObjectId,
+    entity_id: &str,
+) -> Result<Option<EntityDoc>, AppError> {
+    let collection = db.collection::<EntityDoc>("entities");
+    Ok(collection.find_one(doc! { "world_id": world_id, "entity_id": entity_id }).await?)
+}
+
+/// Children of `parent_entity_id` (`None` for cities) in tree order, paginated.
+pub async fn get_child_entities(
+    db: &Database,
+    world_id: ObjectId,
+    parent_entity_id: Option<&str>,
+    offset: u64,
+    limit: i64,
+) -> Result<(Vec<EntityDoc>, u64), AppError> {
+    let collection = db.collection::<EntityDoc>("entities");
+    let filter = match parent_entity_id {
+        Some(parent) => doc! { "world_id": world_id, "parent_entity_id": parent },
+        None => doc! { "world_id": world_id, "parent_entity_id": { "$exists": false } },
+    };
+
+    let total = collection.count_documents(filter.clone()).await?;
+    let cursor = collection
+        .find(filter)
+        .sort(doc! { "sort_order": 1 })
+        .skip(offset)
+        .limit(limit)
+        .await?;
+    let entities: Vec<EntityDoc> = cursor.try_collect().await?;
+    Ok((entities, total))
+}
+
+/// Number of direct children for each of `entity_ids`.
+pub async fn count_children(
+    db: &Database,
+    world_id: ObjectId,
+    entity_ids: &[String],
+) -> Result<HashMap<String, u64>, AppError> {
+    let collection = db.collection::<EntityDoc>("entities");
+    let pipeline = vec![
+        doc! { "$match": { "world_id": world_id, "parent_entity_id": { "$in": entity_ids } } },
+        doc! { "$group": { "_id": "$parent_entity_id", "count": { "$sum": 1 } } },
+    ];
+
+    let mut counts = HashMap::new();
+    let mut cursor = collection.aggregate(pipeline).await?;
+    while let Some(group) = cursor.try_next().await? {
+        if let (Ok(id), Some(count)) = (group.get_str("_id"), group.get("count")) {
+            let count = count.as_i32().map(i64::from).or_else(|| count.as_i64()).unwrap_or(0);
+            counts.insert(id.to_string(), count as u64);
+        }
+    }
+    Ok(counts)
+}
+
+/// Routes starting or ending at any of `entity_ids`.
+pub async fn get_routes_touching(
+    db: &Database,
+    world_id: ObjectId,
+    entity_ids: &[String],
+) -> Result<Vec<Route>, AppError> {
+    let collection = db.collection::<RouteDoc>("routes");
+    let cursor = collection.find(routes_touching_filter(world_id, entity_ids)).await?;
+    let route_docs: Vec<RouteDoc> = cursor.try_collect().await?;
+    Ok(route_docs.into_iter().map(|rd| rd.route).collect())
+}
+
+/// Routes of `world_id` starting or ending at one of `entity_ids`.
+fn routes_touching_filter(world_id: ObjectId, entity_ids: &[String]) -> Document {
+    doc! {
+        "world_id": world_id,
+        "$or": [
+            { "route.from_id": { "$in": entity_ids } },
+            { "route.to_id": { "$in": entity_ids } },
+        ],
+    }
+}
+
+/// Every route of a world whose type is one of `route_types` (RouteType names).
+pub async fn get_routes_of_types(
+    db: &Database,
+    world_id: ObjectId,
+    route_types: &[String],
+) -> Result<Vec<Route>, AppError> {
+    let collection = db.collection::<RouteDoc>("routes");
+    let cursor = collection
+        .find(doc! { "world_id": world_id, "route.route_type": { "$in": route_types } })
+        .await?;
+    let route_docs: Vec<RouteDoc> = cursor.try_collect().await?;
+    Ok(route_docs.into_iter().map(|rd| rd.route).collect())
+}
+
+/// Routes of a world that break one of its architecture rules.
+pub async fn get_routes_with_violations(db: &Database, world_id: ObjectId) -> Result<Vec<Route>, AppError> {
+    let collection = db.collection::<RouteDoc>("routes");
+    let cursor = collection
+        .find(doc! { "world_id": world_id, "route.metadata.violations": { "$exists": true } })
+        .await?;
+    let route_docs: Vec<RouteDoc> = cursor.try_collect().await?;
+    Ok(route_docs.into_iter().map(|rd| rd.route).collect())
+}
+
+/// Rooms and Buildings flagged as likely dead code.
+pub async fn get_dead_entities(db: &Database, world_id: ObjectId) -> Result<Vec<EntityDoc>, AppError> {
+    let collection = db.collection::<EntityDoc>("entities");
+    let cursor = collection
+        .find(doc! { "world_id": world_id, "entity.spec.metadata.dead": "true" })
+        .sort(doc! { "sort_order": 1 })
+        .await?;
+    Ok(cursor.try_collect().await?)
+}
+
+/// Every entity of a world, rebuilt into its City tree.
+pub async fn get_entity_tree(db: &Database, world_id: ObjectId) -> Result<Vec<GameEntity>, AppError> {
+    let entities = fetch_entities(db, world_id).await?;
+    Ok(entity_tree::reconstruct_tree(&entities))
+}
+
+/// Replaces the coverage metadata of a world's entities, clearing it from
+/// entities the new report no longer covers. Covered entities are updated in
+/// batched `update` commands before anything is cleared, so a failure partway
+/// leaves earlier coverage in place rather than none.
+pub async fn store_coverage(
+    db: &Database,
+    world_id: ObjectId,
+    coverage: &[(String, HashMap<String, String>)],
+) -> Result<(), AppError> {
+    let field = |key: &str| format!("entity.spec.metadata.{key}");
+
+    for chunk in coverage.chunks(500) {
+        let updates: Vec<Document> = chunk
+            .iter()
+            .map(|(entity_id, metadata)| {
+                let mut set = Document::new();
+                for (key, value) in metadata {
+                    set.insert(field(key), value);
+                }
+                let mut update = doc! { "$set": set };
+                // Keys the new report lacks, e.g. branch coverage
+                let stale: Document = crate::coverage::METADATA_KEYS
+                    .iter()
+                    .filter(|key| !metadata.contains_key(**key))
+                    .map(|key| (field(key), Bson::String(String::new())))
+                    .collect();
+                if !stale.is_empty() {
+                    update.insert("$unset", stale);
+                }
+                doc! { "q": { "world_id": world_id, "entity_id": entity_id }, "u": update }
+            })
+            .collect();
+        let reply = db.run_command(doc! { "update": "entities", "updates": updates, "ordered": false }).await?;
+        if let Ok(errors) = reply.get_array("writeErrors")
+            && !errors.is_empty()
+        {
+            return Err(AppError::Internal(format!("{} coverage updates failed: {:?}", errors.len(), errors[0])));
+        }
+    }
+
+    let covered: Vec<&str> = coverage.iter().map(|(entity_id, _)| entity_id.as_str()).collect();
+    let unset: Document = crate::coverage::METADATA_KEYS.iter().map(|key| (field(key), Bson::String(String::new()))).collect();
+    db.collection::<EntityDoc>("entities")
+        .update_many(doc! { "world_id": world_id, "entity_id": { "$nin": covered } }, doc! { "$unset": unset })
+        .await?;
+
+    info!(world_id = %world_id, entities = coverage.len(), "Stored coverage");
+    Ok(())
+}
+
+/// Call and import targets of a world that did not resolve to an entity.
+pub async fn get_unresolved(db: &Database, world_id: ObjectId) -> Result<Vec<UnresolvedRef>, AppError> {
+    let collection = db.collection::<UnresolvedDoc>("unresolved");
+    let cursor = collection.find(doc! { "world_id": world_id }).await?;
+    let docs: Vec<UnresolvedDoc> = cursor.try_collect().await?;
+    Ok(docs.into_iter().map(|d| d.reference).collect())
+}
+
+/// Import cycles of a world, optionally only those at one level.
+pub async fn get_cycles(db: &Database, world_id: ObjectId, level: Option<CycleLevel>) -> Result<Vec<Cycle>, AppError> {
+    let collection = db.collection::<CycleDoc>("cycles");
+    let mut filter = doc! { "world_id": world_id };
+    if let Some(level) = level {
+        filter.insert("cycle.level", mongodb::bson::to_bson(&level).map_err(|e| AppError::Internal(e.to_string()))?);
+    }
+    let cursor = collection.find(filter).await?;
+    let docs: Vec<CycleDoc> = cursor.try_collect().await?;
+    Ok(docs.into_iter().map(|d| d.cycle).collect())
+}
+
 fn build_route_docs(routes: &[Route], world_id: ObjectId) -> Vec<RouteDoc> {
     routes.iter().map(|route| RouteDoc { id: None, world_id, route: route.clone() }).collect()
 }
@@ -135,3 +373,29 @@ async fn fetch_routes(db: &Database, world_id: ObjectId) -> Result<Vec<Route>, A
     let route_docs: Vec<RouteDoc> = cursor.try_collect().await?;
     Ok(route_docs.into_iter().map(|rd| rd.route).collect())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes_touching_filter_matches_either_end_within_the_world() {
+        let world_id = ObjectId::new();
+        let ids = vec!["src/a.rs::f".to_string(), "src/b.rs".to_string()];
+
+        let filter = routes_touching_filter(world_id, &ids);
+
+        assert_eq!(filter.get_object_id("world_id").unwrap(), world_id);
+        let ends: Vec<(String, Vec<String>)> = filter
+            .get_array("$or")
+            .unwrap()
+            .iter()
+            .map(|clause| {
+                let (field, condition) = clause.as_document().unwrap().iter().next().unwrap();
+                let ids = condition.as_document().unwrap().get_array("$in").unwrap();
+                (field.clone(), ids.iter().map(|id| id.as_str().unwrap().to_string()).collect())
+            })
+            .collect();
+        assert_eq!(ends, [("route.from_id".to_string(), ids.clone()), ("route.to_id".to_string(), ids)]);
+    }
+}
@@ -0,0 +1,263 @@
+//! Dead code detection: private Rooms and Buildings that no entry point
+//! reaches through resolved routes.
+
+use std::collections::{HashMap, HashSet};
+
+use tracing::debug;
+
+use crate::models::{DeadEntity, GameEntity, Route};
+use crate::symbol_table::SymbolTable;
+
+const NO_INCOMING: &str = "no incoming routes";
+const UNREACHABLE: &str = "only referenced from unreachable code";
+
+/// A Room or Building as seen by the reachability walk.
+struct Node {
+    parent: Option<String>,
+    /// Private and not a trait impl or impl block, so a candidate for being dead
+    candidate: bool,
+}
+
+/// Flags private Rooms and Buildings that cannot be reached from any entry
+/// point as likely dead, setting `dead` and `dead_reason` in their metadata.
+///
+/// Entry points are `main` functions, anything public (exported APIs), trait
+/// impl methods, HTTP handlers, FFI exports and tests. Reachability follows
+/// every resolved route, and a reachable member keeps the type or function
+/// enclosing it alive. Functions only used as values (callbacks, serde
+/// defaults) or inside macros have no routes and are reported too, hence
+/// "likely". Returns the number of flagged entities.
+pub fn annotate_dead_code(cities: &mut [GameEntity], routes: &[Route], symbol_table: &SymbolTable) -> u32 {
+    let mut nodes = HashMap::new();
+    let mut roots = Vec::new();
+    for city in cities.iter() {
+        index(city, None, false, symbol_table, &mut nodes, &mut roots);
+    }
+
+    let mut outgoing: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut referenced = HashSet::new();
+    for route in routes.iter().filter(|r| r.from_id != r.to_id) {
+        outgoing.entry(route.from_id.as_str()).or_default().push(&route.to_id);
+        referenced.insert(route.to_id.as_str());
+    }
+
+    let mut reachable: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = roots.iter().map(String::as_str).collect();
+    while let Some(id) = stack.pop() {
+        if !reachable.insert(id) {
+            continue;
+        }
+        stack.extend(outgoing.get(id).into_iter().flatten());
+        if let Some(parent) = nodes.get(id).and_then(|n| n.parent.as_deref()) {
+            stack.push(parent);
+        }
+    }
+
+    let dead: HashMap<String, &'static str> = nodes
+        .iter()
+        .filter(|(id, node)| node.candidate && !reachable.contains(id.as_str()))
+        .map(|(id, _)| (id.clone(), if referenced.contains(id.as_str()) { UNREACHABLE } else { NO_INCOMING }))
+        .collect();
+    for city in cities.iter_mut() {
+        flag(city, &dead);
+    }
+    debug!(entities = nodes.len(), reachable = reachable.len(), dead = dead.len(), "Computed dead code");
+    dead.len() as u32
+}
+
+fn index(
+    entity: &GameEntity,
+    parent: Option<&str>,
+    in_trait_impl: bool,
+    symbol_table: &SymbolTable,
+    nodes: &mut HashMap<String, Node>,
+    roots: &mut Vec<String>,
+) {
+    let (id, children, in_trait_impl) = match entity {
+        GameEntity::City { children, .. } | GameEntity::District { children, .. } => {
+            for child in children {
+                index(child, None, false, symbol_table, nodes, roots);
+            }
+            return;
+        }
+        GameEntity::Artifact { id, artifact_type, metadata, .. } => {
+            if let Some(owner) = parent {
+                match artifact_type.as_str() {
+                    "ffi_export" => roots.push(owner.to_string()),
+                    "http_endpoint" => {
+                        let metadata = metadata.as_ref();
+                        if let Some(handler) = metadata.and_then(|m| m.get("handler_id")) {
+                            roots.push(handler.clone());
+                        } else if let Some(resolution) = metadata.and_then(|m| m.get("handler")).and_then(|h| symbol_table.resolve(h, id)) {
+                            roots.push(resolution.id);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            return;
+        }
+        GameEntity::Building { id, name, building_type, is_public, is_test, children, .. } => {
+            let is_impl = building_type == "impl";
+            let candidate = !is_public && !is_impl && building_type != "file";
+            if *is_public || *is_test {
+                roots.push(id.clone());
+            }
+            nodes.insert(id.clone(), Node { parent: parent.map(str::to_string), candidate });
+            // Methods of `impl Trait for Type` are called through the trait
+            (id, children, is_impl && name.contains(" for "))
+        }
+        GameEntity::Room { id, is_main, is_test, visibility, children, .. } => {
+            let is_private = visibility == "private";
+            if !is_private || *is_main || in_trait_impl || *is_test {
+                roots.push(id.clone());
+            }
+            nodes.insert(id.clone(), Node { parent: parent.map(str::to_string), candidate: is_private && !in_trait_impl });
+            (id, children, false)
+        }
+    };
+    for child in children {
+        index(child, Some(id), in_trait_impl, symbol_table, nodes, roots);
+    }
+}
+
+fn flag(entity: &mut GameEntity, dead: &HashMap<String, &'static str>) {
+    let children = match entity {
+        GameEntity::Building { id, metadata, children, .. } | GameEntity::Room { id, metadata, children, .. } => {
+            if let Some(reason) = dead.get(id.as_str()) {
+                let metadata = metadata.get_or_insert_with(HashMap::new);
+                metadata.insert("dead".into(), "true".into());
+                metadata.insert("dead_reason".into(), reason.to_string());
+            }
+            children
+        }
+        GameEntity::City { children, .. } | GameEntity::District { children, .. } => children,
+        GameEntity::Artifact { .. } => return,
+    };
+    for child in children {
+        flag(child, dead);
+    }
+}
+
+/// The dead code flag of a Room or Building, as set by [`annotate_dead_code`].
+pub fn dead_entity(entity: &GameEntity) -> Option<DeadEntity> {
+    let (id, name, entity_type, loc, span, metadata) = match entity {
+        GameEntity::Building { id, name, building_type, loc, span, metadata, .. } => (id, name, building_type, loc, span, metadata),
+        GameEntity::Room { id, name, room_type, loc, span, metadata, .. } => (id, name, room_type, loc, span, metadata),
+        _ => return None,
+    };
+    let metadata = metadata.as_ref().filter(|m| m.get("dead").is_some_and(|v| v == "true"))?;
+    Some(DeadEntity {
+        id: id.clone(),
+        name: name.clone(),
+        entity_type: entity_type.clone(),
+        loc: *loc,
+        reason: metadata.get("dead_reason").cloned().unwrap_or_default(),
+        span: span.clone(),
+    })
+}
+
+/// Lines of code in `entities`, counting an entity nested inside another
+/// flagged entity only once (a dead struct's loc already includes its methods).
+pub fn total_loc(entities: &[DeadEntity]) -> u32 {
+    let ids: HashSet<&str> = entities.iter().map(|e| e.id.as_str()).collect();
+    let has_dead_ancestor = |id: &str| {
+        let mut scope = id;
+        while let Some((parent, _)) = scope.rsplit_once("::") {
+            if ids.contains(parent) {
+                return true;
+            }
+            scope = parent;
+        }
+        false
+    };
+    entities.iter().filter(|e| !has_dead_ancestor(&e.id)).map(|e| e.loc).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RouteType;
+
+    fn room(id: &str, visibility: &str, is_main: bool) -> GameEntity {
+        let name = id.rsplit("::").next().unwrap().to_string();
+        GameEntity::Room {
+            id: id.into(), name, room_type: "function".into(), is_main, is_async: false, is_test: false, visibility: visibility.into(), complexity: 1, loc: 3,
+            parameters: vec![], return_type: None, calls: vec![], children: vec![], span: None, metadata: None,
+        }
+    }
+
+    fn test_room(id: &str) -> GameEntity {
+        let mut test = room(id, "private", false);
+        if let GameEntity::Room { is_test, .. } = &mut test {
+            *is_test = true;
+        }
+        test
+    }
+
+    fn building(id: &str, building_type: &str, is_public: bool, children: Vec<GameEntity>) -> GameEntity {
+        let name = id.rsplit("::").next().unwrap().replace('_', " ");
+        GameEntity::Building { id: id.into(), name, building_type: building_type.into(), is_public, is_test: false, loc: 1, imports: vec![], supertypes: vec![], children, span: None, metadata: None }
+    }
+
+    fn call(from: &str, to: &str) -> Route {
+        Route { id: format!("{from}->{to}"), from_id: from.into(), to_id: to.into(), route_type: RouteType::FunctionCall, bidirectional: false, metadata: None }
+    }
+
+    #[test]
+    fn test_unreachable_private_entities_are_flagged() {
+        let mut cities = vec![GameEntity::District {
+            id: "district_src".into(), name: "src".into(), path: "src".into(), metadata: None,
+            children: vec![
+                building("src/main.rs", "file", true, vec![
+                    room("src/main.rs::main", "private", true),
+                    room("src/main.rs::helper", "private", false),
+                    room("src/main.rs::orphan", "private", false),
+                    room("src/main.rs::island", "private", false),
+                    room("src/main.rs::island_helper", "private", false),
+                    building("src/main.rs::Config", "struct", false, vec![]),
+                    building("src/main.rs::Unused", "struct", false, vec![]),
+                    building("src/main.rs::impl_Display_for_Unused", "impl", false, vec![room("src/main.rs::impl_Display_for_Unused::fmt", "private", false)]),
+                    test_room("src/main.rs::check_helper"),
+                ]),
+                building("src/lib.rs", "file", true, vec![room("src/lib.rs::api", "public", false), room("src/lib.rs::internal", "private", false)]),
+            ],
+        }];
+        let routes = vec![
+            call("src/main.rs::main", "src/main.rs::helper"),
+            call("src/main.rs::helper", "src/main.rs::Config"),
+            call("src/main.rs::island", "src/main.rs::island_helper"),
+            call("src/main.rs::island_helper", "src/main.rs::island"),
+            call("src/lib.rs::api", "src/lib.rs::internal"),
+        ];
+
+        let count = annotate_dead_code(&mut cities, &routes, &SymbolTable::default());
+        let mut dead = Vec::new();
+        fn collect(entity: &GameEntity, out: &mut Vec<(String, String)>) {
+            if let Some(d) = dead_entity(entity) {
+                out.push((d.name, d.reason));
+            }
+            if let GameEntity::District { children, .. } | GameEntity::Building { children, .. } = entity {
+                children.iter().for_each(|c| collect(c, out));
+            }
+        }
+        collect(&cities[0], &mut dead);
+        dead.sort();
+
+        assert_eq!(count, 4);
+        assert_eq!(dead, [
+            ("Unused".to_string(), NO_INCOMING.to_string()),
+            ("island".to_string(), UNREACHABLE.to_string()),
+            ("island_helper".to_string(), UNREACHABLE.to_string()),
+            ("orphan".to_string(), NO_INCOMING.to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_total_loc_skips_entities_inside_dead_parents() {
+        let dead = |id: &str, loc: u32| DeadEntity { id: id.into(), name: String::new(), entity_type: String::new(), loc, reason: String::new(), span: None };
+        let entities = [dead("src/a.rs::Unused", 10), dead("src/a.rs::Unused::inner::f", 4), dead("src/a.rs::orphan", 3)];
+
+        assert_eq!(total_loc(&entities), 13);
+    }
+}
@@ -41,6 +41,7 @@ fn flatten_recursive(
         entity_type,
         sort_order: current_order,
         loc,
+        span: entity.span().cloned(),
         entity: entity_without_children,
     });
 
@@ -97,7 +98,7 @@ pub fn reconstruct_tree(entities: &[EntityDoc]) -> Vec<GameEntity> {
 
     root_entities
         .iter()
-        .map(|doc| build_entity_with_children(*doc, &children_map))
+        .map(|doc| build_entity_with_children(doc, &children_map))
         .collect()
 }
 
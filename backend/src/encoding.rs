@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::{GameEntity, Route, RouteType, WorldMeta, WorldResponse};
+
+/// Bumped whenever the interned layout changes in a way decoders must notice.
+pub const INTERNED_FORMAT_VERSION: u32 = 2;
+
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+pub const INTERNED_JSON_CONTENT_TYPE: &str = "application/vnd.nilsbohr.interned+json";
+
+/// Wire format for world responses, picked from `?format=` or the `Accept` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WorldEncoding {
+    /// Indented JSON of the full `WorldResponse` (the historical default).
+    #[default]
+    Pretty,
+    /// Minified JSON of the full `WorldResponse`.
+    Compact,
+    /// Minified JSON of an `InternedWorld`.
+    Interned,
+    /// MessagePack of an `InternedWorld`, with named fields.
+    Msgpack,
+}
+
+impl WorldEncoding {
+    /// An explicit `format` wins; otherwise the first recognised `Accept` media type.
+    pub fn negotiate(format: Option<WorldEncoding>, accept: Option<&str>) -> Self {
+        if let Some(format) = format {
+            return format;
+        }
+        let Some(accept) = accept else {
+            return WorldEncoding::Pretty;
+        };
+
+        for media_type in accept.split(',').map(|m| m.split(';').next().unwrap_or("").trim()) {
+            match media_type {
+                "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                    return WorldEncoding::Msgpack;
+                }
+                INTERNED_JSON_CONTENT_TYPE => return WorldEncoding::Interned,
+                "application/json" => return WorldEncoding::Pretty,
+                _ => {}
+            }
+        }
+        WorldEncoding::Pretty
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            WorldEncoding::Pretty | WorldEncoding::Compact => "application/json",
+            WorldEncoding::Interned => INTERNED_JSON_CONTENT_TYPE,
+            WorldEncoding::Msgpack => MSGPACK_CONTENT_TYPE,
+        }
+    }
+}
+
+/// A world with every entity id stored once in `ids`. Routes reference
+/// entities by their index in that table instead of repeating the id.
+///
+/// `ids` lists the entities of `cities` in depth-first pre-order (a parent
+/// before its children, siblings in order), followed by any route endpoint
+/// that is not part of the tree. Routes keep their original ids, which are
+/// what violations, cycles and impact reports refer to.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InternedWorld {
+    pub version: u32,
+    pub project_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub world_id: Option<String>,
+    pub generated_at: String,
+    pub world_meta: WorldMeta,
+    pub ids: Vec<String>,
+    pub cities: Vec<GameEntity>,
+    pub routes: Vec<InternedRoute>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InternedRoute {
+    pub id: String,
+    pub from: u32,
+    pub to: u32,
+    pub route_type: RouteType,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bidirectional: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+impl InternedWorld {
+    pub fn from_response(response: &WorldResponse) -> Self {
+        let mut table = IdTable::default();
+        for city in &response.seed.cities {
+            table.intern_tree(city);
+        }
+
+        let routes = response
+            .seed
+            .highways
+            .iter()
+            .map(|route| InternedRoute {
+                id: route.id.clone(),
+                from: table.intern(&route.from_id),
+                to: table.intern(&route.to_id),
+                route_type: route.route_type.clone(),
+                bidirectional: route.bidirectional,
+                metadata: route.metadata.clone(),
+            })
+            .collect();
+
+        InternedWorld {
+            version: INTERNED_FORMAT_VERSION,
+            project_name: response.project_name.clone(),
+            world_id: response.world_id.clone(),
+            generated_at: response.generated_at.clone(),
+            world_meta: response.seed.world_meta.clone(),
+            ids: table.ids,
+            cities: response.seed.cities.clone(),
+            routes,
+        }
+    }
+
+    /// Expands interned routes back into full `Route`s. Fails when a route
+    /// references an index outside `ids`.
+    pub fn routes(&self) -> Result<Vec<Route>, String> {
+        let id_at = |i: u32| {
+            self.ids.get(i as usize).cloned().ok_or_else(|| format!("Route endpoint {i} is not in the id table"))
+        };
+        self.routes
+            .iter()
+            .map(|r| {
+                Ok(Route {
+                    id: r.id.clone(),
+                    from_id: id_at(r.from)?,
+                    to_id: id_at(r.to)?,
+                    route_type: r.route_type.clone(),
+                    bidirectional: r.bidirectional,
+                    metadata: r.metadata.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct IdTable {
+    ids: Vec<String>,
+    index: HashMap<String, u32>,
+}
+
+impl IdTable {
+    fn intern(&mut self, id: &str) -> u32 {
+        if let Some(&i) = self.index.get(id) {
+            return i;
+        }
+        let i = self.ids.len() as u32;
+        self.ids.push(id.to_string());
+        self.index.insert(id.to_string(), i);
+        i
+    }
+
+    fn intern_tree(&mut self, entity: &GameEntity) {
+        let (id, children) = match entity {
+            GameEntity::City { id, children, .. }
+            | GameEntity::District { id, children, .. }
+            | GameEntity::Building { id, children, .. }
+            | GameEntity::Room { id, children, .. } => (id, children.as_slice()),
+            GameEntity::Artifact { id, .. } => (id, &[][..]),
+        };
+        self.intern(id);
+        for child in children {
+            self.intern_tree(child);
+        }
+    }
+}
+
+/// Serialises a world response in `encoding`.
+pub fn encode_world(response: &WorldResponse, encoding: WorldEncoding) -> Result<Vec<u8>, String> {
+    match encoding {
+        WorldEncoding::Pretty => serde_json::to_vec_pretty(response).map_err(|e| e.to_string()),
+        WorldEncoding::Compact => serde_json::to_vec(response).map_err(|e| e.to_string()),
+        WorldEncoding::Interned => {
+            serde_json::to_vec(&InternedWorld::from_response(response)).map_err(|e| e.to_string())
+        }
+        WorldEncoding::Msgpack => {
+            rmp_serde::to_vec_named(&InternedWorld::from_response(response)).map_err(|e| e.to_string())
+        }
+    }
+}
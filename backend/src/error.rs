@@ -7,6 +7,8 @@ pub enum AppError {
     NotFound(String),
     #[error("{0}")]
     Unauthorized(String),
+    #[error("{0}")]
+    BadRequest(String),
     #[error("MongoDB error: {0}")]
     Database(#[from] mongodb::error::Error),
     #[error("MySQL error: {0}")]
@@ -26,6 +28,7 @@ impl IntoResponse for AppError {
         let (status, message) = match &self {
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
             AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg.clone()),
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
             AppError::Database(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             AppError::Sqlx(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             AppError::Git(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
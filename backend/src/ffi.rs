@@ -0,0 +1,454 @@
+use std::collections::{HashMap, HashSet};
+use tree_sitter::{Node, Tree};
+
+use crate::artifacts::{attach, push_unique, Anchor};
+use crate::languages::paths::{self, file_of, is_c_family};
+use crate::languages::parser_utils::{attribute_value, has_attribute, preceding_attributes, string_value, walk};
+use crate::languages::{java_parser, parser_utils};
+use crate::models::{GameEntity, Route, RouteType, SourceSpan};
+use crate::symbol_table::{Confidence, SymbolTable};
+
+/// Python calls that load a shared library: `ctypes.CDLL("libm.so.6")`,
+/// `ctypes.cdll.LoadLibrary(path)`, cffi `ffi.dlopen("foo")`.
+const LIBRARY_LOADERS: &[&str] = &["CDLL", "PyDLL", "WinDLL", "OleDLL", "LoadLibrary", "dlopen"];
+
+enum Finding {
+    /// A definition other languages can call: `#[no_mangle]`, PyO3 and wasm-bindgen items.
+    Export { abi: &'static str, symbol: String, kind: &'static str },
+    /// A function implemented in another language: Rust `extern` blocks, JNI `native` methods.
+    Import { abi: &'static str, symbol: String },
+    /// A shared library loaded through ctypes/cffi and the name it is bound to.
+    Library { library: String, binding: String },
+}
+
+struct Located {
+    finding: Finding,
+    anchor: Anchor,
+    span: SourceSpan,
+}
+
+/// Adds `ffi_export`, `ffi_import` and `ffi_library` Artifacts for the foreign
+/// function boundaries a file declares, placed under the Room or Building they
+/// describe (the exported function itself, the Room holding an `extern` block).
+pub fn annotate_ffi(ext: &str, tree: &Tree, source: &str, file_id: &str, entities: &mut Vec<GameEntity>) {
+    if !matches!(ext, "rs" | "py" | "java") {
+        return;
+    }
+
+    let mut findings = Vec::new();
+    let bytes = source.as_bytes();
+    let root = tree.root_node();
+    match ext {
+        "rs" => walk(root, &mut |n| rust_findings(n, bytes, file_id, &mut findings)),
+        "py" => walk(root, &mut |n| python_findings(n, bytes, file_id, &mut findings)),
+        _ => {
+            let package = java_parser::package_name(tree, source);
+            walk(root, &mut |n| java_findings(n, bytes, file_id, &package, &mut findings));
+        }
+    }
+
+    for located in findings {
+        let artifact_for = |owner_id: &str| to_artifact(&located, owner_id);
+        if !attach(entities, &located.anchor, &artifact_for) {
+            push_unique(entities, to_artifact(&located, file_id));
+        }
+    }
+}
+
+// --- Rust: #[no_mangle], PyO3, wasm-bindgen, extern blocks ---
+
+fn rust_findings(node: Node, source: &[u8], file_id: &str, out: &mut Vec<Located>) {
+    match node.kind() {
+        "function_item" | "struct_item" | "enum_item" => {
+            let attributes = preceding_attributes(node, source);
+            let Some(name) = node.child_by_field_name("name").map(|n| parser_utils::get_text(n, source)) else { return };
+            let is_fn = node.kind() == "function_item";
+
+            let export = if is_fn && attributes.iter().any(|a| a.contains("no_mangle") || a.contains("export_name")) {
+                Some(("c", attribute_value(&attributes, "export_name"), "function"))
+            } else if is_fn && has_attribute(&attributes, "pyfunction") {
+                Some(("python", attribute_value(&attributes, "name"), "function"))
+            } else if is_fn && has_attribute(&attributes, "pymodule") {
+                Some(("python", attribute_value(&attributes, "name"), "module"))
+            } else if !is_fn && has_attribute(&attributes, "pyclass") {
+                Some(("python", attribute_value(&attributes, "name"), "class"))
+            } else if has_attribute(&attributes, "wasm_bindgen") {
+                Some(("wasm", attribute_value(&attributes, "js_name"), if is_fn { "function" } else { "class" }))
+            } else {
+                None
+            };
+            if let Some((abi, renamed, kind)) = export {
+                let finding = Finding::Export { abi, symbol: renamed.unwrap_or(name), kind };
+                out.push(Located { finding, anchor: Anchor::StartsAt(node.start_byte()), span: parser_utils::span(node, file_id) });
+            }
+        }
+        "foreign_mod_item" => {
+            let Some(body) = node.child_by_field_name("body") else { return };
+            let mut cursor = body.walk();
+            for item in body.named_children(&mut cursor) {
+                if item.kind() != "function_signature_item" {
+                    continue;
+                }
+                let Some(name) = item.child_by_field_name("name").map(|n| parser_utils::get_text(n, source)) else { continue };
+                let symbol = attribute_value(&preceding_attributes(item, source), "link_name").unwrap_or(name);
+                let anchor = Anchor::Contains(item.start_byte(), item.end_byte());
+                out.push(Located { finding: Finding::Import { abi: "c", symbol }, anchor, span: parser_utils::span(item, file_id) });
+            }
+        }
+        _ => {}
+    }
+}
+
+// --- Python: ctypes / cffi library loads ---
+
+fn python_findings(node: Node, source: &[u8], file_id: &str, out: &mut Vec<Located>) {
+    if node.kind() != "assignment" {
+        return;
+    }
+    let (Some(left), Some(right)) = (node.child_by_field_name("left"), node.child_by_field_name("right")) else {
+        return;
+    };
+    if left.kind() != "identifier" || right.kind() != "call" {
+        return;
+    }
+    let Some(function) = right.child_by_field_name("function") else { return };
+    let function = parser_utils::get_text(function, source);
+    let loader = function.rsplit('.').next().unwrap_or(&function);
+    if !LIBRARY_LOADERS.contains(&loader) {
+        return;
+    }
+    let library = right
+        .child_by_field_name("arguments")
+        .and_then(|args| args.named_child(0))
+        .map(|arg| string_value(arg, source).unwrap_or_else(|| parser_utils::get_text(arg, source)))
+        .unwrap_or_default();
+    let finding = Finding::Library { library, binding: parser_utils::get_text(left, source) };
+    out.push(Located { finding, anchor: Anchor::Contains(node.start_byte(), node.end_byte()), span: parser_utils::span(node, file_id) });
+}
+
+// --- Java: JNI native methods ---
+
+fn java_findings(node: Node, source: &[u8], file_id: &str, package: &Option<String>, out: &mut Vec<Located>) {
+    if node.kind() != "method_declaration" {
+        return;
+    }
+    let is_native = node
+        .child_by_field_name("modifiers")
+        .or_else(|| node.named_child(0).filter(|n| n.kind() == "modifiers"))
+        .is_some_and(|m| parser_utils::get_text(m, source).split_whitespace().any(|w| w == "native"));
+    let Some(name) = node.child_by_field_name("name").map(|n| parser_utils::get_text(n, source)) else { return };
+    if !is_native {
+        return;
+    }
+
+    // Enclosing classes, outermost first; nested classes are `Outer$Inner`
+    let mut classes = Vec::new();
+    let mut parent = node.parent();
+    while let Some(p) = parent {
+        if matches!(p.kind(), "class_declaration" | "enum_declaration" | "interface_declaration")
+            && let Some(class) = p.child_by_field_name("name")
+        {
+            classes.push(parser_utils::get_text(class, source));
+        }
+        parent = p.parent();
+    }
+    classes.reverse();
+
+    let mut class_name = classes.join("$");
+    if let Some(package) = package {
+        class_name = format!("{package}.{class_name}");
+    }
+    let symbol = format!("Java_{}_{}", jni_mangle(&class_name), jni_mangle(&name));
+    out.push(Located {
+        finding: Finding::Import { abi: "jni", symbol },
+        anchor: Anchor::StartsAt(node.start_byte()),
+        span: parser_utils::span(node, file_id),
+    });
+}
+
+/// JNI short-name mangling: `.` separates, `_` -> `_1`, `$` -> `_00024`.
+fn jni_mangle(name: &str) -> String {
+    let mut mangled = String::new();
+    for c in name.chars() {
+        match c {
+            '.' | '/' => mangled.push('_'),
+            '_' => mangled.push_str("_1"),
+            ';' => mangled.push_str("_2"),
+            '[' => mangled.push_str("_3"),
+            c if c.is_ascii_alphanumeric() => mangled.push(c),
+            c => mangled.push_str(&format!("_0{:04x}", c as u32)),
+        }
+    }
+    mangled
+}
+
+fn to_artifact(located: &Located, owner_id: &str) -> GameEntity {
+    let (artifact_type, name, abi, metadata) = match &located.finding {
+        Finding::Export { abi, symbol, kind } => (
+            "ffi_export",
+            format!("export {symbol}"),
+            *abi,
+            HashMap::from([("symbol".to_string(), symbol.clone()), ("kind".to_string(), kind.to_string())]),
+        ),
+        Finding::Import { abi, symbol } => (
+            "ffi_import",
+            format!("extern {symbol}"),
+            *abi,
+            HashMap::from([("symbol".to_string(), symbol.clone())]),
+        ),
+        Finding::Library { library, binding } => (
+            "ffi_library",
+            format!("load {library}"),
+            "c",
+            HashMap::from([("library".to_string(), library.clone()), ("binding".to_string(), binding.clone())]),
+        ),
+    };
+    let mut metadata = metadata;
+    metadata.insert("abi".to_string(), abi.to_string());
+
+    GameEntity::Artifact {
+        id: format!("{owner_id}::{name}"),
+        name,
+        artifact_type: artifact_type.to_string(),
+        datatype: "ffi".to_string(),
+        is_mutable: false,
+        value_hint: None,
+        span: Some(located.span.clone()),
+        metadata: Some(metadata),
+    }
+}
+
+// --- Linking across cities ---
+
+struct Boundary {
+    owner_id: String,
+    artifact_type: String,
+    metadata: HashMap<String, String>,
+}
+
+impl Boundary {
+    fn get(&self, key: &str) -> &str {
+        self.metadata.get(key).map(String::as_str).unwrap_or_default()
+    }
+}
+
+/// Links the FFI Artifacts to the code on the other side of each boundary:
+/// Rust `extern` functions and JNI `native` methods to the C/C++ (or Rust
+/// `#[no_mangle]`) functions implementing them, unimplemented C prototypes to
+/// Rust exports, ctypes/cffi calls to exported C symbols, Python imports to
+/// PyO3 modules and items, and JS/TS named imports to wasm-bindgen exports.
+pub fn ffi_routes(cities: &[GameEntity], symbol_table: &SymbolTable) -> Vec<Route> {
+    let mut boundaries = Vec::new();
+    let mut python_calls = Vec::new();
+    for city in cities {
+        collect_ffi(city, "", &mut boundaries, &mut python_calls);
+    }
+    let exports = |abi: &'static str| boundaries.iter().filter(move |b| b.artifact_type == "ffi_export" && b.get("abi") == abi);
+
+    // C/C++ functions and Rust `#[no_mangle]` functions exported under `symbol`
+    let c_symbol = |symbol: &str| -> Vec<String> {
+        let mut targets: Vec<String> = symbol_table
+            .ids_named(symbol)
+            .iter()
+            .filter(|id| is_c_family(file_of(id)) && id.ends_with(&format!("::{symbol}")) && !symbol_table.is_declaration(id))
+            .cloned()
+            .collect();
+        targets.extend(exports("c").filter(|b| b.get("symbol") == symbol).map(|b| b.owner_id.clone()));
+        targets
+    };
+
+    let mut routes = Vec::new();
+    let mut linked = HashSet::new();
+    let mut push = |from: &str, targets: Vec<String>, abi: &str, symbol: &str, strategy: &str| {
+        let confidence = if targets.len() == 1 { Confidence::High } else { Confidence::Medium };
+        for to in targets {
+            // Only bridges between cities: C calling C is an ordinary call
+            if paths::same_family(file_of(from), file_of(&to)) || !linked.insert((from.to_string(), to.clone())) {
+                continue;
+            }
+            routes.push(Route {
+                id: String::new(),
+                from_id: from.to_string(),
+                to_id: to,
+                route_type: RouteType::Ffi,
+                bidirectional: false,
+                metadata: Some(serde_json::json!({
+                    "abi": abi,
+                    "symbol": symbol,
+                    "confidence": confidence,
+                    "strategy": strategy,
+                })),
+            });
+        }
+    };
+
+    for boundary in boundaries.iter().filter(|b| b.artifact_type == "ffi_import") {
+        let symbol = boundary.get("symbol");
+        match boundary.get("abi") {
+            "jni" => {
+                // Overloads append the mangled signature: `Java_pkg_Class_method__I`
+                let mut targets = c_symbol(symbol);
+                let overload = format!("{symbol}__");
+                targets.extend(exports("c").filter(|b| b.get("symbol").starts_with(&overload)).map(|b| b.owner_id.clone()));
+                push(&boundary.owner_id, targets, "jni", symbol, "jni_name");
+            }
+            abi => push(&boundary.owner_id, c_symbol(symbol), abi, symbol, "extern_block"),
+        }
+    }
+
+    // C prototypes with no C definition, implemented by a Rust export
+    let mut declarations: Vec<&String> = symbol_table.declarations().filter(|id| is_c_family(file_of(id))).collect();
+    declarations.sort_unstable();
+    for declaration in declarations {
+        let Some((_, symbol)) = declaration.split_once("::") else { continue };
+        let targets: Vec<String> = exports("c").filter(|b| b.get("symbol") == symbol).map(|b| b.owner_id.clone()).collect();
+        push(declaration, targets, "c", symbol, "no_mangle");
+    }
+
+    // ctypes / cffi: `lib = ctypes.CDLL(...)` then `lib.symbol(...)`
+    for library in boundaries.iter().filter(|b| b.artifact_type == "ffi_library") {
+        let file = file_of(&library.owner_id);
+        let prefix = format!("{}.", library.get("binding"));
+        for (room_id, call) in &python_calls {
+            if file_of(room_id) == file
+                && let Some(symbol) = call.strip_prefix(&prefix)
+                && !symbol.contains('.')
+            {
+                push(room_id, c_symbol(symbol), "c", symbol, "ctypes");
+            }
+        }
+    }
+
+    // Python imports of a PyO3 module (`import native`, `from pkg.native import parse`)
+    let mut python_files: Vec<&String> = symbol_table.files().filter(|f| f.ends_with(".py")).collect();
+    python_files.sort_unstable();
+    for file in python_files {
+        for import in symbol_table.imports_of(file) {
+            let (module, name) = match import.split_once("::") {
+                Some((module, name)) => (module, Some(name)),
+                None => (import.as_str(), None),
+            };
+            let module_name = module.rsplit('.').next().unwrap_or(module);
+            for module in exports("python").filter(|b| b.get("kind") == "module" && b.get("symbol") == module_name) {
+                let item = name.filter(|n| *n != "*").and_then(|name| {
+                    let mut items = exports("python").filter(|b| b.get("kind") != "module" && b.get("symbol") == name);
+                    let same_crate = items.clone().find(|b| crate_dir(file_of(&b.owner_id)) == crate_dir(file_of(&module.owner_id)));
+                    same_crate.or_else(|| items.next())
+                });
+                let (target, symbol) = match item {
+                    Some(item) => (item.owner_id.clone(), item.get("symbol")),
+                    None => (module.owner_id.clone(), module.get("symbol")),
+                };
+                push(file, vec![target], "python", symbol, "pyo3_module");
+            }
+        }
+    }
+
+    // JS/TS named imports from a wasm-pack package (`import { greet } from "../pkg/engine"`)
+    let mut js_files: Vec<&String> = symbol_table
+        .files()
+        .filter(|f| matches!(f.rsplit('.').next(), Some("js" | "jsx" | "ts" | "tsx")))
+        .collect();
+    js_files.sort_unstable();
+    for file in js_files {
+        for import in symbol_table.imports_of(file) {
+            let Some((specifier, name)) = import.split_once("::") else { continue };
+            // `../pkg/engine.js`, `engine`, or the crate's own `engine/pkg` directory
+            let package = specifier.rsplit('/').find(|s| !s.is_empty() && *s != "pkg").unwrap_or(specifier);
+            let package = package.split('.').next().unwrap_or(package).trim_end_matches("_bg").replace('-', "_");
+            let targets: Vec<String> = exports("wasm")
+                .filter(|b| b.get("symbol") == name && crate_dir(file_of(&b.owner_id)).replace('-', "_") == package)
+                .map(|b| b.owner_id.clone())
+                .collect();
+            push(file, targets, "wasm", name, "wasm_bindgen");
+        }
+    }
+
+    routes
+}
+
+fn collect_ffi(entity: &GameEntity, owner_id: &str, boundaries: &mut Vec<Boundary>, python_calls: &mut Vec<(String, String)>) {
+    match entity {
+        GameEntity::Artifact { artifact_type, metadata: Some(metadata), .. } if artifact_type.starts_with("ffi_") => {
+            boundaries.push(Boundary { owner_id: owner_id.to_string(), artifact_type: artifact_type.clone(), metadata: metadata.clone() });
+        }
+        GameEntity::Artifact { .. } => {}
+        GameEntity::City { children, .. } | GameEntity::District { children, .. } => {
+            for child in children {
+                collect_ffi(child, owner_id, boundaries, python_calls);
+            }
+        }
+        GameEntity::Room { id, children, calls, .. } => {
+            if file_of(id).ends_with(".py") {
+                python_calls.extend(calls.iter().map(|call| (id.clone(), call.clone())));
+            }
+            for child in children {
+                collect_ffi(child, id, boundaries, python_calls);
+            }
+        }
+        GameEntity::Building { id, children, .. } => {
+            for child in children {
+                collect_ffi(child, id, boundaries, python_calls);
+            }
+        }
+    }
+}
+
+/// The directory of the crate a Rust file belongs to (`crates/engine/src/lib.rs` -> `engine`).
+fn crate_dir(file: &str) -> &str {
+    let root = file.split_once("/src/").map(|(root, _)| root).unwrap_or("");
+    root.rsplit('/').next().unwrap_or(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn file(path: &str, source: &str) -> GameEntity {
+        test_support::annotated_file(path, source, annotate_ffi)
+    }
+
+    #[test]
+    fn test_links_boundaries_between_cities() {
+        let files = vec![
+            file("native/src/lib.rs", r#"
+extern "C" { fn crc32(data: *const u8, len: usize) -> u32; }
+#[no_mangle]
+pub extern "C" fn rs_hash(x: u32) -> u32 { x }
+#[pyfunction]
+fn parse(s: &str) -> usize { s.len() }
+#[pymodule]
+fn native(m: &PyModule) -> PyResult<()> { Ok(()) }
+#[no_mangle]
+pub extern "system" fn Java_com_acme_Codec_encode(env: JNIEnv) {}
+"#),
+            file("c/crc.c", "unsigned crc32(const char *data, unsigned long len) { return 0; }\n"),
+            file("c/hash.h", "unsigned rs_hash(unsigned x);\n"),
+            file("py/app.py", "import ctypes\nfrom native import parse\nlib = ctypes.CDLL(\"libcrc.so\")\ndef run():\n    return lib.crc32(b\"x\", 1)\n"),
+            file("java/com/acme/Codec.java", "package com.acme;\nclass Codec { native void encode(); }\n"),
+            file("engine/src/lib.rs", "#[wasm_bindgen]\npub fn greet() {}\n"),
+            file("widgets/src/lib.rs", "#[wasm_bindgen]\npub fn greet() {}\n"),
+            file("web/app.ts", "import { greet } from '../engine/pkg';\nimport { greet as hi } from './pkg/engine_bg.js';\n"),
+        ];
+        let mut table = SymbolTable::new();
+        table.index_cities(&files);
+
+        let mut links: Vec<(String, String, String)> = ffi_routes(&files, &table)
+            .into_iter()
+            .map(|r| (r.from_id, r.to_id, r.metadata.unwrap()["strategy"].as_str().unwrap().to_string()))
+            .collect();
+        links.sort();
+        let expected = [
+            ("c/hash.h::rs_hash", "native/src/lib.rs::rs_hash", "no_mangle"),
+            ("java/com/acme/Codec.java::Codec::encode", "native/src/lib.rs::Java_com_acme_Codec_encode", "jni_name"),
+            ("native/src/lib.rs", "c/crc.c::crc32", "extern_block"),
+            ("py/app.py", "native/src/lib.rs::parse", "pyo3_module"),
+            ("py/app.py::run", "c/crc.c::crc32", "ctypes"),
+            ("web/app.ts", "engine/src/lib.rs::greet", "wasm_bindgen"),
+        ];
+        let expected: Vec<(String, String, String)> =
+            expected.iter().map(|(a, b, c)| (a.to_string(), b.to_string(), c.to_string())).collect();
+        assert_eq!(links, expected);
+    }
+}
@@ -1,12 +1,22 @@
-use chrono::{TimeZone, Utc};
-use git2::{FetchOptions, Repository};
+use chrono::{Datelike, TimeZone, Utc};
+use git2::{FetchOptions, Oid, Repository, Sort};
 use std::collections::HashMap;
 use std::path::Path;
 
+use crate::models::SampleStrategy;
+
 pub struct GitLayer {
     repo: Option<Repository>,
 }
 
+/// A commit picked from the default branch for a timeline frame.
+#[derive(Debug, Clone)]
+pub struct CommitSample {
+    pub hash: String,
+    pub committed_at: i64,
+    pub label: Option<String>,
+}
+
 impl GitLayer {
     pub fn new(repo_path: &Path) -> Self {
         let repo = match Repository::open(repo_path) {
@@ -33,6 +43,117 @@ impl GitLayer {
         builder.clone(url, dest)
     }
 
+    /// Performs a full clone (history and tags) for timeline sampling.
+    pub fn full_clone(url: &str, dest: &Path) -> Result<Repository, git2::Error> {
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.download_tags(git2::AutotagOption::All);
+
+        let mut builder = git2::build::RepoBuilder::new();
+        builder.fetch_options(fetch_options);
+        builder.clone(url, dest)
+    }
+
+    /// Walks the first-parent history of HEAD (oldest first) and picks the
+    /// commits matching `strategy`, thinned evenly down to `max_samples`.
+    /// The tip commit is always the last sample.
+    pub fn sample_commits(
+        &self,
+        strategy: &SampleStrategy,
+        max_samples: usize,
+    ) -> Result<Vec<CommitSample>, git2::Error> {
+        let Some(repo) = self.repo.as_ref() else {
+            return Ok(Vec::new());
+        };
+
+        let mut walk = repo.revwalk()?;
+        walk.push_head()?;
+        walk.simplify_first_parent()?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+
+        let history: Vec<(Oid, i64)> = walk
+            .filter_map(Result::ok)
+            .filter_map(|oid| repo.find_commit(oid).ok().map(|c| (oid, c.time().seconds())))
+            .collect();
+
+        let Some(&(tip, tip_time)) = history.last() else {
+            return Ok(Vec::new());
+        };
+
+        let mut samples: Vec<CommitSample> = match strategy {
+            SampleStrategy::Tags => {
+                let tags = self.tags_by_commit()?;
+                history
+                    .iter()
+                    .filter_map(|(oid, time)| {
+                        tags.get(oid).map(|label| CommitSample {
+                            hash: oid.to_string(),
+                            committed_at: *time,
+                            label: Some(label.clone()),
+                        })
+                    })
+                    .collect()
+            }
+            SampleStrategy::EveryCommits { every } => history
+                .iter()
+                .step_by((*every).max(1) as usize)
+                .map(|(oid, time)| CommitSample { hash: oid.to_string(), committed_at: *time, label: None })
+                .collect(),
+            SampleStrategy::Monthly => {
+                let mut last_month = None;
+                history
+                    .iter()
+                    .filter_map(|(oid, time)| {
+                        // Commits with an out-of-range timestamp are skipped
+                        let date = Utc.timestamp_opt(*time, 0).single()?;
+                        let month = Some((date.year(), date.month()));
+                        let is_new = month != last_month;
+                        last_month = month;
+                        is_new.then(|| CommitSample {
+                            hash: oid.to_string(),
+                            committed_at: *time,
+                            label: Some(date.format("%Y-%m").to_string()),
+                        })
+                    })
+                    .collect()
+            }
+        };
+
+        if samples.last().map(|s| s.hash.as_str()) != Some(tip.to_string().as_str()) {
+            samples.push(CommitSample { hash: tip.to_string(), committed_at: tip_time, label: Some("HEAD".into()) });
+        }
+
+        Ok(thin_samples(samples, max_samples))
+    }
+
+    /// Force-checks out `hash` into the working directory with a detached HEAD.
+    pub fn checkout_commit(&self, hash: &str) -> Result<(), git2::Error> {
+        let Some(repo) = self.repo.as_ref() else {
+            return Err(git2::Error::from_str("repository not opened"));
+        };
+
+        let commit = repo.find_commit(Oid::from_str(hash)?)?;
+        let mut checkout = git2::build::CheckoutBuilder::new();
+        checkout.force().remove_untracked(true);
+        repo.checkout_tree(commit.as_object(), Some(&mut checkout))?;
+        repo.set_head_detached(commit.id())
+    }
+
+    fn tags_by_commit(&self) -> Result<HashMap<Oid, String>, git2::Error> {
+        let mut tags = HashMap::new();
+        let Some(repo) = self.repo.as_ref() else {
+            return Ok(tags);
+        };
+
+        for name in repo.tag_names(None)?.iter().flatten() {
+            if let Ok(reference) = repo.find_reference(&format!("refs/tags/{name}"))
+                && let Ok(commit) = reference.peel_to_commit()
+            {
+                tags.insert(commit.id(), name.to_string());
+            }
+        }
+        Ok(tags)
+    }
+
     /// Returns metadata for the tip commit (the only commit after a shallow clone).
     /// Used instead of per-file blame since shallow clones have no history.
     pub fn get_tip_metadata(&self) -> Option<HashMap<String, String>> {
@@ -56,8 +177,9 @@ impl GitLayer {
         let message = commit.message().unwrap_or("").trim().to_string();
         metadata.insert("last_commit_message".to_string(), message);
 
-        let time = Utc.timestamp_opt(commit.time().seconds(), 0).unwrap();
-        metadata.insert("last_modified".to_string(), time.to_rfc3339());
+        if let Some(time) = Utc.timestamp_opt(commit.time().seconds(), 0).single() {
+            metadata.insert("last_modified".to_string(), time.to_rfc3339());
+        }
 
         metadata.insert("commit_hash".to_string(), commit.id().to_string());
 
@@ -68,3 +190,88 @@ impl GitLayer {
         self.get_tip_metadata()
     }
 }
+
+/// Keeps at most `max` samples, spread evenly and always including the last one.
+fn thin_samples(samples: Vec<CommitSample>, max: usize) -> Vec<CommitSample> {
+    if max == 0 || samples.len() <= max {
+        return samples;
+    }
+    let last = samples.len() - 1;
+    let step = last as f64 / (max - 1).max(1) as f64;
+    let picks: Vec<usize> = (0..max).map(|i| last - ((i as f64 * step).round() as usize).min(last)).collect();
+    samples
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| picks.contains(i))
+        .map(|(_, s)| s)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    /// A repository with one commit per entry of `commits` (Unix time, tag).
+    fn repo_with_history(dir: &Path, commits: &[(i64, Option<&str>)]) {
+        let repo = Repository::init(dir).unwrap();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let mut parent = None;
+        for (i, (time, tag)) in commits.iter().enumerate() {
+            let signature = git2::Signature::new("dev", "dev@example.com", &git2::Time::new(*time, 0)).unwrap();
+            let parents: Vec<&git2::Commit> = parent.iter().collect();
+            let oid = repo.commit(Some("HEAD"), &signature, &signature, &format!("commit {i}"), &tree, &parents).unwrap();
+            let commit = repo.find_commit(oid).unwrap();
+            if let Some(tag) = tag {
+                repo.tag_lightweight(tag, commit.as_object(), false).unwrap();
+            }
+            parent = Some(commit);
+        }
+    }
+
+    fn labels(samples: &[CommitSample]) -> Vec<Option<&str>> {
+        samples.iter().map(|s| s.label.as_deref()).collect()
+    }
+
+    #[test]
+    fn test_sample_commits_per_strategy() {
+        let dir = tempfile::tempdir().unwrap();
+        // 2024-01-01, 2024-01-15, 2024-02-01, 2024-03-01, 2024-03-02
+        let start = 1_704_067_200;
+        repo_with_history(dir.path(), &[
+            (start, Some("v0.1")),
+            (start + 14 * DAY, None),
+            (start + 31 * DAY, Some("v0.2")),
+            (start + 60 * DAY, None),
+            (start + 61 * DAY, None),
+        ]);
+        let git = GitLayer::new(dir.path());
+
+        let monthly = git.sample_commits(&SampleStrategy::Monthly, 10).unwrap();
+        assert_eq!(labels(&monthly), [Some("2024-01"), Some("2024-02"), Some("2024-03"), Some("HEAD")]);
+        assert_eq!(monthly[0].committed_at, start);
+
+        let tags = git.sample_commits(&SampleStrategy::Tags, 10).unwrap();
+        assert_eq!(labels(&tags), [Some("v0.1"), Some("v0.2"), Some("HEAD")]);
+
+        let every = git.sample_commits(&SampleStrategy::EveryCommits { every: 2 }, 10).unwrap();
+        assert_eq!(every.iter().map(|s| s.committed_at).collect::<Vec<_>>(), [start, start + 31 * DAY, start + 61 * DAY]);
+        assert_eq!(labels(&every), [None, None, None]);
+
+        let thinned = git.sample_commits(&SampleStrategy::EveryCommits { every: 1 }, 2).unwrap();
+        assert_eq!(thinned.iter().map(|s| s.committed_at).collect::<Vec<_>>(), [start, start + 61 * DAY]);
+    }
+
+    #[test]
+    fn test_thin_samples_keeps_ends_and_spreads_the_rest() {
+        let samples: Vec<CommitSample> =
+            (0..10).map(|i| CommitSample { hash: i.to_string(), committed_at: i, label: None }).collect();
+        let hashes = |samples: Vec<CommitSample>| samples.into_iter().map(|s| s.hash).collect::<Vec<_>>();
+
+        assert_eq!(hashes(thin_samples(samples.clone(), 4)), ["0", "3", "6", "9"]);
+        assert_eq!(hashes(thin_samples(samples.clone(), 1)), ["9"]);
+        assert_eq!(hashes(thin_samples(samples.clone(), 0)).len(), 10);
+        assert_eq!(hashes(thin_samples(samples[..3].to_vec(), 5)), ["0", "1", "2"]);
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::models::{ImpactNode, Route};
+
+/// Which way to walk the call graph from an entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Towards the code that calls the entity.
+    Callers,
+    /// Towards the code the entity calls.
+    Callees,
+}
+
+/// Adjacency over a world's routes (normally its FunctionCall routes).
+///
+/// An entity stands for itself and everything inside it: asking about a
+/// file Building or a class covers calls made from or into its Rooms.
+pub struct CallGraph<'a> {
+    routes: &'a [Route],
+    outgoing: HashMap<&'a str, Vec<usize>>,
+    incoming: HashMap<&'a str, Vec<usize>>,
+}
+
+impl<'a> CallGraph<'a> {
+    pub fn new(routes: &'a [Route]) -> Self {
+        let mut outgoing: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut incoming: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, route) in routes.iter().enumerate() {
+            outgoing.entry(route.from_id.as_str()).or_default().push(i);
+            incoming.entry(route.to_id.as_str()).or_default().push(i);
+        }
+        Self { routes, outgoing, incoming }
+    }
+
+    /// Graph nodes that are `entity_id` or live inside it.
+    fn members(&self, entity_id: &str) -> HashSet<&'a str> {
+        let prefix = format!("{entity_id}::");
+        self.outgoing
+            .keys()
+            .chain(self.incoming.keys())
+            .copied()
+            .filter(|id| *id == entity_id || id.starts_with(&prefix))
+            .collect()
+    }
+
+    fn edges(&self, node: &str, direction: Direction) -> impl Iterator<Item = &'a Route> + '_ {
+        let adjacency = match direction {
+            Direction::Callers => &self.incoming,
+            Direction::Callees => &self.outgoing,
+        };
+        adjacency.get(node).into_iter().flatten().map(|&i| &self.routes[i])
+    }
+
+    /// Routes into (`Callers`) or out of (`Callees`) the entity, leaving out
+    /// calls between two of its own members.
+    pub fn neighbors(&self, entity_id: &str, direction: Direction) -> Vec<&'a Route> {
+        let members = self.members(entity_id);
+        let mut member_ids: Vec<&str> = members.iter().copied().collect();
+        member_ids.sort_unstable();
+        member_ids
+            .into_iter()
+            .flat_map(|node| self.edges(node, direction))
+            .filter(|route| !members.contains(other_end(route, direction)))
+            .collect()
+    }
+
+    /// Everything reachable from the entity in `direction` within `max_depth`
+    /// hops, nearest first. Returns the nodes and whether `max_nodes` cut the walk short.
+    pub fn impact(&self, entity_id: &str, direction: Direction, max_depth: u32, max_nodes: usize) -> (Vec<ImpactNode>, bool) {
+        let members = self.members(entity_id);
+        let mut visited: HashSet<&str> = members.clone();
+        let mut queue: VecDeque<(&str, u32)> = sorted(&members).into_iter().map(|id| (id, 0)).collect();
+        let mut nodes = Vec::new();
+
+        while let Some((node, depth)) = queue.pop_front() {
+            if depth >= max_depth {
+                continue;
+            }
+            for route in self.edges(node, direction) {
+                let next = other_end(route, direction);
+                if !visited.insert(next) {
+                    continue;
+                }
+                if nodes.len() >= max_nodes {
+                    return (nodes, true);
+                }
+                nodes.push(ImpactNode { entity_id: next.to_string(), depth: depth + 1, via: route.id.clone() });
+                queue.push_back((next, depth + 1));
+            }
+        }
+        (nodes, false)
+    }
+
+    /// The fewest routes leading from `from` to `to` (either may be a container),
+    /// following call direction, or None when `to` is not reachable in `max_depth` hops.
+    pub fn shortest_path(&self, from: &str, to: &str, max_depth: u32) -> Option<Vec<&'a Route>> {
+        let sources = self.members(from);
+        let targets = self.members(to);
+        if sources.is_empty() || targets.is_empty() {
+            return None;
+        }
+
+        let mut reached_by: HashMap<&str, &Route> = HashMap::new();
+        let mut visited: HashSet<&str> = sources.clone();
+        let mut frontier = sorted(&sources);
+        for _ in 0..max_depth {
+            let mut next_frontier = Vec::new();
+            for node in frontier {
+                for route in self.edges(node, Direction::Callees) {
+                    let next = route.to_id.as_str();
+                    if !visited.insert(next) {
+                        continue;
+                    }
+                    reached_by.insert(next, route);
+                    if targets.contains(next) {
+                        return Some(walk_back(next, &reached_by, &sources));
+                    }
+                    next_frontier.push(next);
+                }
+            }
+            if next_frontier.is_empty() {
+                break;
+            }
+            frontier = next_frontier;
+        }
+        None
+    }
+}
+
+fn other_end(route: &Route, direction: Direction) -> &str {
+    match direction {
+        Direction::Callers => &route.from_id,
+        Direction::Callees => &route.to_id,
+    }
+}
+
+fn sorted<'a>(ids: &HashSet<&'a str>) -> Vec<&'a str> {
+    let mut ids: Vec<&str> = ids.iter().copied().collect();
+    ids.sort_unstable();
+    ids
+}
+
+fn walk_back<'a>(mut node: &'a str, reached_by: &HashMap<&str, &'a Route>, sources: &HashSet<&str>) -> Vec<&'a Route> {
+    let mut path = Vec::new();
+    while !sources.contains(node) {
+        let route = reached_by[node];
+        path.push(route);
+        node = &route.from_id;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RouteType;
+
+    fn call(id: &str, from: &str, to: &str) -> Route {
+        Route { id: id.into(), from_id: from.into(), to_id: to.into(), route_type: RouteType::FunctionCall, bidirectional: false, metadata: None }
+    }
+
+    #[test]
+    fn test_impact_and_paths_follow_call_direction() {
+        let routes = vec![
+            call("r0", "src/main.rs::main", "src/parser.rs::generate_world"),
+            call("r1", "src/main.rs::main", "src/db/world.rs::connect"),
+            call("r2", "src/parser.rs::generate_world", "src/parser.rs::parse_single_file"),
+            call("r3", "src/services/parse.rs::run", "src/db/world.rs::store_world"),
+            call("r4", "src/parser.rs::generate_world", "src/services/parse.rs::run"),
+        ];
+        let graph = CallGraph::new(&routes);
+
+        let path: Vec<&str> = graph.shortest_path("src/main.rs::main", "src/db/world.rs::store_world", 10).unwrap().iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(path, ["r0", "r4", "r3"]);
+        assert!(graph.shortest_path("src/db/world.rs::store_world", "src/main.rs::main", 10).is_none());
+        assert!(graph.shortest_path("src/main.rs::main", "src/db/world.rs::store_world", 2).is_none());
+
+        // A file stands for its Rooms; calls inside it are not its callees
+        let callees: Vec<&str> = graph.neighbors("src/parser.rs", Direction::Callees).iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(callees, ["r4"]);
+
+        let (callers, truncated) = graph.impact("src/db/world.rs::store_world", Direction::Callers, 10, 100);
+        let callers: Vec<(&str, u32)> = callers.iter().map(|n| (n.entity_id.as_str(), n.depth)).collect();
+        assert_eq!(callers, [("src/services/parse.rs::run", 1), ("src/parser.rs::generate_world", 2), ("src/main.rs::main", 3)]);
+        assert!(!truncated);
+        assert!(graph.impact("src/main.rs::main", Direction::Callees, 10, 2).1);
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
-use crate::models::{GameEntity, Route};
+use crate::languages::paths;
+use crate::models::GameEntity;
 use crate::parser::ParsedFile;
 
 struct DirNode {
@@ -22,32 +23,134 @@ impl DirNode {
             children.push(subdir.to_entity());
         }
         GameEntity::District {
-            id: format!("district_{}", self.path.replace('/', "_")),
+            id: district_id(&self.path),
             name: self.name.clone(),
             path: self.path.clone(),
             children,
+            metadata: None,
         }
     }
 }
 
+/// The file Building, District and City an entity sits in.
+#[derive(Debug, Default, Clone)]
+pub struct Owners {
+    pub file: Option<String>,
+    pub district: Option<String>,
+    pub city: Option<String>,
+}
+
+/// Owners of every entity in `cities`, keyed by entity id. A District maps
+/// to itself; namespaces (Districts inside a file) do not move their contents.
+pub fn index_owners(cities: &[GameEntity]) -> HashMap<String, Owners> {
+    fn visit(entity: &GameEntity, mut owners: Owners, index: &mut HashMap<String, Owners>) {
+        let (id, children) = match entity {
+            GameEntity::City { id, children, .. } => {
+                owners.city = Some(id.clone());
+                (id, children.as_slice())
+            }
+            GameEntity::District { id, children, .. } => {
+                if owners.file.is_none() {
+                    owners.district = Some(id.clone());
+                }
+                (id, children.as_slice())
+            }
+            GameEntity::Building { id, children, .. } => {
+                owners.file.get_or_insert_with(|| id.clone());
+                (id, children.as_slice())
+            }
+            GameEntity::Room { id, children, .. } => (id, children.as_slice()),
+            GameEntity::Artifact { id, .. } => (id, &[][..]),
+        };
+        for child in children {
+            visit(child, owners.clone(), index);
+        }
+        index.insert(id.clone(), owners);
+    }
+
+    let mut index = HashMap::new();
+    for city in cities {
+        visit(city, Owners::default(), &mut index);
+    }
+    index
+}
+
+/// Path of every District in `cities`, keyed by District id.
+pub fn district_paths(cities: &[GameEntity]) -> HashMap<String, String> {
+    fn visit(entity: &GameEntity, paths: &mut HashMap<String, String>) {
+        match entity {
+            GameEntity::District { id, path, children, .. } => {
+                paths.insert(id.clone(), path.clone());
+                children.iter().for_each(|child| visit(child, paths));
+            }
+            GameEntity::City { children, .. } | GameEntity::Building { children, .. } => {
+                children.iter().for_each(|child| visit(child, paths));
+            }
+            GameEntity::Room { .. } | GameEntity::Artifact { .. } => {}
+        }
+    }
+
+    let mut paths = HashMap::new();
+    cities.iter().for_each(|city| visit(city, &mut paths));
+    paths
+}
+
+/// Id of the District for the directory `path` (`"src/db"` -> `"district_src_db"`).
+pub fn district_id(path: &str) -> String {
+    format!("district_{}", path.replace('/', "_"))
+}
+
+/// Path of the District holding a Java `package` declared by a file in `dir`:
+/// the directory itself when it ends with the package path (Maven's
+/// `src/main/java/com/example`), else the package path under `dir`.
+pub fn package_path(dir: &str, package: &str) -> String {
+    let package = package.replace('.', "/");
+    if dir.is_empty() {
+        package
+    } else if dir == package || dir.ends_with(&format!("/{package}")) {
+        dir.to_string()
+    } else {
+        format!("{dir}/{package}")
+    }
+}
+
+/// (name, path) of each District from the city root down to the file at `id`.
+/// Files declaring a package sit under their source root, collapsed into one
+/// District, then one District per package level (`com`, `com.example`, ...).
+fn district_chain(id: &str, package: Option<&String>) -> Vec<(String, String)> {
+    let dir = paths::dir_of(id);
+    let Some(package) = package else {
+        let parts: Vec<&str> = dir.split('/').filter(|p| !p.is_empty()).collect();
+        return (0..parts.len()).map(|i| (parts[i].to_string(), parts[..=i].join("/"))).collect();
+    };
+
+    let path = package_path(dir, package);
+    let levels: Vec<&str> = package.split('.').collect();
+    let source_root = path[..path.len() - package.len()].trim_end_matches('/');
+    let mut chain = Vec::new();
+    if !source_root.is_empty() {
+        chain.push((source_root.to_string(), source_root.to_string()));
+    }
+    for i in 0..levels.len() {
+        let package_dir = levels[..=i].join("/");
+        let path = if source_root.is_empty() { package_dir } else { format!("{source_root}/{package_dir}") };
+        chain.push((levels[..=i].join("."), path));
+    }
+    chain
+}
+
 pub fn reconstruct_hierarchy(files: Vec<ParsedFile>) -> Vec<GameEntity> {
     let mut root = DirNode::new("root".to_string(), "".to_string());
 
     for file in files {
-        if let GameEntity::Building { id, .. } = &file.entity {
-            let parts: Vec<&str> = id.split('/').collect();
+        if let GameEntity::Building { id, metadata, .. } = &file.entity {
+            let package = metadata.as_ref().and_then(|m| m.get("package"));
             let mut current_node = &mut root;
-            let mut current_path = String::new();
-
-            if parts.len() > 1 {
-                for &part in &parts[..parts.len() - 1] {
-                    if !current_path.is_empty() { current_path.push('/'); }
-                    current_path.push_str(part);
-                    current_node = current_node
-                        .subdirs
-                        .entry(part.to_string())
-                        .or_insert_with(|| DirNode::new(part.to_string(), current_path.clone()));
-                }
+            for (name, path) in district_chain(id, package) {
+                current_node = current_node
+                    .subdirs
+                    .entry(path.clone())
+                    .or_insert_with(|| DirNode::new(name, path));
             }
             current_node.files.push(file.entity);
         }
@@ -77,10 +180,3 @@ pub fn find_entry_point(children: &[GameEntity], _lang: &str) -> Option<String>
     }
     None
 }
-
-pub fn calculate_complexity_score(buildings: u32, rooms: u32, routes: &[Route]) -> f32 {
-    let building_score = (buildings as f32 / 10.0).min(3.0);
-    let room_score = (rooms as f32 / 50.0).min(4.0);
-    let route_score = (routes.len() as f32 / 100.0).min(3.0);
-    (building_score + room_score + route_score).clamp(1.0, 10.0)
-}
@@ -4,16 +4,10 @@ use super::traits::LanguageParser;
 use super::registry::CParser;
 
 use tracing::{debug, instrument, trace};
-use tree_sitter::{Node, Parser};
+use tree_sitter::{Node, Tree};
 
 impl LanguageParser for CParser {
-    fn parse(source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
-        let mut parser = Parser::new();
-        parser
-            .set_language(tree_sitter_c::language())
-            .expect("Error loading C grammar");
-
-        let tree = parser.parse(source, None).unwrap();
+    fn parse(tree: &Tree, source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
         let mut imports = Vec::new();
         let entities = parse_node(tree.root_node(), source.as_bytes(), parent_id, &mut imports);
         (entities, imports)
@@ -73,9 +67,7 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
         let kind = child.kind();
         match kind {
             "preproc_include" => {
-                let text = parser_utils::get_text(child, source);
-                let header = text.replace("#include", "").trim_matches(|c: char| c.is_whitespace() || c == '"' || c == '<' || c == '>').to_string();
-                if !header.is_empty() { imports.push(header); }
+                if let Some(header) = parser_utils::c_include(child, source) { imports.push(header); }
             }
 
             "struct_specifier" | "union_specifier" | "enum_specifier" => {
@@ -87,11 +79,13 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
                 let building_type = match kind { "enum_specifier" => "enum", "union_specifier" => "union", _ => "struct" };
 
                 debug!(name = %name, kind = "Building", "Found {building_type}");
-                entities.push(GameEntity::Building { id, name, building_type: building_type.to_string(), is_public: true, loc, imports: vec![], children, metadata: None });
+                entities.push(GameEntity::Building { id, name, building_type: building_type.to_string(), is_public: true, is_test: false, loc, imports: vec![], supertypes: vec![], children, span: Some(parser_utils::span(child, parent_id)), metadata: None });
             }
 
             "function_definition" | "declaration" => {
-                let name = child.child_by_field_name("declarator").map(|n| parser_utils::get_text(n, source)).unwrap_or_else(|| "fn".into());
+                // `int *make(void)` is named `make`, not `*make`
+                let declarator = parser_utils::c_function_declarator(child).and_then(|f| f.child_by_field_name("declarator")).or_else(|| child.child_by_field_name("declarator"));
+                let name = declarator.map(|n| parser_utils::get_text(n, source)).unwrap_or_else(|| "fn".into());
                 let clean_name = name.split('(').next().unwrap_or(&name).trim().to_string();
                 if clean_name.is_empty() || clean_name == "{" { continue; }
 
@@ -102,11 +96,12 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
                 let complexity = parser_utils::calculate_complexity(child, C_COMPLEXITY_KINDS);
 
                 let body = child.child_by_field_name("body");
+                let metadata = if kind == "declaration" && parser_utils::c_function_declarator(child).is_some() { parser_utils::declaration_metadata() } else { None };
                 let calls = body.map(|b| parser_utils::extract_function_calls(b, source, "call_expression", is_builtin)).unwrap_or_default();
                 let children = body.map(|b| parse_node(b, source, &id, imports)).unwrap_or_default();
 
                 trace!(name = %clean_name, kind = "Room", "Found function");
-                entities.push(GameEntity::Room { id, name: clean_name, room_type: "function".to_string(), is_main: false, is_async: false, visibility: "public".to_string(), complexity, loc, parameters, return_type, calls, children, metadata: None });
+                entities.push(GameEntity::Room { id, name: clean_name, room_type: "function".to_string(), is_main: false, is_async: false, is_test: false, visibility: "public".to_string(), complexity, loc, parameters, return_type, calls, children, span: Some(parser_utils::span(child, parent_id)), metadata });
             }
 
             "field_declaration" => {
@@ -114,31 +109,31 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
                     let name = parser_utils::get_text(declarator, source);
                     let id = format!("{parent_id}::{name}");
                     let datatype = child.child_by_field_name("type").map(|t| parser_utils::get_text(t, source)).unwrap_or_else(|| "int".to_string());
-                    entities.push(GameEntity::Artifact { id, name, artifact_type: "field".to_string(), datatype, is_mutable: true, value_hint: None, metadata: None });
+                    entities.push(GameEntity::Artifact { id, name, artifact_type: "field".to_string(), datatype, is_mutable: true, value_hint: None, span: Some(parser_utils::span(child, parent_id)), metadata: None });
                 }
             }
 
             "enumerator" => {
                 let name = child.child_by_field_name("name").map(|n| parser_utils::get_text(n, source)).unwrap_or_else(|| "enumerator".into());
                 let id = format!("{parent_id}::{name}");
-                entities.push(GameEntity::Artifact { id, name, artifact_type: "enum_value".to_string(), datatype: "enum".to_string(), is_mutable: false, value_hint: None, metadata: None });
+                entities.push(GameEntity::Artifact { id, name, artifact_type: "enum_value".to_string(), datatype: "enum".to_string(), is_mutable: false, value_hint: None, span: Some(parser_utils::span(child, parent_id)), metadata: None });
             }
 
             "expression_statement" => {
                 let mut expr_cursor = child.walk();
                 for expr_child in child.children(&mut expr_cursor) {
-                    if expr_child.kind() == "assignment_expression" {
-                        if let Some(left) = expr_child.child_by_field_name("left") {
-                            let name = parser_utils::get_text(left, source);
-                            if !name.contains('.') && !name.is_empty() && !name.contains('[') {
-                                let id = format!("{parent_id}::{name}");
-                                let value_hint = expr_child.child_by_field_name("right").map(|v| {
-                                    let val = parser_utils::get_text(v, source);
-                                    if val.len() > 30 { format!("{}...", val.chars().take(27).collect::<String>()) } else { val }
-                                });
-                                trace!(name = %name, kind = "Artifact", "Found variable");
-                                entities.push(GameEntity::Artifact { id, name, artifact_type: "variable".to_string(), datatype: "int".to_string(), is_mutable: true, value_hint, metadata: None });
-                            }
+                    if expr_child.kind() == "assignment_expression"
+                        && let Some(left) = expr_child.child_by_field_name("left")
+                    {
+                        let name = parser_utils::get_text(left, source);
+                        if !name.contains('.') && !name.is_empty() && !name.contains('[') {
+                            let id = format!("{parent_id}::{name}");
+                            let value_hint = expr_child.child_by_field_name("right").map(|v| {
+                                let val = parser_utils::get_text(v, source);
+                                if val.len() > 30 { format!("{}...", val.chars().take(27).collect::<String>()) } else { val }
+                            });
+                            trace!(name = %name, kind = "Artifact", "Found variable");
+                            entities.push(GameEntity::Artifact { id, name, artifact_type: "variable".to_string(), datatype: "int".to_string(), is_mutable: true, value_hint, span: Some(parser_utils::span(child, parent_id)), metadata: None });
                         }
                     }
                 }
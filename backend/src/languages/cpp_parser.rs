@@ -4,16 +4,10 @@ use super::traits::LanguageParser;
 use super::registry::CppParser;
 
 use tracing::{debug, instrument, trace};
-use tree_sitter::{Node, Parser};
+use tree_sitter::{Node, Tree};
 
 impl LanguageParser for CppParser {
-    fn parse(source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
-        let mut parser = Parser::new();
-        parser
-            .set_language(tree_sitter_cpp::language())
-            .expect("Error loading C++ grammar");
-
-        let tree = parser.parse(source, None).unwrap();
+    fn parse(tree: &Tree, source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
         let mut imports = Vec::new();
         let entities = parse_node(tree.root_node(), source.as_bytes(), parent_id, &mut imports);
         (entities, imports)
@@ -86,16 +80,14 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
         let kind = child.kind();
         match kind {
             "preproc_include" => {
-                let text = parser_utils::get_text(child, source);
-                let header = text.replace("#include", "").trim_matches(|c: char| c.is_whitespace() || c == '"' || c == '<' || c == '>').to_string();
-                if !header.is_empty() { imports.push(header); }
+                if let Some(header) = parser_utils::c_include(child, source) { imports.push(header); }
             }
 
             "namespace_definition" => {
                 let name = child.child_by_field_name("name").map(|n| parser_utils::get_text(n, source)).unwrap_or_else(|| "anonymous".into());
                 let id = format!("{parent_id}::{name}");
                 let children = child.child_by_field_name("body").map(|body| parse_node(body, source, &id, imports)).unwrap_or_default();
-                entities.push(GameEntity::District { id, name: format!("namespace {name}"), path: name, children });
+                entities.push(GameEntity::District { id, name: format!("namespace {name}"), path: name, children, metadata: None });
             }
 
             "class_specifier" | "struct_specifier" | "enum_specifier" | "union_specifier" => {
@@ -108,7 +100,7 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
                 let building_type = match kind { "enum_specifier" => "enum", "struct_specifier" => "struct", "union_specifier" => "union", _ => "class" };
 
                 debug!(name = %name, kind = "Building", "Found {building_type}");
-                entities.push(GameEntity::Building { id, name, building_type: building_type.to_string(), is_public, loc, imports: vec![], children, metadata: None });
+                entities.push(GameEntity::Building { id, name, building_type: building_type.to_string(), is_public, is_test: false, loc, imports: vec![], supertypes: parser_utils::supertypes(child, source), children, span: Some(parser_utils::span(child, parent_id)), metadata: None });
             }
 
             "template_declaration" => {
@@ -116,7 +108,9 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
             }
 
             "function_definition" | "declaration" => {
-                let name = child.child_by_field_name("declarator").map(|n| parser_utils::get_text(n, source)).unwrap_or_else(|| "fn".into());
+                // `int *make(void)` is named `make`, not `*make`
+                let declarator = parser_utils::c_function_declarator(child).and_then(|f| f.child_by_field_name("declarator")).or_else(|| child.child_by_field_name("declarator"));
+                let name = declarator.map(|n| parser_utils::get_text(n, source)).unwrap_or_else(|| "fn".into());
                 let clean_name = name.split('(').next().unwrap_or(&name).trim().to_string();
                 if clean_name.is_empty() || clean_name == "{" { continue; }
 
@@ -128,45 +122,57 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
                 let visibility = get_access_specifier(child, source);
 
                 let body = child.child_by_field_name("body");
+                let metadata = if kind == "declaration" && parser_utils::c_function_declarator(child).is_some() { parser_utils::declaration_metadata() } else { None };
                 let calls = body.map(|b| parser_utils::extract_function_calls(b, source, "call_expression", is_builtin)).unwrap_or_default();
                 let children = body.map(|b| parse_node(b, source, &id, imports)).unwrap_or_default();
                 let room_type = if parent_id.contains("::") { "method" } else { "function" };
 
                 trace!(name = %clean_name, kind = "Room", "Found {room_type}");
-                entities.push(GameEntity::Room { id, name: clean_name, room_type: room_type.to_string(), is_main: false, is_async: false, visibility: visibility.to_string(), complexity, loc, parameters, return_type, calls, children, metadata: None });
+                entities.push(GameEntity::Room { id, name: clean_name, room_type: room_type.to_string(), is_main: false, is_async: false, is_test: false, visibility: visibility.to_string(), complexity, loc, parameters, return_type, calls, children, span: Some(parser_utils::span(child, parent_id)), metadata });
             }
 
             "field_declaration" => {
-                if let Some(declarator) = child.child_by_field_name("declarator") {
+                if let Some(function) = parser_utils::c_function_declarator(child) {
+                    // In-class method prototype; the body lives in an out-of-class definition.
+                    let name = function.child_by_field_name("declarator").map(|n| parser_utils::get_text(n, source)).unwrap_or_default();
+                    if name.is_empty() { continue; }
+                    let id = format!("{parent_id}::{name}");
+                    let parameters = extract_parameters(function, source);
+                    let return_type = extract_return_type(child, source);
+                    let visibility = get_access_specifier(child, source);
+
+                    trace!(name = %name, kind = "Room", "Found method declaration");
+                    entities.push(GameEntity::Room { id, name, room_type: "method".to_string(), is_main: false, is_async: false, is_test: false, visibility: visibility.to_string(), complexity: 1, loc: parser_utils::count_lines(child), parameters, return_type, calls: vec![], children: vec![], span: Some(parser_utils::span(child, parent_id)), metadata: parser_utils::declaration_metadata() });
+                } else if let Some(declarator) = child.child_by_field_name("declarator") {
                     let name = parser_utils::get_text(declarator, source);
                     let id = format!("{parent_id}::{name}");
                     let datatype = child.child_by_field_name("type").map(|t| parser_utils::get_text(t, source)).unwrap_or_else(|| "auto".to_string());
 
-                    entities.push(GameEntity::Artifact { id, name, artifact_type: "field".to_string(), datatype, is_mutable: true, value_hint: None, metadata: None });
+                    entities.push(GameEntity::Artifact { id, name, artifact_type: "field".to_string(), datatype, is_mutable: true, value_hint: None, span: Some(parser_utils::span(child, parent_id)), metadata: None });
                 }
             }
 
             "enumerator" => {
                 let name = child.child_by_field_name("name").map(|n| parser_utils::get_text(n, source)).unwrap_or_else(|| "enumerator".into());
                 let id = format!("{parent_id}::{name}");
-                entities.push(GameEntity::Artifact { id, name, artifact_type: "enum_value".to_string(), datatype: "enum".to_string(), is_mutable: false, value_hint: None, metadata: None });
+                entities.push(GameEntity::Artifact { id, name, artifact_type: "enum_value".to_string(), datatype: "enum".to_string(), is_mutable: false, value_hint: None, span: Some(parser_utils::span(child, parent_id)), metadata: None });
             }
 
             "expression_statement" => {
                 let mut expr_cursor = child.walk();
                 for expr_child in child.children(&mut expr_cursor) {
-                    if expr_child.kind() == "assignment_expression" {
-                        if let Some(left) = expr_child.child_by_field_name("left") {
-                            let name = parser_utils::get_text(left, source);
-                            if !name.contains('.') && !name.is_empty() {
-                                let id = format!("{parent_id}::{name}");
-                                let value_hint = expr_child.child_by_field_name("right").map(|v| {
-                                    let val = parser_utils::get_text(v, source);
-                                    if val.len() > 30 { format!("{}...", val.chars().take(27).collect::<String>()) } else { val }
-                                });
-                                trace!(name = %name, kind = "Artifact", "Found variable");
-                                entities.push(GameEntity::Artifact { id, name, artifact_type: "variable".to_string(), datatype: "auto".to_string(), is_mutable: true, value_hint, metadata: None });
-                            }
+                    if expr_child.kind() == "assignment_expression"
+                        && let Some(left) = expr_child.child_by_field_name("left")
+                    {
+                        let name = parser_utils::get_text(left, source);
+                        if !name.contains('.') && !name.is_empty() {
+                            let id = format!("{parent_id}::{name}");
+                            let value_hint = expr_child.child_by_field_name("right").map(|v| {
+                                let val = parser_utils::get_text(v, source);
+                                if val.len() > 30 { format!("{}...", val.chars().take(27).collect::<String>()) } else { val }
+                            });
+                            trace!(name = %name, kind = "Artifact", "Found variable");
+                            entities.push(GameEntity::Artifact { id, name, artifact_type: "variable".to_string(), datatype: "auto".to_string(), is_mutable: true, value_hint, span: Some(parser_utils::span(child, parent_id)), metadata: None });
                         }
                     }
                 }
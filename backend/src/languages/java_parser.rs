@@ -4,16 +4,10 @@ use super::traits::LanguageParser;
 use super::registry::JavaParser;
 
 use tracing::{debug, instrument, trace};
-use tree_sitter::{Node, Parser};
+use tree_sitter::{Node, Tree};
 
 impl LanguageParser for JavaParser {
-    fn parse(source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
-        let mut parser = Parser::new();
-        parser
-            .set_language(tree_sitter_java::language())
-            .expect("Error loading Java grammar");
-
-        let tree = parser.parse(source, None).unwrap();
+    fn parse(tree: &Tree, source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
         let mut imports = Vec::new();
         let entities = parse_node(tree.root_node(), source.as_bytes(), parent_id, &mut imports);
         (entities, imports)
@@ -24,6 +18,35 @@ impl LanguageParser for JavaParser {
     }
 }
 
+/// The package a compilation unit declares, e.g. `com.example.app`.
+pub fn package_name(tree: &Tree, source: &str) -> Option<String> {
+    let root = tree.root_node();
+    let mut cursor = root.walk();
+    let package = root.children(&mut cursor).find(|child| child.kind() == "package_declaration")?;
+    let mut cursor = package.walk();
+    let name = package
+        .named_children(&mut cursor)
+        .find(|child| matches!(child.kind(), "scoped_identifier" | "identifier"))?;
+    Some(parser_utils::get_text(name, source.as_bytes()))
+}
+
+/// An import as written: `a.b.Type`, `a.b.*`, and for static imports the
+/// member after `::` (`a.b.Type::member`, `a.b.Type::*`).
+fn import_path(node: Node, source: &[u8]) -> Option<String> {
+    let text: String = parser_utils::get_text(node, source).chars().filter(|c| !c.is_whitespace()).collect();
+    let path = text.strip_prefix("import")?.trim_end_matches(';');
+    let mut cursor = node.walk();
+    let is_static = node.children(&mut cursor).any(|child| child.kind() == "static");
+    let import = match path.strip_prefix("static").filter(|_| is_static) {
+        Some(member) => {
+            let (owner, name) = member.rsplit_once('.')?;
+            format!("{owner}::{name}")
+        }
+        None => path.to_string(),
+    };
+    (!import.is_empty()).then_some(import)
+}
+
 fn extract_modifiers(node: Node, source: &[u8]) -> (String, bool, bool) {
     let mut visibility = "package".to_string();
     let mut is_static = false;
@@ -40,6 +63,12 @@ fn extract_modifiers(node: Node, source: &[u8]) -> (String, bool, bool) {
     (visibility, is_static, is_final)
 }
 
+fn is_abstract(node: Node, source: &[u8]) -> bool {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .any(|c| c.kind() == "modifiers" && parser_utils::get_text(c, source).split_whitespace().any(|m| m == "abstract"))
+}
+
 fn extract_parameters(node: Node, source: &[u8]) -> Vec<Parameter> {
     let mut params = Vec::new();
     if let Some(param_list) = node.child_by_field_name("parameters") {
@@ -92,11 +121,7 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
         let kind = child.kind();
         match kind {
             "import_declaration" => {
-                let text = parser_utils::get_text(child, source);
-                let cleaned = text.replace("import", "").replace("static", "").trim_end_matches(';').trim().to_string();
-                if !cleaned.is_empty() {
-                    imports.push(format!("{}.java", cleaned.replace('.', "/")));
-                }
+                if let Some(import) = import_path(child, source) { imports.push(import); }
             }
 
             "class_declaration" | "interface_declaration" | "enum_declaration" | "annotation_type_declaration" => {
@@ -109,8 +134,11 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
                 let children = parse_node(body_node, source, &id, imports);
                 let building_type = match kind { "interface_declaration" => "interface", "enum_declaration" => "enum", "annotation_type_declaration" => "annotation", _ => "class" };
 
+                let mut metadata = None;
+                if is_abstract(child, source) { parser_utils::mark_abstract(&mut metadata); }
+
                 debug!(name = %name, kind = "Building", "Found {building_type}");
-                entities.push(GameEntity::Building { id, name, building_type: building_type.to_string(), is_public, loc, imports: vec![], children, metadata: None });
+                entities.push(GameEntity::Building { id, name, building_type: building_type.to_string(), is_public, is_test: false, loc, imports: vec![], supertypes: parser_utils::supertypes(child, source), children, span: Some(parser_utils::span(child, parent_id)), metadata });
             }
 
             "method_declaration" | "constructor_declaration" => {
@@ -128,7 +156,7 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
                 let room_type = if kind == "constructor_declaration" { "constructor" } else { "method" };
 
                 trace!(name = %name, kind = "Room", "Found {room_type}");
-                entities.push(GameEntity::Room { id, name, room_type: room_type.to_string(), is_main: false, is_async: false, visibility: visibility.to_string(), complexity, loc, parameters, return_type, calls, children, metadata: None });
+                entities.push(GameEntity::Room { id, name, room_type: room_type.to_string(), is_main: false, is_async: false, is_test: false, visibility: visibility.to_string(), complexity, loc, parameters, return_type, calls, children, span: Some(parser_utils::span(child, parent_id)), metadata: None });
             }
 
             "field_declaration" => {
@@ -150,7 +178,7 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
                         let mut metadata = None;
                         if is_static { let mut m = std::collections::HashMap::new(); m.insert("static".into(), "true".into()); metadata = Some(m); }
 
-                        entities.push(GameEntity::Artifact { id, name, artifact_type: artifact_type.to_string(), datatype, is_mutable: !is_final, value_hint, metadata });
+                        entities.push(GameEntity::Artifact { id, name, artifact_type: artifact_type.to_string(), datatype, is_mutable: !is_final, value_hint, span: Some(parser_utils::span(field_child, parent_id)), metadata });
                     }
                 }
             }
@@ -158,7 +186,7 @@ fn parse_node(node: Node, source: &[u8], parent_id: &str, imports: &mut Vec<Stri
             "enum_constant" => {
                 let name = child.child_by_field_name("name").map(|n| parser_utils::get_text(n, source)).unwrap_or_else(|| "constant".into());
                 let id = format!("{parent_id}::{name}");
-                entities.push(GameEntity::Artifact { id, name, artifact_type: "enum_value".to_string(), datatype: "enum".to_string(), is_mutable: false, value_hint: None, metadata: None });
+                entities.push(GameEntity::Artifact { id, name, artifact_type: "enum_value".to_string(), datatype: "enum".to_string(), is_mutable: false, value_hint: None, span: Some(parser_utils::span(child, parent_id)), metadata: None });
             }
 
             _ => {
@@ -4,18 +4,12 @@ use super::traits::LanguageParser;
 use super::registry::JavaScriptParser;
 
 use tracing::{debug, instrument, trace};
-use tree_sitter::{Node, Parser};
+use tree_sitter::{Node, Tree};
 
 impl LanguageParser for JavaScriptParser {
-    fn parse(source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
-        let mut parser = Parser::new();
-        parser
-            .set_language(tree_sitter_javascript::language())
-            .expect("Error loading JavaScript grammar");
-
-        let tree = parser.parse(source, None).unwrap();
-        let mut imports = Vec::new();
-        let entities = parse_node(tree.root_node(), source.as_bytes(), parent_id, &mut imports);
+    fn parse(tree: &Tree, source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
+        let entities = parse_node(tree.root_node(), source.as_bytes(), parent_id);
+        let imports = parser_utils::js_module_imports(tree.root_node(), source.as_bytes());
         (entities, imports)
     }
 
@@ -106,12 +100,11 @@ const JS_COMPLEXITY_KINDS: &[&str] = &[
     "switch_case",
 ];
 
-#[instrument(skip(node, source, imports), level = "trace")]
+#[instrument(skip(node, source), level = "trace")]
 fn parse_node(
     node: Node,
     source: &[u8],
     parent_id: &str,
-    imports: &mut Vec<String>,
 ) -> Vec<GameEntity> {
     let mut entities = Vec::new();
     let mut cursor = node.walk();
@@ -120,42 +113,8 @@ fn parse_node(
         let kind = child.kind();
 
         match kind {
-            "import_statement" => {
-                if let Some(source_node) = child.child_by_field_name("source") {
-                    let import_path = parser_utils::get_text(source_node, source)
-                        .trim_matches(|c| c == '"' || c == '\'' || c == '`')
-                        .to_string();
-                    if import_path.starts_with("./") || import_path.starts_with("../") {
-                        let ext = if import_path.ends_with(".js") || import_path.ends_with(".jsx") { "" } else { ".js" };
-                        imports.push(format!("{import_path}{ext}"));
-                    }
-                }
-            }
-
             "lexical_declaration" | "variable_declaration" => {
-                let mut decl_cursor = child.walk();
-                for decl in child.children(&mut decl_cursor) {
-                    if decl.kind() == "variable_declarator"
-                        && let Some(value) = decl.child_by_field_name("value")
-                        && value.kind() == "call_expression"
-                    {
-                        let func = value
-                            .child_by_field_name("function")
-                            .map(|n| parser_utils::get_text(n, source))
-                            .unwrap_or_default();
-                        if func == "require"
-                            && let Some(args) = value.child_by_field_name("arguments")
-                        {
-                            let path = parser_utils::get_text(args, source)
-                                .trim_matches(|c| c == '(' || c == ')' || c == '"' || c == '\'')
-                                .to_string();
-                            if path.starts_with("./") || path.starts_with("../") {
-                                imports.push(format!("{path}.js"));
-                            }
-                        }
-                    }
-                }
-                entities.extend(parse_variables(child, source, parent_id, imports));
+                entities.extend(parse_variables(child, source, parent_id));
             }
 
             "class_declaration" => {
@@ -166,12 +125,12 @@ fn parse_node(
                 let id = format!("{parent_id}::{name}");
                 let is_public = is_exported(child, source);
                 let loc = parser_utils::count_lines(child);
-                let children = parse_node(child, source, &id, imports);
+                let children = parse_node(child, source, &id);
 
                 debug!(name = %name, kind = "Building", "Found class");
                 entities.push(GameEntity::Building {
-                    id, name, building_type: "class".to_string(), is_public, loc,
-                    imports: vec![], children, metadata: None,
+                    id, name, building_type: "class".to_string(), is_public, is_test: false, loc,
+                    imports: vec![], supertypes: parser_utils::supertypes(child, source), children, span: Some(parser_utils::span(child, parent_id)), metadata: None,
                 });
             }
 
@@ -189,13 +148,13 @@ fn parse_node(
                 let calls = child.child_by_field_name("body")
                     .map(|body| parser_utils::extract_function_calls(body, source, "call_expression", is_builtin))
                     .unwrap_or_default();
-                let children = parse_function_body(child, source, &id, imports);
+                let children = parse_function_body(child, source, &id);
 
                 debug!(name = %name, kind = "Room", "Found function");
                 entities.push(GameEntity::Room {
                     id, name, room_type: "function".to_string(),
-                    is_main: false, is_async: is_async_fn, visibility: visibility.to_string(),
-                    complexity, loc, parameters, return_type: None, calls, children, metadata: None,
+                    is_main: false, is_async: is_async_fn, is_test: false, visibility: visibility.to_string(),
+                    complexity, loc, parameters, return_type: None, calls, children, span: Some(parser_utils::span(child, parent_id)), metadata: None,
                 });
             }
 
@@ -212,12 +171,12 @@ fn parse_node(
                 let calls = child.child_by_field_name("body")
                     .map(|body| parser_utils::extract_function_calls(body, source, "call_expression", is_builtin))
                     .unwrap_or_default();
-                let children = parse_function_body(child, source, &id, imports);
+                let children = parse_function_body(child, source, &id);
 
                 entities.push(GameEntity::Room {
                     id, name, room_type: "method".to_string(),
-                    is_main: false, is_async: is_async_fn, visibility: "public".to_string(),
-                    complexity, loc, parameters, return_type: None, calls, children, metadata: None,
+                    is_main: false, is_async: is_async_fn, is_test: false, visibility: "public".to_string(),
+                    complexity, loc, parameters, return_type: None, calls, children, span: Some(parser_utils::span(child, parent_id)), metadata: None,
                 });
             }
 
@@ -231,13 +190,13 @@ fn parse_node(
 
                 entities.push(GameEntity::Artifact {
                     id, name, artifact_type: "field".to_string(), datatype: "any".to_string(),
-                    is_mutable: true, value_hint: None, metadata: None,
+                    is_mutable: true, value_hint: None, span: Some(parser_utils::span(child, parent_id)), metadata: None,
                 });
             }
 
             _ => {
                 if child.child_count() > 0 {
-                    entities.extend(parse_node(child, source, parent_id, imports));
+                    entities.extend(parse_node(child, source, parent_id));
                 }
             }
         }
@@ -249,7 +208,6 @@ fn parse_variables(
     node: Node,
     source: &[u8],
     parent_id: &str,
-    imports: &mut Vec<String>,
 ) -> Vec<GameEntity> {
     let mut entities = Vec::new();
     let mut decl_cursor = node.walk();
@@ -271,14 +229,14 @@ fn parse_variables(
                 let parameters = extract_parameters(val, source);
                 let complexity = parser_utils::calculate_complexity(val, JS_COMPLEXITY_KINDS);
                 let calls = parser_utils::extract_function_calls(val, source, "call_expression", is_builtin);
-                let children = parse_function_body(val, source, &id, imports);
+                let children = parse_function_body(val, source, &id);
 
                 debug!(name = %name, kind = "Room", "Found arrow function");
                 entities.push(GameEntity::Room {
                     id, name, room_type: "arrow_function".to_string(),
-                    is_main: false, is_async: is_async_fn,
+                    is_main: false, is_async: is_async_fn, is_test: false,
                     visibility: if is_exported(node, source) { "public" } else { "private" }.to_string(),
-                    complexity, loc, parameters, return_type: None, calls, children, metadata: None,
+                    complexity, loc, parameters, return_type: None, calls, children, span: Some(parser_utils::span(decl, parent_id)), metadata: None,
                 });
                 continue;
             }
@@ -292,7 +250,7 @@ fn parse_variables(
             trace!(name = %name, kind = "Artifact", "Found variable");
             entities.push(GameEntity::Artifact {
                 id, name, artifact_type: artifact_type.to_string(), datatype: "any".to_string(),
-                is_mutable: !parser_utils::get_text(node, source).starts_with("const"), value_hint, metadata: None,
+                is_mutable: !parser_utils::get_text(node, source).starts_with("const"), value_hint, span: Some(parser_utils::span(decl, parent_id)), metadata: None,
             });
         }
     }
@@ -303,11 +261,10 @@ fn parse_function_body(
     node: Node,
     source: &[u8],
     parent_id: &str,
-    imports: &mut Vec<String>,
 ) -> Vec<GameEntity> {
     let mut contents = Vec::new();
     if let Some(body) = node.child_by_field_name("body") {
-        contents.extend(parse_node(body, source, parent_id, imports));
+        contents.extend(parse_node(body, source, parent_id));
     }
     contents
 }
@@ -3,6 +3,7 @@ pub mod cpp_parser;
 pub mod java_parser;
 pub mod js_parser;
 pub mod parser_utils;
+pub mod paths;
 pub mod py_parser;
 pub mod registry;
 pub mod rs_parser;
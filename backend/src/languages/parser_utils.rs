@@ -1,5 +1,7 @@
 use tree_sitter::Node;
 
+use crate::models::SourceSpan;
+
 pub fn get_text<'a>(node: Node<'a>, source: &'a [u8]) -> String {
     node.utf8_text(source).unwrap_or("").to_string()
 }
@@ -10,6 +12,82 @@ pub fn count_lines(node: Node) -> u32 {
     (end - start + 1) as u32
 }
 
+/// Splits an import recorded with a local name (`crate::db::world as w`) into
+/// its path and alias.
+pub fn split_import_alias(import: &str) -> (&str, Option<&str>) {
+    match import.split_once(" as ") {
+        Some((path, alias)) => (path, Some(alias)),
+        None => (import, None),
+    }
+}
+
+/// Span of `node` within the file that owns `parent_id` (the id prefix before the first `::`).
+pub fn span(node: Node, parent_id: &str) -> SourceSpan {
+    let start = node.start_position();
+    let end = node.end_position();
+    SourceSpan {
+        file: super::paths::file_of(parent_id).to_string(),
+        start_line: start.row as u32 + 1,
+        start_column: start.column as u32,
+        end_line: end.row as u32 + 1,
+        end_column: end.column as u32,
+        start_byte: node.start_byte() as u32,
+        end_byte: node.end_byte() as u32,
+    }
+}
+
+/// Clauses under a type declaration that list its supertypes, across grammars.
+const SUPERTYPE_CLAUSE_KINDS: &[&str] = &[
+    "superclass", "super_interfaces", "extends_interfaces", // Java
+    "class_heritage", "extends_clause", "implements_clause", "extends_type_clause", // TS/JS
+    "argument_list", // Python class bases
+    "base_class_clause", // C++
+    "trait_bounds", // Rust supertraits
+    "type_list", // Java: the types inside `super_interfaces` and `extends_interfaces`
+];
+
+/// Children of a supertype clause that are not types themselves.
+const SUPERTYPE_SKIP_KINDS: &[&str] = &[
+    "access_specifier", "virtual", "attribute_declaration", "lifetime", "removed_trait_bound",
+    "type_arguments", "keyword_argument", "list_splat", "dictionary_splat", "comment",
+];
+
+/// Supertypes listed by a class/interface/trait declaration, with generic arguments removed.
+pub fn supertypes(decl: Node, source: &[u8]) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut cursor = decl.walk();
+    for child in decl.named_children(&mut cursor) {
+        if SUPERTYPE_CLAUSE_KINDS.contains(&child.kind()) {
+            collect_supertype_names(child, source, &mut names);
+        }
+    }
+    names
+}
+
+fn collect_supertype_names(clause: Node, source: &[u8], names: &mut Vec<String>) {
+    let mut cursor = clause.walk();
+    for child in clause.named_children(&mut cursor) {
+        let kind = child.kind();
+        if SUPERTYPE_CLAUSE_KINDS.contains(&kind) {
+            collect_supertype_names(child, source, names);
+        } else if !SUPERTYPE_SKIP_KINDS.contains(&kind) {
+            let name = clean_type_name(&get_text(child, source));
+            if !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+}
+
+/// Drops generic arguments: `Base<T>` -> `Base`, `Generic[T]` -> `Generic`.
+pub fn clean_type_name(raw: &str) -> String {
+    raw.split(['<', '['])
+        .next()
+        .unwrap_or(raw)
+        .trim()
+        .to_string()
+}
+
 pub fn calculate_complexity(node: Node, complexity_kinds: &[&str]) -> u32 {
     let mut complexity = 1;
     count_complexity_nodes(node, complexity_kinds, &mut complexity);
@@ -27,6 +105,9 @@ fn count_complexity_nodes(node: Node, complexity_kinds: &[&str], complexity: &mu
     }
 }
 
+/// Calls made under `node`, as `name` or `receiver.name` / `receiver::name` when the
+/// receiver is a plain identifier (`self`, `this`, a type or a module). The receiver
+/// is kept so resolution can use it; builtins are filtered on the bare name.
 pub fn extract_function_calls(
     node: Node,
     source: &[u8],
@@ -37,7 +118,7 @@ pub fn extract_function_calls(
     extract_calls_recursive_with_name(node, source, call_kind, &mut calls);
     calls
         .into_iter()
-        .filter(|c| !c.is_empty() && !is_builtin_fn(c))
+        .filter(|c| !c.is_empty() && !is_builtin_fn(call_target_name(c)))
         .collect()
 }
 
@@ -47,12 +128,11 @@ fn extract_calls_recursive_with_name(
     call_kind: &str,
     calls: &mut Vec<String>,
 ) {
-    if node.kind() == call_kind {
-        if let Some(name) = extract_call_name(node, source) {
-            if !name.is_empty() {
-                calls.push(name);
-            }
-        }
+    if node.kind() == call_kind
+        && let Some(name) = extract_call_name(node, source)
+        && !name.is_empty()
+    {
+        calls.push(name);
     }
 
     let mut cursor = node.walk();
@@ -61,32 +141,298 @@ fn extract_calls_recursive_with_name(
     }
 }
 
-#[allow(clippy::collapsible_match)]
 fn extract_call_name(node: Node, source: &[u8]) -> Option<String> {
     match node.kind() {
-        "call_expression" => node
-            .child_by_field_name("function")
-            .map(|n| clean_call_name(&get_text(n, source))),
-        "call" => node
+        "call_expression" | "call" => node
             .child_by_field_name("function")
-            .map(|n| clean_dotted_name(&get_text(n, source))),
-        "method_invocation" => node
-            .child_by_field_name("name")
-            .map(|n| get_text(n, source)),
+            .map(|n| qualified_call_name(&get_text(n, source))),
+        "method_invocation" => {
+            let name = node.child_by_field_name("name").map(|n| get_text(n, source))?;
+            let receiver = node.child_by_field_name("object").map(|n| get_text(n, source));
+            Some(match receiver {
+                Some(receiver) => qualified_call_name(&format!("{receiver}.{name}")),
+                None => name,
+            })
+        }
         _ => None,
     }
 }
 
-fn clean_call_name(raw: &str) -> String {
-    raw.split("::")
-        .last()
-        .unwrap_or(raw)
-        .split('.')
-        .next_back()
-        .unwrap_or(raw)
-        .to_string()
+/// `crate::utils::parse` -> `utils::parse`, `self.helper` -> `self.helper`,
+/// `ptr->run` -> `ptr.run`, `a.b(x).c` -> `c`.
+fn qualified_call_name(raw: &str) -> String {
+    let raw = raw.replace("->", ".");
+    let Some((receiver, name, separator)) = split_last_segment(&raw) else {
+        return raw.trim().to_string();
+    };
+    let receiver = split_last_segment(receiver).map(|(_, last, _)| last).unwrap_or(receiver);
+    if is_identifier(receiver) {
+        format!("{receiver}{separator}{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+fn split_last_segment(path: &str) -> Option<(&str, &str, &'static str)> {
+    let dot = path.rfind('.').map(|i| (i, 1, "."));
+    let colons = path.rfind("::").map(|i| (i, 2, "::"));
+    let (i, len, separator) = match (dot, colons) {
+        (Some(d), Some(c)) => if c.0 > d.0 { c } else { d },
+        (d, c) => d.or(c)?,
+    };
+    Some((&path[..i], &path[i + len..], separator))
+}
+
+fn is_identifier(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// The function name of a call as recorded by `extract_function_calls`.
+pub fn call_target_name(call: &str) -> &str {
+    split_last_segment(call).map(|(_, name, _)| name).unwrap_or(call)
+}
+
+/// Module specifiers of a JS/TS file as written, in source order and without
+/// duplicates: `import`/`export ... from`, `require()`, dynamic `import()` and
+/// `import x = require()`. Named imports add `specifier::name` entries
+/// (`import { a, b as c } from "./m"` -> `./m::a`, `./m::b`); default,
+/// namespace and side-effect imports add the bare specifier.
+pub fn js_module_imports(root: Node, source: &[u8]) -> Vec<String> {
+    let mut imports = Vec::new();
+    collect_js_imports(root, source, &mut imports);
+    let mut seen = std::collections::HashSet::new();
+    imports.retain(|import| seen.insert(import.clone()));
+    imports
 }
 
-fn clean_dotted_name(raw: &str) -> String {
-    raw.split('.').next_back().unwrap_or(raw).to_string()
+fn collect_js_imports(node: Node, source: &[u8], out: &mut Vec<String>) {
+    match node.kind() {
+        "import_statement" | "export_statement" => {
+            if let Some(specifier) = node.child_by_field_name("source").map(|s| string_literal(s, source)) {
+                let mut names = Vec::new();
+                collect_js_named_imports(node, source, &mut names);
+                if names.is_empty() || has_default_or_namespace(node) {
+                    out.push(specifier.clone());
+                }
+                out.extend(names.into_iter().map(|name| format!("{specifier}::{name}")));
+                return;
+            }
+        }
+        "import_require_clause" => {
+            if let Some(specifier) = node.child_by_field_name("source") {
+                out.push(string_literal(specifier, source));
+            }
+        }
+        "call_expression" => {
+            let function = node.child_by_field_name("function");
+            let is_import = function.is_some_and(|f| f.kind() == "import" || get_text(f, source) == "require");
+            let argument = node
+                .child_by_field_name("arguments")
+                .and_then(|args| args.named_child(0))
+                .filter(|arg| arg.kind() == "string");
+            if is_import && let Some(argument) = argument {
+                let specifier = string_literal(argument, source);
+                // const { a, b: c } = require("./m")
+                let pattern = node
+                    .parent()
+                    .filter(|p| p.kind() == "variable_declarator")
+                    .and_then(|p| p.child_by_field_name("name"))
+                    .filter(|n| n.kind() == "object_pattern");
+                match pattern {
+                    Some(pattern) => {
+                        let mut cursor = pattern.walk();
+                        for property in pattern.named_children(&mut cursor) {
+                            let name = match property.kind() {
+                                "shorthand_property_identifier_pattern" => Some(property),
+                                "pair_pattern" => property.child_by_field_name("key"),
+                                _ => None,
+                            };
+                            if let Some(name) = name {
+                                out.push(format!("{specifier}::{}", get_text(name, source)));
+                            }
+                        }
+                    }
+                    None => out.push(specifier),
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_js_imports(child, source, out);
+    }
+}
+
+/// Imported or re-exported names (not their local aliases) under an import/export statement.
+fn collect_js_named_imports(node: Node, source: &[u8], names: &mut Vec<String>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match child.kind() {
+            "import_specifier" | "export_specifier" => {
+                if let Some(name) = child.child_by_field_name("name") {
+                    names.push(get_text(name, source));
+                }
+            }
+            "import_clause" | "named_imports" | "export_clause" => collect_js_named_imports(child, source, names),
+            _ => {}
+        }
+    }
+}
+
+/// `import x from`, `import * as x from` or `export * from`: the whole module is bound.
+fn has_default_or_namespace(statement: Node) -> bool {
+    let mut cursor = statement.walk();
+    statement.children(&mut cursor).any(|child| match child.kind() {
+        "*" | "namespace_export" => true,
+        "import_clause" => {
+            let mut clause_cursor = child.walk();
+            child
+                .named_children(&mut clause_cursor)
+                .any(|c| matches!(c.kind(), "identifier" | "namespace_import"))
+        }
+        _ => false,
+    })
+}
+
+fn string_literal(node: Node, source: &[u8]) -> String {
+    get_text(node, source).trim_matches(|c| c == '"' || c == '\'' || c == '`').to_string()
+}
+
+/// Header named by a C/C++ `#include`: quoted includes as written, system includes
+/// kept in angle brackets (`<stdio.h>`) so resolution can tell the two apart.
+pub fn c_include(include: Node, source: &[u8]) -> Option<String> {
+    let path = include.child_by_field_name("path")?;
+    let text = get_text(path, source);
+    let header = match path.kind() {
+        "system_lib_string" => text.trim().to_string(),
+        _ => text.trim().trim_matches('"').to_string(),
+    };
+    (!header.is_empty() && header != "<>").then_some(header)
+}
+
+/// The `function_declarator` of a C/C++ declaration, looking through pointer and
+/// reference declarators (`int *make(void)`, `Foo &get()`).
+pub fn c_function_declarator(decl: Node) -> Option<Node> {
+    let mut declarator = decl.child_by_field_name("declarator")?;
+    loop {
+        match declarator.kind() {
+            "function_declarator" => return Some(declarator),
+            "pointer_declarator" | "reference_declarator" => {
+                declarator = declarator
+                    .child_by_field_name("declarator")
+                    .or_else(|| declarator.named_child(declarator.named_child_count().checked_sub(1)?))?;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Flag a Building as an abstract type (abstract class, ABC) for abstractness metrics.
+pub fn mark_abstract(metadata: &mut Option<std::collections::HashMap<String, String>>) {
+    metadata.get_or_insert_with(Default::default).insert("abstract".to_string(), "true".to_string());
+}
+
+/// Room metadata marking a function prototype, so it can be linked to its definition.
+pub fn declaration_metadata() -> Option<std::collections::HashMap<String, String>> {
+    Some(std::collections::HashMap::from([("declaration".to_string(), "true".to_string())]))
+}
+
+/// The value of a string literal, with interpolations (`${id}`, `{id}`) kept as `{...}` placeholders.
+pub fn string_value(node: Node, source: &[u8]) -> Option<String> {
+    if !matches!(node.kind(), "string_literal" | "string" | "template_string") {
+        return None;
+    }
+    let text = get_text(node, source);
+    let unquoted = text
+        .trim_start_matches(|c: char| c.is_ascii_alphabetic()) // Python prefixes: f"", r""
+        .trim_matches(['"', '\'', '`']);
+    Some(unquoted.replace("${", "{"))
+}
+
+/// Calls `visit` on `node` and every node below it, in document order.
+pub fn walk<'a>(node: Node<'a>, visit: &mut impl FnMut(Node<'a>)) {
+    visit(node);
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        walk(child, visit);
+    }
+}
+
+/// Text of the `#[...]` attributes directly above an item.
+pub fn preceding_attributes(node: Node, source: &[u8]) -> Vec<String> {
+    let mut attributes = Vec::new();
+    let mut previous = node.prev_named_sibling();
+    while let Some(sibling) = previous {
+        match sibling.kind() {
+            "attribute_item" => attributes.push(get_text(sibling, source)),
+            "line_comment" | "block_comment" => {}
+            _ => break,
+        }
+        previous = sibling.prev_named_sibling();
+    }
+    attributes
+}
+
+/// Whether an attribute is `#[name]`, `#[name(...)]` or a path ending in it (`#[pyo3::pyfunction]`).
+pub fn has_attribute(attributes: &[String], name: &str) -> bool {
+    attributes.iter().any(|attribute| {
+        let inner = attribute.trim_start_matches("#[").trim_end_matches(']');
+        let path = inner.split(['(', ' ', '=']).next().unwrap_or(inner);
+        path == name || path.ends_with(&format!("::{name}"))
+    })
+}
+
+/// The value of `key = "value"` (or `key = ident`) inside any of the attributes.
+pub fn attribute_value(attributes: &[String], key: &str) -> Option<String> {
+    for attribute in attributes {
+        for (i, _) in attribute.match_indices(key) {
+            // `name` must not match inside `js_name` or `export_name`
+            if attribute[..i].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            let Some(rest) = attribute[i + key.len()..].trim_start().strip_prefix('=') else { continue };
+            let value = rest.split([',', ')', ']']).next().unwrap_or(rest).trim().trim_matches('"');
+            if !value.is_empty() {
+                return Some(value.to_string());
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::languages::registry;
+    use crate::models::GameEntity;
+
+    /// Supertypes of every Building named in `source`, outermost first.
+    fn supertypes_by_name(ext: &str, source: &str) -> Vec<(String, Vec<String>)> {
+        let (entities, _) = registry::parse_by_extension(ext, source, "file").unwrap();
+        entities
+            .into_iter()
+            .filter_map(|e| match e {
+                GameEntity::Building { name, supertypes, .. } => Some((name, supertypes)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_supertypes_across_grammars() {
+        let java = "class Circle extends Shape implements Drawable, Comparable<Circle> {}\ninterface Drawable extends Paintable, Visible {}\n";
+        let ts = "class Admin extends User<Role> implements Auditable, Named {}\ninterface Named extends Labelled {}\n";
+        let python = "class Admin(User, mixins.Audit, metaclass=ABCMeta):\n    pass\n";
+        let cpp = "class Circle : public Shape, protected virtual Drawable<int> {};\n";
+
+        let expected = |name: &str, supertypes: &[&str]| (name.to_string(), supertypes.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        assert_eq!(supertypes_by_name("java", java), [
+            expected("Circle", &["Shape", "Drawable", "Comparable"]),
+            expected("Drawable", &["Paintable", "Visible"]),
+        ]);
+        assert_eq!(supertypes_by_name("ts", ts), [expected("Admin", &["User", "Auditable", "Named"]), expected("Named", &["Labelled"])]);
+        assert_eq!(supertypes_by_name("py", python), [expected("Admin", &["User", "mixins.Audit"])]);
+        assert_eq!(supertypes_by_name("cpp", cpp), [expected("Circle", &["Shape", "Drawable"])]);
+    }
 }
@@ -0,0 +1,72 @@
+//! Helpers for the file paths and entity ids shared by the parsers, resolvers
+//! and analyses: entity ids are `file::Item::member`, file ids are
+//! repository-relative paths with `/` separators.
+
+/// Languages that resolve against each other: TS and JS share modules, C and
+/// C++ share headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    Rust,
+    Js,
+    Python,
+    C,
+    Java,
+    Other,
+}
+
+/// Extensions of C and C++ sources and headers.
+const C_EXTENSIONS: &[&str] = &["c", "h", "cpp", "cc", "cxx", "hpp"];
+
+/// The file part of an entity id ("src/a.rs::Foo::bar" -> "src/a.rs").
+pub fn file_of(id: &str) -> &str {
+    id.split("::").next().unwrap_or(id)
+}
+
+/// The directory of a file id ("src/db/mod.rs" -> "src/db", "main.rs" -> "").
+pub fn dir_of(file: &str) -> &str {
+    file.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+/// The extension of a file id, or the whole name when it has none.
+pub fn extension(file: &str) -> &str {
+    file.rsplit('.').next().unwrap_or_default()
+}
+
+/// Joins `relative` onto `base_dir`, folding `.` and `..` segments.
+pub fn normalize_relative(base_dir: &str, relative: &str) -> String {
+    let mut parts: Vec<&str> = base_dir.split('/').filter(|p| !p.is_empty()).collect();
+    for part in relative.split('/') {
+        match part {
+            "." | "" => {}
+            ".." => {
+                parts.pop();
+            }
+            other => parts.push(other),
+        }
+    }
+    parts.join("/")
+}
+
+pub fn family(file: &str) -> Family {
+    match extension(file) {
+        "rs" => Family::Rust,
+        "ts" | "tsx" | "js" | "jsx" => Family::Js,
+        "py" => Family::Python,
+        ext if C_EXTENSIONS.contains(&ext) => Family::C,
+        "java" => Family::Java,
+        _ => Family::Other,
+    }
+}
+
+pub fn is_c_family(file: &str) -> bool {
+    family(file) == Family::C
+}
+
+/// Whether two files are in the same language family; files of languages
+/// without a family only match their own extension.
+pub fn same_family(a: &str, b: &str) -> bool {
+    match (family(a), family(b)) {
+        (Family::Other, Family::Other) => extension(a) == extension(b),
+        (a, b) => a == b,
+    }
+}
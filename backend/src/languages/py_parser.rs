@@ -4,16 +4,10 @@ use super::traits::LanguageParser;
 use super::registry::PythonParser;
 
 use tracing::{debug, instrument, trace};
-use tree_sitter::{Node, Parser};
+use tree_sitter::{Node, Tree};
 
 impl LanguageParser for PythonParser {
-    fn parse(source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
-        let mut parser = Parser::new();
-        parser
-            .set_language(tree_sitter_python::language())
-            .expect("Error loading Python grammar");
-
-        let tree = parser.parse(source, None).unwrap();
+    fn parse(tree: &Tree, source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
         let mut imports = Vec::new();
         let entities = parse_node(tree.root_node(), source.as_bytes(), parent_id, &mut imports);
         (entities, imports)
@@ -146,22 +140,31 @@ fn parse_node(
         let kind = child.kind();
 
         match kind {
+            // Recorded as written: `pkg.mod` for `import pkg.mod`, `.models::User`
+            // for `from .models import User`; resolved later by resolvers::python
             "import_statement" => {
                 let mut import_cursor = child.walk();
-                for import_child in child.children(&mut import_cursor) {
-                    if import_child.kind() == "dotted_name" {
-                        let module = parser_utils::get_text(import_child, source);
-                        if !module.is_empty() {
-                            imports.push(format!("{}.py", module.replace('.', "/")));
-                        }
-                    }
+                for name in child.children_by_field_name("name", &mut import_cursor) {
+                    let module = name.child_by_field_name("name").unwrap_or(name);
+                    imports.push(parser_utils::get_text(module, source));
                 }
             }
             "import_from_statement" => {
                 if let Some(module_node) = child.child_by_field_name("module_name") {
                     let module = parser_utils::get_text(module_node, source);
-                    if module.starts_with('.') {
-                        imports.push(format!("{}.py", module.trim_start_matches('.')));
+                    let mut names = Vec::new();
+                    let mut import_cursor = child.walk();
+                    for name in child.children_by_field_name("name", &mut import_cursor) {
+                        let name = name.child_by_field_name("name").unwrap_or(name);
+                        names.push(parser_utils::get_text(name, source));
+                    }
+                    if child.children(&mut child.walk()).any(|c| c.kind() == "wildcard_import") {
+                        names.push("*".to_string());
+                    }
+                    if names.is_empty() {
+                        imports.push(module);
+                    } else {
+                        imports.extend(names.into_iter().map(|name| format!("{module}::{name}")));
                     }
                 }
             }
@@ -180,8 +183,8 @@ fn parse_node(
 
                 debug!(name = %name, kind = "Building", "Found class");
                 entities.push(GameEntity::Building {
-                    id, name, building_type: "class".to_string(), is_public, loc,
-                    imports: vec![], children, metadata: None,
+                    id, name, building_type: "class".to_string(), is_public, is_test: false, loc,
+                    imports: vec![], supertypes: parser_utils::supertypes(child, source), children, span: Some(parser_utils::span(child, parent_id)), metadata: None,
                 });
             }
 
@@ -219,9 +222,9 @@ fn parse_node(
 
                 debug!(name = %name, kind = "Room", "Found function");
                 entities.push(GameEntity::Room {
-                    id, name, room_type: room_type.to_string(), is_main, is_async: is_async_fn,
+                    id, name, room_type: room_type.to_string(), is_main, is_async: is_async_fn, is_test: false,
                     visibility: visibility.to_string(), complexity, loc, parameters,
-                    return_type, calls, children, metadata: None,
+                    return_type, calls, children, span: Some(parser_utils::span(child, parent_id)), metadata: None,
                 });
             }
 
@@ -244,21 +247,22 @@ fn parse_node(
             "if_statement" => {
                 if let Some(condition) = child.child_by_field_name("condition") {
                     let cond_text = parser_utils::get_text(condition, source);
-                    if cond_text.contains("__name__") && cond_text.contains("__main__") {
-                        if let Some(consequence) = child.child_by_field_name("consequence") {
-                            let main_children = parse_node(consequence, source, parent_id, imports);
-                            entities.push(GameEntity::Room {
-                                id: format!("{parent_id}::__main_guard__"),
-                                name: "__main__".to_string(),
-                                room_type: "main_guard".to_string(), is_main: true, is_async: false,
-                                visibility: "public".to_string(),
-                                complexity: parser_utils::calculate_complexity(child, PY_COMPLEXITY_KINDS),
-                                loc: parser_utils::count_lines(child), parameters: vec![],
-                                return_type: None,
-                                calls: parser_utils::extract_function_calls(child, source, "call", is_builtin),
-                                children: main_children, metadata: None,
-                            });
-                        }
+                    if cond_text.contains("__name__")
+                        && cond_text.contains("__main__")
+                        && let Some(consequence) = child.child_by_field_name("consequence")
+                    {
+                        let main_children = parse_node(consequence, source, parent_id, imports);
+                        entities.push(GameEntity::Room {
+                            id: format!("{parent_id}::__main_guard__"),
+                            name: "__main__".to_string(),
+                            room_type: "main_guard".to_string(), is_main: true, is_async: false, is_test: false,
+                            visibility: "public".to_string(),
+                            complexity: parser_utils::calculate_complexity(child, PY_COMPLEXITY_KINDS),
+                            loc: parser_utils::count_lines(child), parameters: vec![],
+                            return_type: None,
+                            calls: parser_utils::extract_function_calls(child, source, "call", is_builtin),
+                            children: main_children, span: Some(parser_utils::span(child, parent_id)), metadata: None,
+                        });
                     }
                 }
             }
@@ -294,7 +298,7 @@ fn parse_assignment(node: Node, source: &[u8], parent_id: &str) -> Vec<GameEntit
         trace!(name = %name, kind = "Artifact", "Found variable");
         entities.push(GameEntity::Artifact {
             id, name, artifact_type: artifact_type.to_string(), datatype,
-            is_mutable: !is_constant, value_hint, metadata: None,
+            is_mutable: !is_constant, value_hint, span: Some(parser_utils::span(node, parent_id)), metadata: None,
         });
     }
     entities
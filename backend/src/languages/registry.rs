@@ -1,15 +1,19 @@
+use tree_sitter::Tree;
+
 use crate::models::GameEntity;
 use super::traits::LanguageParser;
 
 macro_rules! language_registry {
     ($($ext:expr => $parser:ty),* $(,)?) => {
-        pub fn parse_by_extension(
+        /// Entities and imports of a file already parsed with [`parse_tree`].
+        pub fn parse_with_tree(
             ext: &str,
+            tree: &Tree,
             source: &str,
             parent_id: &str,
         ) -> Option<(Vec<GameEntity>, Vec<String>)> {
             match ext {
-                $($ext => Some(<$parser>::parse(source, parent_id)),)*
+                $($ext => Some(<$parser>::parse(tree, source, parent_id)),)*
                 _ => None,
             }
         }
@@ -41,3 +45,32 @@ pub struct PythonParser;
 pub struct CppParser;
 pub struct CParser;
 pub struct JavaParser;
+
+/// Tree-sitter grammar for a file extension, matching the parser registered above.
+/// Used by analyses that walk the syntax tree outside the language parsers.
+pub fn grammar_for_extension(ext: &str) -> Option<tree_sitter::Language> {
+    match ext {
+        "rs" => Some(tree_sitter_rust::language()),
+        "ts" | "tsx" => Some(tree_sitter_typescript::language_typescript()),
+        "js" | "jsx" => Some(tree_sitter_javascript::language()),
+        "py" => Some(tree_sitter_python::language()),
+        "cpp" | "cc" | "cxx" | "hpp" => Some(tree_sitter_cpp::language()),
+        "c" | "h" => Some(tree_sitter_c::language()),
+        "java" => Some(tree_sitter_java::language()),
+        _ => None,
+    }
+}
+
+/// Parses `source` with the grammar for `ext`. Each file is parsed once and
+/// the tree shared by the language parser and every later analysis.
+pub fn parse_tree(ext: &str, source: &str) -> Option<Tree> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(grammar_for_extension(ext)?).ok()?;
+    parser.parse(source, None)
+}
+
+/// Parses `source` and extracts its entities and imports in one go.
+pub fn parse_by_extension(ext: &str, source: &str, parent_id: &str) -> Option<(Vec<GameEntity>, Vec<String>)> {
+    let tree = parse_tree(ext, source)?;
+    parse_with_tree(ext, &tree, source, parent_id)
+}
@@ -5,16 +5,10 @@ use super::traits::LanguageParser;
 use super::registry::RustParser;
 
 use tracing::instrument;
-use tree_sitter::{Node, Parser};
+use tree_sitter::{Node, Tree};
 
 impl LanguageParser for RustParser {
-    fn parse(source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
-        let mut parser = Parser::new();
-        parser
-            .set_language(tree_sitter_rust::language())
-            .expect("Error loading Rust grammar");
-
-        let tree = parser.parse(source, None).unwrap();
+    fn parse(tree: &Tree, source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
         let mut imports = Vec::new();
         let entities = parse_rust_node(tree.root_node(), source.as_bytes(), parent_id, &mut imports);
         (entities, imports)
@@ -95,6 +89,42 @@ fn is_builtin(name: &str) -> bool {
     || matches!(name, "Vec" | "String" | "Option" | "Result" | "Box" | "Rc" | "Arc" | "Mutex" | "RwLock")
 }
 
+/// Flattens a use tree into paths: `crate::{a, b::{c, d as e}}` ->
+/// `crate::a`, `crate::b::c`, `crate::b::d as e`. Aliases are kept after
+/// ` as ` (see `parser_utils::split_import_alias`) and globs as `path::*`.
+fn expand_use_tree(node: Node, source: &[u8], prefix: &str, out: &mut Vec<String>) {
+    let join = |path: &str| if prefix.is_empty() { path.to_string() } else { format!("{prefix}::{path}") };
+    match node.kind() {
+        "use_list" => {
+            let mut cursor = node.walk();
+            for item in node.named_children(&mut cursor) {
+                expand_use_tree(item, source, prefix, out);
+            }
+        }
+        "scoped_use_list" => {
+            let path = node
+                .child_by_field_name("path")
+                .map(|p| join(&parser_utils::get_text(p, source)))
+                .unwrap_or_else(|| prefix.to_string());
+            if let Some(list) = node.child_by_field_name("list") {
+                expand_use_tree(list, source, &path, out);
+            }
+        }
+        "use_as_clause" => {
+            if let Some(path) = node.child_by_field_name("path") {
+                let path = join(&parser_utils::get_text(path, source));
+                match node.child_by_field_name("alias").map(|a| parser_utils::get_text(a, source)) {
+                    // `use a::b as _` only brings trait methods into scope
+                    Some(alias) if alias != "_" => out.push(format!("{path} as {alias}")),
+                    _ => out.push(path),
+                }
+            }
+        }
+        "line_comment" | "block_comment" => {}
+        _ => out.push(join(&parser_utils::get_text(node, source))),
+    }
+}
+
 const RS_COMPLEXITY_KINDS: &[&str] = &[
     "if_expression", "match_expression", "while_expression", "for_expression",
     "loop_expression", "?", "match_arm",
@@ -115,16 +145,50 @@ fn parse_rust_node(
 
         match kind {
             "use_declaration" => {
-                let import_path = parser_utils::get_text(child, source)
-                    .trim_start_matches("use ")
-                    .trim_end_matches(';')
-                    .to_string();
-                if import_path.starts_with("crate::") {
-                    let path = import_path.replace("crate::", "src/").replace("::", "/");
-                    imports.push(format!("{}.rs", path));
+                // Each leaf of the use tree as a full path, resolved later by resolvers::rust
+                if let Some(argument) = child.child_by_field_name("argument") {
+                    expand_use_tree(argument, source, "", imports);
                 }
             }
 
+            // An inline module is a Building of its own; its `use` paths stay
+            // on the file, rewritten to be relative to the file's module
+            "mod_item" => {
+                let Some(body) = child.child_by_field_name("body") else {
+                    continue;
+                };
+                let name = child
+                    .child_by_field_name("name")
+                    .map(|n| parser_utils::get_text(n, source))
+                    .unwrap_or_default();
+                let id = format!("{parent_id}::{name}");
+                let mut inner_imports = Vec::new();
+                let children = parse_rust_node(body, source, &id, &mut inner_imports);
+                imports.extend(inner_imports.into_iter().map(|path| {
+                    if let Some(rest) = path.strip_prefix("super::") {
+                        format!("self::{rest}")
+                    } else if let Some(rest) = path.strip_prefix("self::") {
+                        format!("self::{name}::{rest}")
+                    } else {
+                        path
+                    }
+                }));
+
+                entities.push(GameEntity::Building {
+                    id,
+                    name,
+                    building_type: "module".to_string(),
+                    is_public: is_public(child, source),
+                    is_test: false,
+                    loc: parser_utils::count_lines(child),
+                    imports: vec![],
+                    supertypes: vec![],
+                    children,
+                    span: Some(parser_utils::span(child, parent_id)),
+                    metadata: None,
+                });
+            }
+
             "struct_item" | "enum_item" | "trait_item" => {
                 let name = child
                     .child_by_field_name("name")
@@ -140,9 +204,12 @@ fn parse_rust_node(
                     name,
                     building_type: kind.replace("_item", ""),
                     is_public: is_public(child, source),
+                    is_test: false,
                     loc,
                     imports: vec![],
+                    supertypes: parser_utils::supertypes(child, source),
                     children,
+                    span: Some(parser_utils::span(child, parent_id)),
                     metadata: None,
                 });
             }
@@ -168,6 +235,12 @@ fn parse_rust_node(
                     "{parent_id}::{}",
                     name.replace(' ', "_").replace(['<', '>', ':'], "_")
                 );
+                // An impl block extends both the trait it implements and the type it is for.
+                let supertypes = [trait_node, self_type_node]
+                    .into_iter()
+                    .flatten()
+                    .map(|n| parser_utils::clean_type_name(&parser_utils::get_text(n, source)))
+                    .collect();
                 let children = parse_rust_node(child, source, &id, imports);
                 let loc = parser_utils::count_lines(child);
 
@@ -176,9 +249,12 @@ fn parse_rust_node(
                     name,
                     building_type: "impl".to_string(),
                     is_public: false,
+                    is_test: false,
                     loc,
                     imports: vec![],
+                    supertypes,
                     children,
+                    span: Some(parser_utils::span(child, parent_id)),
                     metadata: None,
                 });
             }
@@ -218,6 +294,7 @@ fn parse_rust_node(
                     room_type: "function".to_string(),
                     is_main,
                     is_async: is_async_fn,
+                    is_test: false,
                     visibility: visibility.to_string(),
                     complexity,
                     loc,
@@ -225,6 +302,7 @@ fn parse_rust_node(
                     return_type,
                     calls,
                     children: contents,
+                    span: Some(parser_utils::span(child, parent_id)),
                     metadata: None,
                 });
             }
@@ -268,6 +346,7 @@ fn parse_rust_node(
                         datatype,
                         is_mutable,
                         value_hint,
+                        span: Some(parser_utils::span(child, parent_id)),
                         metadata: None,
                     });
                 }
@@ -292,6 +371,7 @@ fn parse_rust_node(
                         datatype,
                         is_mutable: false,
                         value_hint: None,
+                        span: Some(parser_utils::span(child, parent_id)),
                         metadata: None,
                     });
                 }
@@ -310,6 +390,7 @@ fn parse_rust_node(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::languages::registry;
 
     #[test]
     fn test_parse_impl_blocks() {
@@ -340,7 +421,7 @@ mod tests {
         }
         "#;
 
-        let (entities, _imports) = RustParser::parse(source_code, "test_file");
+        let (entities, _imports) = registry::parse_by_extension("rs", source_code, "test_file").unwrap();
 
         let impl_blocks: Vec<_> = entities
             .iter()
@@ -371,4 +452,69 @@ mod tests {
         assert!(has_inherent_impl, "Should have an inherent impl block");
         assert!(has_trait_impl, "Should have a trait impl block");
     }
+
+    #[test]
+    fn test_spans_cover_items() {
+        let source_code = "struct A;\n\nfn run() {\n    helper();\n}\n";
+
+        let (entities, _imports) = registry::parse_by_extension("rs", source_code, "src/lib.rs").unwrap();
+
+        let span = entities
+            .iter()
+            .find(|e| matches!(e, GameEntity::Room { name, .. } if name == "run"))
+            .and_then(|e| e.span())
+            .expect("run should have a span");
+
+        assert_eq!(span.file, "src/lib.rs");
+        assert_eq!((span.start_line, span.end_line), (3, 5));
+        assert_eq!(&source_code[span.start_byte as usize..span.end_byte as usize], "fn run() {\n    helper();\n}");
+    }
+
+    #[test]
+    fn test_supertypes_of_traits_and_impls() {
+        let source_code = r#"
+        trait Shape: Clone + std::fmt::Debug {}
+        impl<T> Shape for Wrapper<T> {}
+        "#;
+
+        let (entities, _imports) = registry::parse_by_extension("rs", source_code, "src/lib.rs").unwrap();
+
+        let supertypes_of = |wanted: &str| {
+            entities.iter().find_map(|e| match e {
+                GameEntity::Building { name, supertypes, .. } if name == wanted => Some(supertypes.clone()),
+                _ => None,
+            })
+        };
+
+        assert_eq!(supertypes_of("Shape").unwrap(), vec!["Clone", "std::fmt::Debug"]);
+        assert_eq!(supertypes_of("impl Shape for Wrapper<T>").unwrap(), vec!["Shape", "Wrapper"]);
+    }
+
+    #[test]
+    fn test_use_trees_are_flattened() {
+        let source_code = r#"
+        use crate::{db::{self, world::store_world}, models::GameEntity as Entity};
+        use serde::Serialize;
+        pub use super::registry::*;
+
+        mod tests {
+            use super::*;
+            use self::helpers::setup;
+        }
+        "#;
+
+        let (_entities, imports) = registry::parse_by_extension("rs", source_code, "src/parser.rs").unwrap();
+        assert_eq!(
+            imports,
+            vec![
+                "crate::db::self",
+                "crate::db::world::store_world",
+                "crate::models::GameEntity as Entity",
+                "serde::Serialize",
+                "super::registry::*",
+                "self::*",
+                "self::tests::helpers::setup",
+            ]
+        );
+    }
 }
@@ -1,6 +1,9 @@
+use tree_sitter::Tree;
+
 use crate::models::GameEntity;
 
 pub trait LanguageParser {
-    fn parse(source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>);
+    /// Entities and imports of a file, from the tree `registry::parse_tree` built for it.
+    fn parse(tree: &Tree, source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>);
     fn extensions() -> &'static [&'static str];
 }
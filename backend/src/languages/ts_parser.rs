@@ -4,18 +4,12 @@ use super::traits::LanguageParser;
 use super::registry::TypeScriptParser;
 
 use tracing::{debug, instrument};
-use tree_sitter::{Node, Parser};
+use tree_sitter::{Node, Tree};
 
 impl LanguageParser for TypeScriptParser {
-    fn parse(source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
-        let mut parser = Parser::new();
-        parser
-            .set_language(tree_sitter_typescript::language_typescript())
-            .expect("Error loading TypeScript grammar");
-
-        let tree = parser.parse(source, None).unwrap();
-        let mut imports = Vec::new();
-        let entities = parse_node(tree.root_node(), source.as_bytes(), parent_id, &mut imports);
+    fn parse(tree: &Tree, source: &str, parent_id: &str) -> (Vec<GameEntity>, Vec<String>) {
+        let entities = parse_node(tree.root_node(), source.as_bytes(), parent_id);
+        let imports = parser_utils::js_module_imports(tree.root_node(), source.as_bytes());
         (entities, imports)
     }
 
@@ -149,12 +143,11 @@ fn get_comments(node: Node, source: &[u8]) -> Option<String> {
     if comments.is_empty() { None } else { Some(comments.join(" ")) }
 }
 
-#[instrument(skip(node, source, imports), level = "trace")]
+#[instrument(skip(node, source), level = "trace")]
 fn parse_node(
     node: Node,
     source: &[u8],
     parent_id: &str,
-    imports: &mut Vec<String>,
 ) -> Vec<GameEntity> {
     let mut entities = Vec::new();
     let mut cursor = node.walk();
@@ -164,16 +157,6 @@ fn parse_node(
         let comments = get_comments(child, source);
 
         match kind {
-            "import_statement" => {
-                if let Some(source_node) = child.child_by_field_name("source") {
-                    let text = parser_utils::get_text(source_node, source);
-                    let import_path = text.trim_matches(|c| c == '"' || c == '\'' || c == '`');
-                    if import_path.starts_with('.') {
-                        imports.push(format!("{import_path}.ts"));
-                    }
-                }
-            }
-
             "class_declaration" | "abstract_class_declaration" | "interface_declaration" | "enum_declaration" => {
                 let name = child
                     .child_by_field_name("name")
@@ -183,17 +166,20 @@ fn parse_node(
                 let is_public = is_exported(child, source);
                 let loc = parser_utils::count_lines(child);
                 let body_node = child.child_by_field_name("body").unwrap_or(child);
-                let children = parse_node(body_node, source, &id, imports);
+                let children = parse_node(body_node, source, &id);
                 let building_type = match kind {
                     "interface_declaration" => "interface",
                     "enum_declaration" => "enum",
                     _ => "class",
                 };
 
+                let mut metadata = make_doc_metadata(comments);
+                if kind == "abstract_class_declaration" { parser_utils::mark_abstract(&mut metadata); }
+
                 debug!(name = %name, kind = "Building", "Found {building_type}");
                 entities.push(GameEntity::Building {
-                    id, name, building_type: building_type.to_string(), is_public, loc,
-                    imports: vec![], children, metadata: make_doc_metadata(comments),
+                    id, name, building_type: building_type.to_string(), is_public, is_test: false, loc,
+                    imports: vec![], supertypes: parser_utils::supertypes(child, source), children, span: Some(parser_utils::span(child, parent_id)), metadata,
                 });
             }
 
@@ -205,8 +191,8 @@ fn parse_node(
                 let id = format!("{parent_id}::{name}");
                 entities.push(GameEntity::Building {
                     id, name, building_type: "type_alias".to_string(),
-                    is_public: is_exported(child, source), loc: parser_utils::count_lines(child),
-                    imports: vec![], children: vec![], metadata: make_doc_metadata(comments),
+                    is_public: is_exported(child, source), is_test: false, loc: parser_utils::count_lines(child),
+                    imports: vec![], supertypes: vec![], children: vec![], span: Some(parser_utils::span(child, parent_id)), metadata: make_doc_metadata(comments),
                 });
             }
 
@@ -232,14 +218,15 @@ fn parse_node(
                     .map(|b| parser_utils::extract_function_calls(b, source, "call_expression", is_builtin))
                     .unwrap_or_default();
                 let children = body
-                    .map(|b| parse_node(b, source, &id, imports))
+                    .map(|b| parse_node(b, source, &id))
                     .unwrap_or_default();
 
                 entities.push(GameEntity::Room {
                     id, name,
                     room_type: if kind == "method_definition" { "method".into() } else { "function".into() },
-                    is_main: false, is_async: is_async_fn, visibility: visibility.to_string(),
+                    is_main: false, is_async: is_async_fn, is_test: false, visibility: visibility.to_string(),
                     complexity, loc, parameters, return_type, calls, children,
+                    span: Some(parser_utils::span(child, parent_id)),
                     metadata: make_doc_metadata(comments),
                 });
             }
@@ -270,14 +257,15 @@ fn parse_node(
                             .map(|b| parser_utils::extract_function_calls(b, source, "call_expression", is_builtin))
                             .unwrap_or_default();
                         let children = body
-                            .map(|b| parse_node(b, source, &id, imports))
+                            .map(|b| parse_node(b, source, &id))
                             .unwrap_or_default();
 
                         entities.push(GameEntity::Room {
                             id, name, room_type: "arrow_function".to_string(),
-                            is_main: false, is_async: is_async_fn,
+                            is_main: false, is_async: is_async_fn, is_test: false,
                             visibility: if is_exported(child, source) { "public" } else { "private" }.into(),
                             complexity, loc, parameters, return_type, calls, children,
+                            span: Some(parser_utils::span(decl, parent_id)),
                             metadata: make_doc_metadata(comments.clone()),
                         });
                         continue;
@@ -297,6 +285,7 @@ fn parse_node(
                         id, name,
                         artifact_type: if is_const { "constant".into() } else { "variable".into() },
                         datatype, is_mutable: !is_const, value_hint,
+                        span: Some(parser_utils::span(decl, parent_id)),
                         metadata: make_doc_metadata(comments.clone()),
                     });
                 }
@@ -316,7 +305,7 @@ fn parse_node(
 
                 entities.push(GameEntity::Artifact {
                     id, name, artifact_type: "field".to_string(), datatype,
-                    is_mutable: true, value_hint: None, metadata: make_doc_metadata(comments),
+                    is_mutable: true, value_hint: None, span: Some(parser_utils::span(child, parent_id)), metadata: make_doc_metadata(comments),
                 });
             }
 
@@ -329,18 +318,19 @@ fn parse_node(
                 entities.push(GameEntity::Artifact {
                     id, name, artifact_type: "enum_value".to_string(),
                     datatype: "enum".to_string(), is_mutable: false, value_hint: None,
+                    span: Some(parser_utils::span(child, parent_id)),
                     metadata: make_doc_metadata(comments),
                 });
             }
 
             "statement_block" | "export_statement" => {
-                entities.extend(parse_node(child, source, parent_id, imports));
+                entities.extend(parse_node(child, source, parent_id));
             }
             _ => {
                 if !matches!(kind, "class_declaration" | "function_declaration" | "interface_declaration" | "lexical_declaration")
                     && child.child_count() > 0
                 {
-                    entities.extend(parse_node(child, source, parent_id, imports));
+                    entities.extend(parse_node(child, source, parent_id));
                 }
             }
         }
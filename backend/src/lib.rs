@@ -1,24 +1,46 @@
+pub mod artifacts;
 pub mod auth;
+pub mod clones;
+pub mod coupling;
+pub mod coverage;
+pub mod cycles;
+pub mod dead_code;
 pub mod db;
 pub mod domain;
+pub mod encoding;
 pub mod error;
+pub mod ffi;
 pub mod git_layer;
+pub mod graph;
 pub mod hierarchy;
 pub mod languages;
+pub mod metrics;
 pub mod models;
 pub mod multiplayer;
+pub mod network;
 pub mod parser;
+pub mod resolvers;
 pub mod routes;
+pub mod rules;
 pub mod services;
+pub mod sources;
 pub mod state;
 pub mod symbol_table;
+#[cfg(test)]
+pub mod test_support;
+pub mod testing;
+pub mod type_refs;
+pub mod unresolved;
 pub mod walker;
 
-use axum::{Router, response::IntoResponse, routing::get, routing::post};
+use axum::{Router, extract::DefaultBodyLimit, response::IntoResponse, routing::get, routing::post};
 use std::sync::Arc;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::{AllowOrigin, CorsLayer};
 use http::{header, Method};
 
+const COVERAGE_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
 async fn health_check() -> impl IntoResponse {
     axum::Json(serde_json::json!({"status": "healthy"}))
 }
@@ -28,6 +50,41 @@ pub fn build_app(state: Arc<state::AppState>) -> Router {
 
     Router::new()
         .route("/parse", post(routes::parse_repo_handler))
+        .route("/timeline", post(routes::timeline_handler))
+        .route("/worlds/:world_id", get(routes::entities::world_summary))
+        .route("/worlds/:world_id/routes", post(routes::entities::routes_for_entities))
+        .route("/worlds/:world_id/unresolved", get(routes::entities::unresolved_summary))
+        .route("/worlds/:world_id/violations", get(routes::entities::rule_violations))
+        .route("/worlds/:world_id/dead-code", get(routes::entities::dead_code))
+        // Coverage reports for large repositories run well past axum's 2 MB default.
+        .route(
+            "/worlds/:world_id/coverage",
+            post(routes::entities::import_coverage).layer(DefaultBodyLimit::max(COVERAGE_BODY_LIMIT)),
+        )
+        .route("/worlds/:world_id/entities/:entity_id", get(routes::entities::entity))
+        .route(
+            "/worlds/:world_id/entities/:entity_id/children",
+            get(routes::entities::entity_children),
+        )
+        .route(
+            "/worlds/:world_id/entities/:entity_id/source",
+            get(routes::source::entity_source),
+        )
+        .route(
+            "/worlds/:world_id/entities/:entity_id/callers",
+            get(routes::graph::callers),
+        )
+        .route(
+            "/worlds/:world_id/entities/:entity_id/callees",
+            get(routes::graph::callees),
+        )
+        .route(
+            "/worlds/:world_id/entities/:entity_id/impact",
+            get(routes::graph::impact),
+        )
+        .route("/worlds/:world_id/path", get(routes::graph::shortest_path))
+        .route("/worlds/:world_id/cycles", get(routes::graph::cycles))
+        .route("/worlds/:world_id/clones", get(routes::graph::clones))
         .route("/auth/login", get(auth::routes::login))
         .route("/auth/callback", get(auth::routes::callback))
         .route("/auth/google/login", get(auth::routes::google_login))
@@ -41,6 +98,8 @@ pub fn build_app(state: Arc<state::AppState>) -> Router {
         .with_state(state)
         .route("/", get(health_check))
         .route("/health", get(health_check))
+        // gzip, brotli or zstd, whichever the client's Accept-Encoding prefers.
+        .layer(CompressionLayer::new())
         .layer(
             CorsLayer::new()
                 .allow_origin(AllowOrigin::predicate(
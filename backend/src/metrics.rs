@@ -0,0 +1,313 @@
+//! Code metrics computed from the tree-sitter tree of each file.
+//!
+//! Per Room (stored in its metadata; cyclomatic complexity stays in the
+//! Room's `complexity` field):
+//! - `cognitive_complexity`: SonarSource's cognitive complexity. +1 per
+//!   branch or loop, plus its nesting level; +1 per `else`/`elif`; +1 per
+//!   run of `&&`/`||`.
+//! - `nesting_depth`: deepest nesting of control structures.
+//! - `halstead_volume`: N * log2(n) over the Room's tokens. Identifiers and
+//!   literals are operands; every other token is an operator.
+//! - `maintainability_index`: max(0, (171 - 5.2 ln V - 0.23 G - 16.2 ln LOC) * 100 / 171),
+//!   the 0..100 variant used by Visual Studio.
+//!
+//! Buildings, Districts and Cities get the distribution of their Rooms:
+//! `<metric>_p50`, `<metric>_p90` and `<metric>_max`. Maintainability is
+//! the exception: lower is worse, so it gets `_p50`, `_p10` and `_min`.
+
+use std::collections::HashMap;
+
+use tree_sitter::{Node, Tree};
+
+use crate::languages::parser_utils;
+use crate::models::GameEntity;
+
+/// Version of the `complexity_score` formula below. Bump it whenever the
+/// formula or the per-Room metrics change, so scores of different versions
+/// are never compared.
+pub const SCORE_VERSION: u32 = 2;
+
+/// Branches and loops: +1 cognitive complexity plus the nesting level, and they nest their bodies.
+const STRUCTURAL_KINDS: &[&str] = &[
+    "if_statement", "if_expression", "if_let_expression",
+    "for_statement", "for_in_statement", "enhanced_for_statement", "for_range_loop", "for_expression",
+    "while_statement", "while_expression", "while_let_expression", "do_statement", "loop_expression",
+    "switch_statement", "switch_expression", "match_expression", "match_statement",
+    "catch_clause", "except_clause", "conditional_expression", "ternary_expression",
+];
+
+/// Nested functions and closures deepen nesting without adding complexity themselves.
+const NESTING_KINDS: &[&str] = &[
+    "closure_expression", "arrow_function", "function_expression", "lambda", "lambda_expression",
+    "function_item", "function_definition", "function_declaration", "method_declaration",
+];
+
+/// Tokens counted as Halstead operands besides identifiers and literals.
+const OPERAND_KINDS: &[&str] = &[
+    "number", "integer", "float", "string", "string_content", "string_fragment", "char_literal",
+    "true", "false", "null", "none", "None", "self", "this", "super",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RoomMetrics {
+    pub cyclomatic: f32,
+    pub cognitive: f32,
+    pub nesting_depth: f32,
+    pub halstead_volume: f32,
+    pub maintainability_index: f32,
+}
+
+impl RoomMetrics {
+    fn from_metadata(complexity: u32, metadata: &HashMap<String, String>) -> Option<Self> {
+        let get = |key: &str| metadata.get(key).and_then(|v| v.parse::<f32>().ok());
+        Some(Self {
+            cyclomatic: complexity as f32,
+            cognitive: get("cognitive_complexity")?,
+            nesting_depth: get("nesting_depth")?,
+            halstead_volume: get("halstead_volume")?,
+            maintainability_index: get("maintainability_index")?,
+        })
+    }
+}
+
+/// Compute metrics for every Room of a parsed file and store them in its metadata.
+pub fn annotate_rooms(tree: &Tree, source: &str, entities: &mut [GameEntity]) {
+    annotate(entities, tree.root_node(), source.as_bytes());
+}
+
+fn annotate(entities: &mut [GameEntity], root: Node, source: &[u8]) {
+    for entity in entities {
+        match entity {
+            GameEntity::Room { complexity, loc, span: Some(span), metadata, children, .. } => {
+                if let Some(node) = root.descendant_for_byte_range(span.start_byte as usize, span.end_byte as usize) {
+                    let metrics = room_metrics(node, source, *complexity, *loc);
+                    let metadata = metadata.get_or_insert_with(HashMap::new);
+                    metadata.insert("cognitive_complexity".into(), format!("{}", metrics.cognitive));
+                    metadata.insert("nesting_depth".into(), format!("{}", metrics.nesting_depth));
+                    metadata.insert("halstead_volume".into(), format!("{:.1}", metrics.halstead_volume));
+                    metadata.insert("maintainability_index".into(), format!("{:.1}", metrics.maintainability_index));
+                }
+                annotate(children, root, source);
+            }
+            GameEntity::City { children, .. }
+            | GameEntity::District { children, .. }
+            | GameEntity::Building { children, .. }
+            | GameEntity::Room { children, .. } => annotate(children, root, source),
+            GameEntity::Artifact { .. } => {}
+        }
+    }
+}
+
+pub fn room_metrics(node: Node, source: &[u8], cyclomatic: u32, loc: u32) -> RoomMetrics {
+    let mut cognitive = Cognitive::default();
+    // The Room's own node does not count as nesting
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        cognitive.visit(child, 0, source);
+    }
+
+    let halstead_volume = halstead_volume(node, source);
+    let maintainability_index = (171.0
+        - 5.2 * halstead_volume.max(1.0).ln()
+        - 0.23 * cyclomatic as f32
+        - 16.2 * (loc.max(1) as f32).ln())
+        * 100.0
+        / 171.0;
+
+    RoomMetrics {
+        cyclomatic: cyclomatic as f32,
+        cognitive: cognitive.score as f32,
+        nesting_depth: cognitive.max_nesting as f32,
+        halstead_volume,
+        maintainability_index: maintainability_index.clamp(0.0, 100.0),
+    }
+}
+
+#[derive(Default)]
+struct Cognitive {
+    score: u32,
+    max_nesting: u32,
+}
+
+impl Cognitive {
+    fn visit(&mut self, node: Node, nesting: u32, source: &[u8]) {
+        let kind = node.kind();
+        let child_nesting = if STRUCTURAL_KINDS.contains(&kind) {
+            if is_else_if(node) {
+                // Scored like an `else`, and its body sits at the `if`'s level
+                self.score += 1;
+                nesting
+            } else {
+                self.score += 1 + nesting;
+                self.max_nesting = self.max_nesting.max(nesting + 1);
+                nesting + 1
+            }
+        } else if NESTING_KINDS.contains(&kind) {
+            nesting + 1
+        } else {
+            if matches!(kind, "elif_clause") || (kind == "else_clause" && !has_child_if(node)) {
+                self.score += 1;
+            }
+            if let Some(operator) = logical_operator(node, source)
+                && node.parent().and_then(|p| logical_operator(p, source)) != Some(operator)
+            {
+                self.score += 1;
+            }
+            nesting
+        };
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.visit(child, child_nesting, source);
+        }
+    }
+}
+
+fn is_else_if(node: Node) -> bool {
+    let Some(parent) = node.parent() else { return false };
+    parent.kind() == "else_clause"
+        || (parent.kind().starts_with("if_") && parent.child_by_field_name("alternative") == Some(node))
+}
+
+fn has_child_if(node: Node) -> bool {
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor).any(|c| c.kind().starts_with("if_"))
+}
+
+fn logical_operator(node: Node, source: &[u8]) -> Option<String> {
+    if !matches!(node.kind(), "binary_expression" | "boolean_operator") {
+        return None;
+    }
+    let operator = parser_utils::get_text(node.child_by_field_name("operator")?, source);
+    matches!(operator.as_str(), "&&" | "||" | "and" | "or").then_some(operator)
+}
+
+fn halstead_volume(node: Node, source: &[u8]) -> f32 {
+    let mut operators: HashMap<String, u32> = HashMap::new();
+    let mut operands: HashMap<String, u32> = HashMap::new();
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        if node.child_count() == 0 {
+            let kind = node.kind();
+            if kind.contains("comment") {
+                continue;
+            }
+            if kind.contains("identifier") || kind.contains("literal") || OPERAND_KINDS.contains(&kind) {
+                *operands.entry(parser_utils::get_text(node, source)).or_default() += 1;
+            } else {
+                *operators.entry(kind.to_string()).or_default() += 1;
+            }
+            continue;
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.children(&mut cursor));
+    }
+
+    let length: u32 = operators.values().chain(operands.values()).sum();
+    let vocabulary = operators.len() + operands.len();
+    if vocabulary < 2 { 0.0 } else { length as f32 * (vocabulary as f32).log2() }
+}
+
+// --- Roll-ups ---
+
+/// Picks one metric out of a Room's metrics.
+type Metric = fn(&RoomMetrics) -> f32;
+
+/// Write Room metric distributions onto every Building, District and City,
+/// and return the metrics of every production Room in the world. Test code
+/// keeps its per-Room metrics but stays out of the distributions.
+pub fn roll_up(cities: &mut [GameEntity]) -> Vec<RoomMetrics> {
+    cities.iter_mut().flat_map(roll_up_entity).collect()
+}
+
+fn roll_up_entity(entity: &mut GameEntity) -> Vec<RoomMetrics> {
+    match entity {
+        GameEntity::Room { is_test: true, .. } | GameEntity::Building { is_test: true, .. } => Vec::new(),
+        GameEntity::Room { complexity, children, metadata, .. } => {
+            let own = metadata.as_ref().and_then(|m| RoomMetrics::from_metadata(*complexity, m));
+            own.into_iter().chain(children.iter_mut().flat_map(roll_up_entity)).collect()
+        }
+        GameEntity::City { children, metadata, .. }
+        | GameEntity::District { children, metadata, .. }
+        | GameEntity::Building { children, metadata, .. } => {
+            let samples: Vec<RoomMetrics> = children.iter_mut().flat_map(roll_up_entity).collect();
+            if !samples.is_empty() {
+                write_distribution(metadata.get_or_insert_with(HashMap::new), &samples);
+            }
+            samples
+        }
+        GameEntity::Artifact { .. } => Vec::new(),
+    }
+}
+
+fn write_distribution(metadata: &mut HashMap<String, String>, samples: &[RoomMetrics]) {
+    let series: [(&str, Metric); 4] = [
+        ("cyclomatic", |m| m.cyclomatic),
+        ("cognitive", |m| m.cognitive),
+        ("nesting_depth", |m| m.nesting_depth),
+        ("halstead_volume", |m| m.halstead_volume),
+    ];
+    for (name, value) in series {
+        let values = sorted(samples, value);
+        metadata.insert(format!("{name}_p50"), format!("{:.1}", percentile(&values, 50.0)));
+        metadata.insert(format!("{name}_p90"), format!("{:.1}", percentile(&values, 90.0)));
+        metadata.insert(format!("{name}_max"), format!("{:.1}", values[values.len() - 1]));
+    }
+    let values = sorted(samples, |m| m.maintainability_index);
+    metadata.insert("maintainability_index_p50".into(), format!("{:.1}", percentile(&values, 50.0)));
+    metadata.insert("maintainability_index_p10".into(), format!("{:.1}", percentile(&values, 10.0)));
+    metadata.insert("maintainability_index_min".into(), format!("{:.1}", values[0]));
+}
+
+fn sorted(samples: &[RoomMetrics], value: Metric) -> Vec<f32> {
+    let mut values: Vec<f32> = samples.iter().map(value).collect();
+    values.sort_by(f32::total_cmp);
+    values
+}
+
+/// Nearest-rank percentile of sorted, non-empty `values`.
+fn percentile(values: &[f32], p: f32) -> f32 {
+    let rank = ((p / 100.0) * values.len() as f32).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+/// World complexity score, version [`SCORE_VERSION`]: 0 (simple) to 10.
+///
+/// `10 * (0.35 * min(cyclomatic_p90 / 20, 1) + 0.35 * min(cognitive_p90 / 30, 1) + 0.30 * (1 - maintainability_p50 / 100))`
+///
+/// Only per-Room distributions enter the formula, so the score does not
+/// grow with repository size and worlds of any size can be compared.
+pub fn complexity_score(rooms: &[RoomMetrics]) -> f32 {
+    if rooms.is_empty() {
+        return 0.0;
+    }
+    let cyclomatic = percentile(&sorted(rooms, |m| m.cyclomatic), 90.0);
+    let cognitive = percentile(&sorted(rooms, |m| m.cognitive), 90.0);
+    let maintainability = percentile(&sorted(rooms, |m| m.maintainability_index), 50.0);
+    let score = 0.35 * (cyclomatic / 20.0).min(1.0) + 0.35 * (cognitive / 30.0).min(1.0) + 0.30 * (1.0 - maintainability / 100.0);
+    (score * 1000.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::registry;
+
+    #[test]
+    fn test_room_metrics_follow_nesting() {
+        let source = "fn check(items: &[u32], strict: bool) -> u32 {\n    let mut n = 0;\n    for item in items {\n        if *item > 3 && strict || *item == 0 {\n            n += 1;\n        } else if *item == 1 {\n            n += 2;\n        } else {\n            n += 3;\n        }\n    }\n    n\n}\n";
+        let tree = registry::parse_tree("rs", source).unwrap();
+        let function = tree.root_node().child(0).unwrap();
+        let metrics = room_metrics(function, source.as_bytes(), 4, 13);
+
+        // for +1, if +2 (nested), `&& ||` +2, else if +1, else +1
+        assert_eq!(metrics.cognitive, 7.0);
+        assert_eq!(metrics.nesting_depth, 2.0);
+        assert!(metrics.halstead_volume > 100.0);
+        assert!(metrics.maintainability_index > 0.0 && metrics.maintainability_index < 100.0);
+
+        let simple = RoomMetrics { cyclomatic: 2.0, cognitive: 0.0, nesting_depth: 0.0, halstead_volume: 10.0, maintainability_index: 100.0 };
+        assert_eq!(complexity_score(&[simple]), 0.35);
+        assert_eq!(complexity_score(&[RoomMetrics { cyclomatic: 40.0, cognitive: 60.0, maintainability_index: 0.0, ..simple }]), 10.0);
+    }
+}
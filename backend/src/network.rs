@@ -0,0 +1,776 @@
+//! HTTP endpoints, client requests and the NetworkRequest routes between them.
+//!
+//! Server routes (axum, Express, Flask, FastAPI, Spring) and literal client
+//! URLs (`fetch`, axios, reqwest, requests, httpx, RestTemplate) become
+//! `http_endpoint` and `http_request` Artifacts while a file is parsed; once
+//! every City is built, requests are matched to endpoints by method and path.
+
+use std::collections::{HashMap, HashSet};
+use tree_sitter::{Node, Tree};
+
+use crate::artifacts::{attach, push_unique, Anchor};
+use crate::languages::parser_utils::{self, string_value, walk};
+use crate::models::{GameEntity, Route, RouteType, SourceSpan};
+use crate::symbol_table::{Confidence, SymbolTable};
+
+const HTTP_METHODS: &[&str] = &["get", "post", "put", "delete", "patch", "head", "options"];
+
+/// How an endpoint names the code that serves it.
+enum Handler {
+    /// A function passed by name, e.g. axum `get(list_users)` or Express `app.get("/", list)`.
+    Name(String),
+    /// The function whose definition starts at this byte (decorators and annotations).
+    DefinedAt(usize),
+}
+
+enum Finding {
+    Endpoint { method: String, path: String, handler: Option<Handler> },
+    Request { method: String, url: String },
+}
+
+struct Located {
+    finding: Finding,
+    start_byte: usize,
+    end_byte: usize,
+    span: SourceSpan,
+}
+
+/// Adds `http_endpoint` and `http_request` Artifacts for the HTTP routes a file
+/// serves and the literal URLs it calls. Each Artifact is placed under the Room
+/// or Building containing it (for decorated handlers, the handler itself), or
+/// at file level when it sits outside any of them.
+pub fn annotate_http(ext: &str, tree: &Tree, source: &str, file_id: &str, entities: &mut Vec<GameEntity>) {
+    let mut findings = Vec::new();
+    let bytes = source.as_bytes();
+    let root = tree.root_node();
+    match ext {
+        "rs" => walk(root, &mut |n| rust_findings(n, bytes, file_id, &mut findings)),
+        "ts" | "tsx" | "js" | "jsx" => walk(root, &mut |n| js_findings(n, bytes, file_id, &mut findings)),
+        "py" => walk(root, &mut |n| python_findings(n, bytes, file_id, &mut findings)),
+        "java" => walk(root, &mut |n| java_findings(n, bytes, file_id, &mut findings)),
+        _ => return,
+    }
+
+    for located in findings {
+        let anchor = match &located.finding {
+            Finding::Endpoint { handler: Some(Handler::DefinedAt(byte)), .. } => Anchor::StartsAt(*byte),
+            _ => Anchor::Contains(located.start_byte, located.end_byte),
+        };
+        let artifact_for = |owner_id: &str| to_artifact(&located, owner_id);
+        if !attach(entities, &anchor, &artifact_for) {
+            let artifact = to_artifact(&located, file_id);
+            push_unique(entities, artifact);
+        }
+    }
+}
+
+fn located(finding: Finding, node: Node, file_id: &str) -> Located {
+    Located {
+        finding,
+        start_byte: node.start_byte(),
+        end_byte: node.end_byte(),
+        span: parser_utils::span(node, file_id),
+    }
+}
+
+// --- Rust: axum `Router::route`, reqwest ---
+
+fn rust_findings(node: Node, source: &[u8], file_id: &str, out: &mut Vec<Located>) {
+    if node.kind() != "call_expression" {
+        return;
+    }
+    let (Some(function), Some(args)) = (node.child_by_field_name("function"), node.child_by_field_name("arguments")) else {
+        return;
+    };
+    let first_arg = args.named_child(0);
+
+    match function.kind() {
+        // .route("/path", get(handler).post(other))
+        "field_expression" => {
+            let field = field_name(function, source);
+            if field == "route"
+                && let Some(path) = first_arg.and_then(|a| string_value(a, source))
+                && let Some(router) = args.named_child(1)
+            {
+                for (method, handler) in axum_method_router(router, source) {
+                    let endpoint = Finding::Endpoint { method, path: path.clone(), handler: handler.map(Handler::Name) };
+                    out.push(located(endpoint, node, file_id));
+                }
+            } else if HTTP_METHODS.contains(&field.as_str())
+                && let Some(url) = first_arg.and_then(|a| rust_url_arg(a, source))
+            {
+                out.push(located(Finding::Request { method: field.to_uppercase(), url }, node, file_id));
+            }
+        }
+        // reqwest::get("https://...")
+        "scoped_identifier" => {
+            let text = parser_utils::get_text(function, source);
+            if let Some(method) = text.strip_prefix("reqwest::")
+                && HTTP_METHODS.contains(&method)
+                && let Some(url) = first_arg.and_then(|a| rust_url_arg(a, source))
+            {
+                out.push(located(Finding::Request { method: method.to_uppercase(), url }, node, file_id));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn field_name(field_expression: Node, source: &[u8]) -> String {
+    field_expression
+        .child_by_field_name("field")
+        .map(|f| parser_utils::get_text(f, source))
+        .unwrap_or_default()
+}
+
+/// `get(a).post(b)` -> [("GET", a), ("POST", b)]
+fn axum_method_router(expr: Node, source: &[u8]) -> Vec<(String, Option<String>)> {
+    if expr.kind() != "call_expression" {
+        return Vec::new();
+    }
+    let (Some(function), Some(args)) = (expr.child_by_field_name("function"), expr.child_by_field_name("arguments")) else {
+        return Vec::new();
+    };
+    let handler = args.named_child(0).map(|h| last_path_segment(&parser_utils::get_text(h, source)));
+
+    match function.kind() {
+        "identifier" | "scoped_identifier" => {
+            let name = last_path_segment(&parser_utils::get_text(function, source));
+            method_from_name(&name).map(|m| vec![(m, handler)]).unwrap_or_default()
+        }
+        "field_expression" => {
+            let mut methods = function
+                .child_by_field_name("value")
+                .map(|v| axum_method_router(v, source))
+                .unwrap_or_default();
+            if let Some(method) = method_from_name(&field_name(function, source)) {
+                methods.push((method, handler));
+            }
+            methods
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn method_from_name(name: &str) -> Option<String> {
+    if name == "any" {
+        Some("ANY".to_string())
+    } else if HTTP_METHODS.contains(&name) {
+        Some(name.to_uppercase())
+    } else {
+        None
+    }
+}
+
+/// A URL argument: a string literal, or the template of a `format!` call.
+fn rust_url_arg(arg: Node, source: &[u8]) -> Option<String> {
+    let value = match arg.kind() {
+        "reference_expression" => return arg.child_by_field_name("value").and_then(|v| rust_url_arg(v, source)),
+        "macro_invocation" => {
+            let name = arg.child_by_field_name("macro").map(|m| parser_utils::get_text(m, source))?;
+            if name != "format" {
+                return None;
+            }
+            let tokens = arg.named_child(1).or_else(|| arg.named_child(0))?;
+            let mut cursor = tokens.walk();
+            let literal = tokens.named_children(&mut cursor).find(|c| c.kind() == "string_literal")?;
+            string_value(literal, source)?
+        }
+        _ => string_value(arg, source)?,
+    };
+    looks_like_url(&value).then_some(value)
+}
+
+// --- JavaScript / TypeScript: Express/Koa routers, fetch, axios ---
+
+fn js_findings(node: Node, source: &[u8], file_id: &str, out: &mut Vec<Located>) {
+    if node.kind() != "call_expression" {
+        return;
+    }
+    let (Some(function), Some(args)) = (node.child_by_field_name("function"), node.child_by_field_name("arguments")) else {
+        return;
+    };
+    let first_arg = args.named_child(0);
+
+    match function.kind() {
+        "identifier" => {
+            let name = parser_utils::get_text(function, source);
+            if (name == "fetch" || name == "axios")
+                && let Some(url) = first_arg.and_then(|a| string_value(a, source))
+                && looks_like_url(&url)
+            {
+                let options = args.named_child(1).filter(|o| o.kind() == "object");
+                let method = options
+                    .and_then(|o| js_object_string(o, "method", source))
+                    .map(|m| m.to_uppercase())
+                    .unwrap_or_else(|| "GET".to_string());
+                out.push(located(Finding::Request { method, url }, node, file_id));
+            }
+        }
+        "member_expression" => {
+            let property = function
+                .child_by_field_name("property")
+                .map(|p| parser_utils::get_text(p, source))
+                .unwrap_or_default();
+            let object = function
+                .child_by_field_name("object")
+                .map(|o| parser_utils::get_text(o, source))
+                .unwrap_or_default();
+            let receiver = object.rsplit('.').next().unwrap_or(&object).to_lowercase();
+            let Some(method) = method_from_name(if property == "all" { "any" } else { &property }) else {
+                return;
+            };
+            let Some(path) = first_arg.and_then(|a| string_value(a, source)) else {
+                return;
+            };
+
+            if is_http_client(&receiver) && looks_like_url(&path) {
+                out.push(located(Finding::Request { method, url: path }, node, file_id));
+            } else if is_http_server(&receiver) && path.starts_with('/') && args.named_child_count() >= 2 {
+                let last = args.named_child(args.named_child_count() - 1);
+                let handler = last
+                    .filter(|h| matches!(h.kind(), "identifier" | "member_expression"))
+                    .map(|h| last_path_segment(&parser_utils::get_text(h, source)));
+                let endpoint = Finding::Endpoint { method, path, handler: handler.map(Handler::Name) };
+                out.push(located(endpoint, node, file_id));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Receivers treated as HTTP clients, e.g. `axios.get`, `apiClient.post`.
+fn is_http_client(receiver: &str) -> bool {
+    receiver == "axios" || receiver == "http" || receiver.ends_with("client") || receiver.ends_with("api")
+}
+
+/// Receivers treated as routers, e.g. `app.get`, `userRouter.post`, `fastify.get`.
+fn is_http_server(receiver: &str) -> bool {
+    receiver.ends_with("app") || receiver.ends_with("router") || receiver == "server" || receiver == "fastify"
+}
+
+fn js_object_string(object: Node, key: &str, source: &[u8]) -> Option<String> {
+    let mut cursor = object.walk();
+    object
+        .named_children(&mut cursor)
+        .filter(|c| c.kind() == "pair")
+        .find(|pair| {
+            pair.child_by_field_name("key")
+                .map(|k| parser_utils::get_text(k, source).trim_matches(['"', '\'']) == key)
+                .unwrap_or(false)
+        })
+        .and_then(|pair| pair.child_by_field_name("value"))
+        .and_then(|v| string_value(v, source))
+}
+
+// --- Python: Flask/FastAPI decorators, requests/httpx ---
+
+fn python_findings(node: Node, source: &[u8], file_id: &str, out: &mut Vec<Located>) {
+    match node.kind() {
+        "decorated_definition" => {
+            let Some(definition) = node.child_by_field_name("definition") else {
+                return;
+            };
+            let mut cursor = node.walk();
+            for decorator in node.named_children(&mut cursor).filter(|c| c.kind() == "decorator") {
+                let Some(call) = decorator.named_child(0).filter(|c| c.kind() == "call") else {
+                    continue;
+                };
+                let Some((_, attribute, args)) = python_method_call(call, source) else {
+                    continue;
+                };
+                let Some(path) = args.named_child(0).and_then(|a| string_value(a, source)) else {
+                    continue;
+                };
+                if !path.starts_with('/') {
+                    continue;
+                }
+
+                let methods = match attribute.as_str() {
+                    "route" | "api_route" => python_route_methods(args, source),
+                    other => method_from_name(other).into_iter().collect(),
+                };
+                for method in methods {
+                    let handler = Some(Handler::DefinedAt(definition.start_byte()));
+                    out.push(located(Finding::Endpoint { method, path: path.clone(), handler }, decorator, file_id));
+                }
+            }
+        }
+        "call" => {
+            if let Some((object, attribute, args)) = python_method_call(node, source)
+                && matches!(object.as_str(), "requests" | "httpx" | "session" | "client")
+                && HTTP_METHODS.contains(&attribute.as_str())
+                && let Some(url) = args.named_child(0).and_then(|a| string_value(a, source))
+                && looks_like_url(&url)
+            {
+                out.push(located(Finding::Request { method: attribute.to_uppercase(), url }, node, file_id));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `obj.attr(args)` -> (last segment of obj, attr, args)
+fn python_method_call<'a>(call: Node<'a>, source: &[u8]) -> Option<(String, String, Node<'a>)> {
+    let function = call.child_by_field_name("function").filter(|f| f.kind() == "attribute")?;
+    let object = parser_utils::get_text(function.child_by_field_name("object")?, source);
+    let attribute = parser_utils::get_text(function.child_by_field_name("attribute")?, source);
+    let args = call.child_by_field_name("arguments")?;
+    Some((object.rsplit('.').next().unwrap_or(&object).to_string(), attribute, args))
+}
+
+/// `methods=["GET", "POST"]` on a Flask `route`; GET when absent.
+fn python_route_methods(args: Node, source: &[u8]) -> Vec<String> {
+    let mut cursor = args.walk();
+    let methods: Vec<String> = args
+        .named_children(&mut cursor)
+        .filter(|c| c.kind() == "keyword_argument")
+        .filter(|kw| kw.child_by_field_name("name").map(|n| parser_utils::get_text(n, source)) == Some("methods".into()))
+        .filter_map(|kw| kw.child_by_field_name("value"))
+        .flat_map(|list| {
+            let mut list_cursor = list.walk();
+            list.named_children(&mut list_cursor)
+                .filter_map(|m| string_value(m, source))
+                .map(|m| m.to_uppercase())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    if methods.is_empty() { vec!["GET".to_string()] } else { methods }
+}
+
+// --- Java: Spring mappings, RestTemplate ---
+
+fn java_findings(node: Node, source: &[u8], file_id: &str, out: &mut Vec<Located>) {
+    match node.kind() {
+        "method_declaration" => {
+            let prefix = node
+                .parent()
+                .and_then(|body| body.parent())
+                .filter(|class| class.kind() == "class_declaration")
+                .and_then(|class| java_mappings(class, source).into_iter().next())
+                .map(|(_, path)| path)
+                .unwrap_or_default();
+
+            for (method, path) in java_mappings(node, source) {
+                let path = join_paths(&prefix, &path);
+                let handler = Some(Handler::DefinedAt(node.start_byte()));
+                out.push(located(Finding::Endpoint { method, path, handler }, node, file_id));
+            }
+        }
+        "method_invocation" => {
+            let name = node
+                .child_by_field_name("name")
+                .map(|n| parser_utils::get_text(n, source))
+                .unwrap_or_default();
+            let method = match name.as_str() {
+                "getForObject" | "getForEntity" => "GET",
+                "postForObject" | "postForEntity" | "postForLocation" => "POST",
+                "patchForObject" => "PATCH",
+                "put" => "PUT",
+                "delete" => "DELETE",
+                _ => return,
+            };
+            if let Some(url) = node
+                .child_by_field_name("arguments")
+                .and_then(|a| a.named_child(0))
+                .and_then(|a| string_value(a, source))
+                && looks_like_url(&url)
+            {
+                out.push(located(Finding::Request { method: method.to_string(), url }, node, file_id));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// (method, path) for each `@*Mapping` annotation on a class or method.
+fn java_mappings(decl: Node, source: &[u8]) -> Vec<(String, String)> {
+    let mut mappings = Vec::new();
+    let mut cursor = decl.walk();
+    let Some(modifiers) = decl.children(&mut cursor).find(|c| c.kind() == "modifiers") else {
+        return mappings;
+    };
+
+    let mut mod_cursor = modifiers.walk();
+    for annotation in modifiers.named_children(&mut mod_cursor) {
+        if !matches!(annotation.kind(), "annotation" | "marker_annotation") {
+            continue;
+        }
+        let name = annotation
+            .child_by_field_name("name")
+            .map(|n| last_path_segment(&parser_utils::get_text(n, source)))
+            .unwrap_or_default();
+        let Some(kind) = name.strip_suffix("Mapping") else {
+            continue;
+        };
+
+        let mut method = if kind == "Request" { "ANY".to_string() } else { kind.to_uppercase() };
+        let mut path = String::new();
+        if let Some(args) = annotation.child_by_field_name("arguments") {
+            let mut arg_cursor = args.walk();
+            for arg in args.named_children(&mut arg_cursor) {
+                if arg.kind() == "element_value_pair" {
+                    let key = arg.child_by_field_name("key").map(|k| parser_utils::get_text(k, source)).unwrap_or_default();
+                    let Some(value) = arg.child_by_field_name("value") else { continue };
+                    match key.as_str() {
+                        "value" | "path" => path = first_string(value, source).unwrap_or_default(),
+                        "method" => method = last_path_segment(&parser_utils::get_text(value, source)).trim_matches(['{', '}']).to_string(),
+                        _ => {}
+                    }
+                } else if let Some(value) = first_string(arg, source) {
+                    path = value;
+                }
+            }
+        }
+        mappings.push((method, path));
+    }
+    mappings
+}
+
+/// A string literal, or the first one in an array initializer.
+fn first_string(node: Node, source: &[u8]) -> Option<String> {
+    string_value(node, source).or_else(|| {
+        let mut cursor = node.walk();
+        node.named_children(&mut cursor).find_map(|c| string_value(c, source))
+    })
+}
+
+fn join_paths(prefix: &str, path: &str) -> String {
+    let joined = format!("{}/{}", prefix.trim_end_matches('/'), path.trim_start_matches('/'));
+    if joined.starts_with('/') { joined } else { format!("/{joined}") }
+}
+
+// --- Shared helpers ---
+
+fn looks_like_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://") || value.starts_with('/') || value.starts_with('{')
+}
+
+fn last_path_segment(text: &str) -> String {
+    text.rsplit(['.', ':']).next().unwrap_or(text).trim().to_string()
+}
+
+fn to_artifact(located: &Located, owner_id: &str) -> GameEntity {
+    let (artifact_type, method, target, key, handler) = match &located.finding {
+        Finding::Endpoint { method, path, handler } => {
+            let handler = match handler {
+                Some(Handler::Name(name)) => Some(("handler", name.clone())),
+                Some(Handler::DefinedAt(_)) => Some(("handler_id", owner_id.to_string())),
+                None => None,
+            };
+            ("http_endpoint", method, path, "path", handler)
+        }
+        Finding::Request { method, url } => ("http_request", method, url, "url", None),
+    };
+
+    let mut metadata = HashMap::from([
+        ("method".to_string(), method.clone()),
+        (key.to_string(), target.clone()),
+    ]);
+    if let Some((handler_key, value)) = handler {
+        metadata.insert(handler_key.to_string(), value);
+    }
+
+    let name = format!("{method} {target}");
+    GameEntity::Artifact {
+        id: format!("{owner_id}::{name}"),
+        name,
+        artifact_type: artifact_type.to_string(),
+        datatype: "http".to_string(),
+        is_mutable: false,
+        value_hint: Some(target.clone()),
+        span: Some(located.span.clone()),
+        metadata: Some(metadata),
+    }
+}
+
+// --- Linking requests to endpoints ---
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Param,
+    Rest,
+}
+
+/// Path segments of a route or URL. Scheme, host, query and fragment are dropped;
+/// `:id`, `{id}`, `<int:id>` and interpolations are parameters; `*rest` matches the remainder.
+fn segments(path: &str) -> Vec<Segment> {
+    let path = match path.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|i| &rest[i..]).unwrap_or("/"),
+        None => path,
+    };
+    let path = path.split(['?', '#']).next().unwrap_or(path);
+
+    path.split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            if s.starts_with('*') {
+                Segment::Rest
+            } else if s.starts_with(':') || s.contains('{') || s.contains('<') {
+                Segment::Param
+            } else {
+                Segment::Literal(s.to_string())
+            }
+        })
+        .collect()
+}
+
+/// Number of literal segments matched, or None when the request cannot hit the endpoint.
+fn match_score(endpoint: &[Segment], request: &[Segment]) -> Option<usize> {
+    let mut score = 0;
+    for (i, segment) in endpoint.iter().enumerate() {
+        match (segment, request.get(i)) {
+            (Segment::Rest, _) => return Some(score),
+            (_, None) => return None,
+            (Segment::Literal(a), Some(Segment::Literal(b))) if a == b => score += 1,
+            (Segment::Literal(_), Some(Segment::Literal(_))) => return None,
+            _ => {}
+        }
+    }
+    (endpoint.len() == request.len()).then_some(score)
+}
+
+struct Endpoint {
+    id: String,
+    method: String,
+    path: String,
+    segments: Vec<Segment>,
+    handler: Option<String>,
+}
+
+struct Request {
+    owner_id: String,
+    method: String,
+    url: String,
+    segments: Vec<Segment>,
+}
+
+/// Links each client request to the endpoint(s) whose method and path match best,
+/// across all cities. Routes point at the handler Room when it can be resolved,
+/// otherwise at the endpoint Artifact. A leading interpolated base URL
+/// (`${API}/users`) is ignored; router prefixes such as axum `nest` are not applied.
+pub fn network_routes(cities: &[GameEntity], symbol_table: &SymbolTable) -> Vec<Route> {
+    let mut endpoints = Vec::new();
+    let mut requests = Vec::new();
+    for city in cities {
+        collect_http(city, "", &mut endpoints, &mut requests);
+    }
+
+    let mut routes: Vec<Route> = Vec::new();
+    let mut linked = HashSet::new();
+    for request in &requests {
+        let mut request_segments = request.segments.as_slice();
+        while let Some(Segment::Param) = request_segments.first() {
+            request_segments = &request_segments[1..];
+        }
+
+        let scored: Vec<(usize, &Endpoint)> = endpoints
+            .iter()
+            .filter(|e| e.method == request.method || e.method == "ANY")
+            .filter_map(|e| {
+                match_score(&e.segments, &request.segments)
+                    .or_else(|| match_score(&e.segments, request_segments))
+                    .map(|score| (score, e))
+            })
+            .collect();
+        let Some(best) = scored.iter().map(|(score, _)| *score).max() else {
+            continue;
+        };
+
+        for (_, endpoint) in scored.iter().filter(|(score, _)| *score == best) {
+            let handler = endpoint.handler.as_ref().and_then(|h| symbol_table.resolve(h, &endpoint.id));
+            let (to_id, confidence) = match handler {
+                Some(resolution) => (resolution.id, resolution.confidence),
+                None => (endpoint.id.clone(), Confidence::High),
+            };
+            if !linked.insert((request.owner_id.clone(), to_id.clone())) {
+                continue;
+            }
+            routes.push(Route {
+                id: String::new(),
+                from_id: request.owner_id.clone(),
+                to_id,
+                route_type: RouteType::NetworkRequest,
+                bidirectional: false,
+                metadata: Some(serde_json::json!({
+                    "method": request.method,
+                    "path": endpoint.path,
+                    "url": request.url,
+                    "confidence": confidence,
+                    "strategy": "http_path",
+                })),
+            });
+        }
+    }
+    routes
+}
+
+fn collect_http(entity: &GameEntity, owner_id: &str, endpoints: &mut Vec<Endpoint>, requests: &mut Vec<Request>) {
+    match entity {
+        GameEntity::Artifact { id, artifact_type, metadata: Some(metadata), .. } => {
+            let method = metadata.get("method").cloned().unwrap_or_default();
+            if artifact_type == "http_endpoint"
+                && let Some(path) = metadata.get("path")
+            {
+                endpoints.push(Endpoint {
+                    id: id.clone(),
+                    method,
+                    path: path.clone(),
+                    segments: segments(path),
+                    handler: metadata.get("handler_id").or(metadata.get("handler")).cloned(),
+                });
+            } else if artifact_type == "http_request"
+                && let Some(url) = metadata.get("url")
+            {
+                requests.push(Request { owner_id: owner_id.to_string(), method, url: url.clone(), segments: segments(url) });
+            }
+        }
+        GameEntity::Artifact { .. } => {}
+        GameEntity::City { children, .. } | GameEntity::District { children, .. } => {
+            for child in children {
+                collect_http(child, owner_id, endpoints, requests);
+            }
+        }
+        GameEntity::Building { id, children, .. } | GameEntity::Room { id, children, .. } => {
+            for child in children {
+                collect_http(child, id, endpoints, requests);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::registry;
+    use crate::test_support::{self, city};
+
+    #[test]
+    fn test_placeholders_match_across_frameworks() {
+        let endpoint = segments("/users/:id/posts");
+        assert_eq!(match_score(&endpoint, &segments("https://api.example.com/users/{id}/posts?page=2")), Some(2));
+        assert_eq!(match_score(&segments("/users/{user_id}"), &segments("/users/42")), Some(1));
+        assert_eq!(match_score(&segments("/static/*path"), &segments("/static/css/app.css")), Some(1));
+        assert_eq!(match_score(&endpoint, &segments("/users/1/comments")), None);
+    }
+
+    #[test]
+    fn test_express_endpoint_and_fetch_call() {
+        let source = r#"
+app.get("/users/:id", getUser);
+async function load(id) {
+    return fetch(`/users/${id}`, { method: "GET" });
+}
+"#;
+        let mut entities = Vec::new();
+        let tree = registry::parse_tree("js", source).unwrap();
+        annotate_http("js", &tree, source, "server.js", &mut entities);
+
+        let names: Vec<_> = entities
+            .iter()
+            .filter_map(|e| match e {
+                GameEntity::Artifact { name, artifact_type, .. } => Some((artifact_type.as_str(), name.as_str())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, vec![("http_endpoint", "GET /users/:id"), ("http_request", "GET /users/{id}")]);
+    }
+
+    fn file(path: &str, source: &str) -> GameEntity {
+        test_support::annotated_file(path, source, annotate_http)
+    }
+
+    /// `(endpoint name, handler)`, where the handler is the named function or the Room the endpoint sits in.
+    fn endpoints(entity: &GameEntity, owner_id: &str, out: &mut Vec<(String, String)>) {
+        match entity {
+            GameEntity::Artifact { name, artifact_type, metadata, .. } if artifact_type == "http_endpoint" => {
+                let handler = metadata.as_ref().and_then(|m| m.get("handler")).cloned();
+                out.push((name.clone(), handler.unwrap_or_else(|| owner_id.to_string())));
+            }
+            GameEntity::City { id, children, .. }
+            | GameEntity::District { id, children, .. }
+            | GameEntity::Building { id, children, .. }
+            | GameEntity::Room { id, children, .. } => children.iter().for_each(|c| endpoints(c, id, out)),
+            GameEntity::Artifact { .. } => {}
+        }
+    }
+
+    #[test]
+    fn test_server_extractors() {
+        let files = [
+            file("api/src/main.rs", r#"
+fn app() -> Router {
+    Router::new().route("/users/:id", get(show_user).post(update_user))
+}
+async fn show_user() {}
+async fn update_user() {}
+"#),
+            file("py/app.py", r#"
+@app.route("/items/<int:item_id>", methods=["GET", "POST"])
+def item(item_id):
+    return {}
+
+@router.get("/orders/{order_id}")
+async def order(order_id: int):
+    return {}
+"#),
+            file("java/OrderController.java", r#"
+@RestController
+@RequestMapping("/api")
+class OrderController {
+    @GetMapping("/orders/{id}")
+    Order show(@PathVariable long id) { return null; }
+    @RequestMapping(value = "/orders", method = RequestMethod.POST)
+    Order create() { return null; }
+}
+"#),
+        ];
+        let mut found = Vec::new();
+        files.iter().for_each(|f| endpoints(f, "", &mut found));
+
+        let expected = [
+            ("GET /users/:id", "show_user"),
+            ("POST /users/:id", "update_user"),
+            ("GET /items/<int:item_id>", "py/app.py::item"),
+            ("POST /items/<int:item_id>", "py/app.py::item"),
+            ("GET /orders/{order_id}", "py/app.py::order"),
+            ("GET /api/orders/{id}", "java/OrderController.java::OrderController::show"),
+            ("POST /api/orders", "java/OrderController.java::OrderController::create"),
+        ];
+        let expected: Vec<(String, String)> = expected.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_links_client_requests_to_handlers_in_another_city() {
+        let cities = vec![
+            city("ts", vec![file("web/src/orders.ts", r#"
+async function load(id: number) {
+    await fetch(`${API}/api/orders/${id}`);
+    return fetch(`/api/orders/${id}`);
+}
+async function create(order: Order) {
+    return axios.post("/api/orders", order);
+}
+"#)]),
+            city("java", vec![file("server/OrderController.java", r#"
+@RequestMapping("/api")
+class OrderController {
+    @GetMapping("/orders/{id}")
+    Order show(@PathVariable long id) { return null; }
+    @PostMapping("/orders")
+    Order create() { return null; }
+}
+"#)]),
+        ];
+        let mut table = SymbolTable::new();
+        table.index_cities(&cities);
+
+        let links: Vec<(String, String)> = network_routes(&cities, &table).into_iter().map(|r| (r.from_id, r.to_id)).collect();
+        assert_eq!(links, [
+            ("web/src/orders.ts::load".to_string(), "server/OrderController.java::OrderController::show".to_string()),
+            ("web/src/orders.ts::create".to_string(), "server/OrderController.java::OrderController::create".to_string()),
+        ]);
+    }
+}
@@ -1,19 +1,34 @@
+use crate::clones;
+use crate::coupling;
+use crate::cycles;
+use crate::dead_code;
+use crate::ffi;
 use crate::git_layer::GitLayer;
 use crate::hierarchy;
-use crate::languages::registry;
-use crate::models::{CityStats, GameEntity, Route, RouteType, WorldMeta, WorldSeed};
+use crate::languages::{java_parser, registry};
+use crate::models::{CityStats, GameEntity, Route, RouteType, SourceSpan, WorldMeta, WorldSeed};
+use crate::metrics;
+use crate::network;
+use crate::resolvers::{ModuleResolver, rust};
+use crate::rules;
 use crate::symbol_table::SymbolTable;
+use crate::testing;
+use crate::type_refs;
+use crate::unresolved;
 use crate::walker;
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
 
 pub struct ParsedFile {
     pub language: String,
     pub entity: GameEntity,
     pub loc: u32,
+    pub fingerprints: Vec<clones::Fingerprint>,
+    /// `mod` items of a Rust file, for rebuilding crate module trees
+    pub mod_declarations: Vec<rust::ModDeclaration>,
 }
 
 #[instrument(skip(path, root_path))]
@@ -27,20 +42,32 @@ pub fn parse_single_file(path: &Path, relative_path: &str, root_path: &Path) ->
     let loc = source_code.lines().count() as u32;
     let file_id = relative_path.to_string();
 
-    let (children, imports, lang_tag) =
-        match registry::parse_by_extension(ext, &source_code, &file_id) {
-            Some((entities, imports)) => (entities, imports, normalize_lang_tag(ext)),
-            None => return None,
-        };
+    // Parsed once; the language parser and every analysis below share the tree
+    let tree = registry::parse_tree(ext, &source_code)?;
+    let (mut children, imports) = registry::parse_with_tree(ext, &tree, &source_code, &file_id)?;
+    let lang_tag = normalize_lang_tag(ext);
+
+    testing::annotate_tests(ext, &tree, &source_code, &file_id, &mut children);
+    network::annotate_http(ext, &tree, &source_code, &file_id, &mut children);
+    ffi::annotate_ffi(ext, &tree, &source_code, &file_id, &mut children);
+    metrics::annotate_rooms(&tree, &source_code, &mut children);
+    let fingerprints = clones::fingerprint_rooms(&tree, &children);
+
+    let mod_declarations = if ext == "rs" { rust::mod_declarations(&tree, &source_code) } else { Vec::new() };
 
+    let span = SourceSpan::whole_file(&file_id, &source_code);
+    let is_test = testing::is_test_path(&file_id);
     let file_entity = GameEntity::Building {
         id: file_id,
         name: path.file_name()?.to_str()?.to_string(),
         building_type: "file".to_string(),
         is_public: true,
+        is_test,
         loc,
         imports,
+        supertypes: vec![],
         children,
+        span: Some(span),
         metadata: None,
     };
 
@@ -54,7 +81,15 @@ pub fn parse_single_file(path: &Path, relative_path: &str, root_path: &Path) ->
         *m = Some(metadata);
     }
 
-    Some(ParsedFile { language: lang_tag.to_string(), entity: file_entity, loc })
+    // Java Districts and imports follow packages, not directories
+    if ext == "java"
+        && let Some(package) = java_parser::package_name(&tree, &source_code)
+        && let GameEntity::Building { metadata, .. } = &mut file_entity
+    {
+        metadata.get_or_insert_with(HashMap::new).insert("package".to_string(), package);
+    }
+
+    Some(ParsedFile { language: lang_tag.to_string(), entity: file_entity, loc, fingerprints, mod_declarations })
 }
 
 pub fn generate_world(root_path: &Path) -> WorldSeed {
@@ -77,16 +112,31 @@ pub fn generate_world(root_path: &Path) -> WorldSeed {
 
     let mut cities = Vec::new();
     let mut all_routes = Vec::new();
+    let mut supertype_refs = Vec::new();
+    let mut clone_routes = Vec::new();
     let mut route_counter = 0;
     let mut lang_loc: HashMap<String, u32> = HashMap::new();
+    let mut rust_modules = HashMap::new();
 
-    for (lang, files) in city_map {
+    for (lang, mut files) in city_map {
         debug!("Building City for language: {}", lang);
 
         let total_loc: u32 = files.iter().map(|f| f.loc).sum();
         *lang_loc.entry(lang.clone()).or_default() += total_loc;
 
-        let city_children = hierarchy::reconstruct_hierarchy(files);
+        for file in &mut files {
+            if let GameEntity::Building { id, .. } = &file.entity
+                && !file.mod_declarations.is_empty()
+            {
+                rust_modules.insert(id.clone(), std::mem::take(&mut file.mod_declarations));
+            }
+        }
+        let fingerprints: Vec<_> = files.iter_mut().flat_map(|f| std::mem::take(&mut f.fingerprints)).collect();
+        let clone_classes = clones::find_clones(&format!("city_{lang}"), &fingerprints);
+        clone_routes.extend(clones::clone_routes(&clone_classes));
+
+        let mut city_children = hierarchy::reconstruct_hierarchy(files);
+        clones::annotate(&mut city_children, &clone_classes);
 
         let (buildings, rooms, artifacts, loc) =
             city_children.iter().fold((0, 0, 0, 0), |acc, child| {
@@ -104,6 +154,7 @@ pub fn generate_world(root_path: &Path) -> WorldSeed {
             entry_point_id,
             stats: CityStats { building_count: buildings, room_count: rooms, artifact_count: artifacts, loc },
             children: city_children,
+            metadata: None,
         };
 
         let call_routes = city.collect_calls();
@@ -118,6 +169,8 @@ pub fn generate_world(root_path: &Path) -> WorldSeed {
             route_counter += 1;
         }
 
+        supertype_refs.extend(city.collect_supertypes());
+
         cities.push(city);
     }
 
@@ -126,13 +179,108 @@ pub fn generate_world(root_path: &Path) -> WorldSeed {
     symbol_table.index_cities(&cities);
 
     debug!("Resolving routes...");
+    let module_resolver = ModuleResolver::new(root_path, &symbol_table, &rust_modules);
+
+    // Imports first, so call resolution knows what each file actually imports
+    let import_resolutions: Vec<_> = all_routes
+        .iter()
+        .map(|route| match route.route_type {
+            RouteType::Import => module_resolver.resolve_import(&route.to_id, &route.from_id, &symbol_table),
+            _ => None,
+        })
+        .collect();
+    for (route, resolution) in all_routes.iter().zip(&import_resolutions) {
+        if let Some(resolution) = resolution {
+            symbol_table.add_resolved_import(&route.from_id, &resolution.id);
+        }
+    }
+
+    // Calls to a C/C++ prototype land on its definition
+    let definition_links = module_resolver.link_declarations(&symbol_table);
+    for (declaration, definition) in &definition_links {
+        symbol_table.add_definition(declaration, &definition.id);
+    }
+
     let mut resolved_routes = Vec::new();
-    for route in all_routes {
-        if let Some(resolved_to) = symbol_table.resolve(&route.to_id, &route.from_id) {
-            resolved_routes.push(Route { to_id: resolved_to, ..route });
+    let mut unresolved_refs = Vec::new();
+    for (route, import_resolution) in all_routes.into_iter().zip(import_resolutions) {
+        let resolution = match route.route_type {
+            RouteType::Import => import_resolution,
+            _ => symbol_table.resolve(&route.to_id, &route.from_id),
+        };
+        match resolution {
+            // `use super::*` in an inline test module
+            Some(resolution) if resolution.id == route.from_id => {}
+            Some(resolution) => resolved_routes.push(Route { to_id: resolution.id.clone(), metadata: Some(resolution.metadata()), ..route }),
+            None => unresolved_refs.push(unresolved::classify_route(&route, &symbol_table)),
         }
     }
 
+    for (declaration, definition) in definition_links {
+        resolved_routes.push(Route {
+            id: format!("route_{route_counter}"),
+            from_id: declaration,
+            to_id: definition.id.clone(),
+            route_type: RouteType::Definition,
+            bidirectional: false,
+            metadata: Some(definition.metadata()),
+        });
+        route_counter += 1;
+    }
+
+    for (from, supertype) in supertype_refs {
+        if let Some(resolution) = symbol_table.resolve_type(&supertype, &from)
+            && resolution.id != from
+        {
+            resolved_routes.push(Route {
+                id: format!("route_{route_counter}"),
+                from_id: from,
+                to_id: resolution.id.clone(),
+                route_type: RouteType::Inheritance,
+                bidirectional: false,
+                metadata: Some(resolution.metadata()),
+            });
+            route_counter += 1;
+        }
+    }
+
+    for route in network::network_routes(&cities, &symbol_table) {
+        resolved_routes.push(Route { id: format!("route_{route_counter}"), ..route });
+        route_counter += 1;
+    }
+
+    for route in ffi::ffi_routes(&cities, &symbol_table) {
+        resolved_routes.push(Route { id: format!("route_{route_counter}"), ..route });
+        route_counter += 1;
+    }
+
+    for route in type_refs::type_reference_routes(&cities, &symbol_table) {
+        resolved_routes.push(Route { id: format!("route_{route_counter}"), ..route });
+        route_counter += 1;
+    }
+
+    let guarded = testing::link_tests(&mut cities, &mut resolved_routes);
+    debug!("Linked tests to {guarded} entities they call");
+
+    let rules_error = match rules::load(root_path) {
+        Ok(Some(rule_set)) => {
+            rules::apply(&rule_set, &cities, &mut resolved_routes);
+            None
+        }
+        Ok(None) => None,
+        Err(e) => {
+            warn!("Ignoring architecture rules: {e}");
+            Some(e)
+        }
+    };
+    dead_code::annotate_dead_code(&mut cities, &resolved_routes, &symbol_table);
+
+    // Added after dead code detection: a copy does not keep the original alive
+    for route in clone_routes {
+        resolved_routes.push(Route { id: format!("route_{route_counter}"), ..route });
+        route_counter += 1;
+    }
+
     let (total_buildings, total_rooms, total_artifacts, _) =
         cities.iter().fold((0, 0, 0, 0), |acc, city| {
             let (b, r, a, l) = city.count_entities();
@@ -145,17 +293,26 @@ pub fn generate_world(root_path: &Path) -> WorldSeed {
         .map(|(lang, _)| lang)
         .unwrap_or_default();
 
-    let complexity_score =
-        hierarchy::calculate_complexity_score(total_buildings, total_rooms, &resolved_routes);
+    let room_metrics = metrics::roll_up(&mut cities);
+    let complexity_score = metrics::complexity_score(&room_metrics);
+    let cycles = cycles::find_cycles(&cities, &resolved_routes);
+    let coupling = coupling::annotate_coupling(&mut cities, &resolved_routes);
 
     WorldSeed {
         world_meta: WorldMeta {
             total_cities: cities.len() as u32,
             total_buildings, total_rooms, total_artifacts,
             dominant_language, complexity_score,
+            complexity_score_version: metrics::SCORE_VERSION,
+            cycle_count: cycles.len() as u32,
+            modularity: coupling.modularity,
+            main_sequence_distance: coupling.main_sequence_distance,
+            rules_error,
         },
         cities,
         highways: resolved_routes,
+        unresolved: unresolved_refs,
+        cycles,
     }
 }
 
@@ -195,3 +352,32 @@ fn normalize_lang_tag(ext: &str) -> String {
         other => other.into(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supertypes_become_inheritance_routes() {
+        let dir = tempfile::tempdir().unwrap();
+        let shapes = dir.path().join("src/shapes");
+        std::fs::create_dir_all(&shapes).unwrap();
+        std::fs::write(shapes.join("Shape.java"), "package shapes;\npublic abstract class Shape {}\n").unwrap();
+        std::fs::write(shapes.join("Drawable.java"), "package shapes;\npublic interface Drawable {}\n").unwrap();
+        std::fs::write(shapes.join("Circle.java"), "package shapes;\npublic class Circle extends Shape implements Drawable {}\n").unwrap();
+
+        let seed = generate_world(dir.path());
+
+        let mut inherits: Vec<(&str, &str)> = seed
+            .highways
+            .iter()
+            .filter(|r| matches!(r.route_type, RouteType::Inheritance))
+            .map(|r| (r.from_id.as_str(), r.to_id.as_str()))
+            .collect();
+        inherits.sort();
+        assert_eq!(inherits, [
+            ("src/shapes/Circle.java::Circle", "src/shapes/Drawable.java::Drawable"),
+            ("src/shapes/Circle.java::Circle", "src/shapes/Shape.java::Shape"),
+        ]);
+    }
+}
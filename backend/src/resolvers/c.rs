@@ -0,0 +1,318 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::debug;
+
+use crate::languages::paths::{dir_of, file_of, is_c_family, normalize_relative};
+use crate::symbol_table::{Confidence, Resolution, SymbolTable};
+
+
+/// Compiler flags that add a header search directory.
+const INCLUDE_FLAGS: &[&str] = &["-I", "-isystem", "-iquote", "-idirafter"];
+
+/// Resolves C/C++ `#include`s against the including file's directory and the
+/// include directories the build declares (`compile_commands.json`, CMake
+/// `include_directories` / `target_include_directories`, Makefile `-I` flags),
+/// and links prototypes in headers to the Rooms that define them.
+pub struct CIncludeResolver {
+    files: HashSet<String>,
+    include_dirs: Vec<String>,
+}
+
+impl CIncludeResolver {
+    pub fn new(repo_root: &Path, symbol_table: &SymbolTable) -> Self {
+        let files: HashSet<String> = symbol_table.files().filter(|f| is_c_family(f)).cloned().collect();
+
+        let mut include_dirs = Vec::new();
+        if !files.is_empty() {
+            include_dirs.extend(compile_commands_dirs(repo_root));
+
+            let mut dirs = HashSet::new();
+            for file in &files {
+                let mut dir = dir_of(file);
+                while dirs.insert(dir.to_string()) && !dir.is_empty() {
+                    dir = dir_of(dir);
+                }
+            }
+            let mut dirs: Vec<String> = dirs.into_iter().collect();
+            dirs.sort_unstable();
+            for dir in &dirs {
+                if let Ok(cmake) = fs::read_to_string(repo_root.join(dir).join("CMakeLists.txt")) {
+                    include_dirs.extend(cmake_include_dirs(&cmake, dir));
+                }
+                for makefile in ["Makefile", "makefile", "GNUmakefile"] {
+                    if let Ok(make) = fs::read_to_string(repo_root.join(dir).join(makefile)) {
+                        include_dirs.extend(makefile_include_dirs(&make, dir));
+                    }
+                }
+            }
+        }
+        let mut seen = HashSet::new();
+        include_dirs.retain(|dir| seen.insert(dir.clone()));
+
+        debug!(include_dirs = ?include_dirs, "Found C/C++ include directories");
+        Self { files, include_dirs }
+    }
+
+    /// Resolve an include recorded by the C/C++ parsers (`util.h`, `<lib/api.h>`)
+    /// made from `file_id`. Quoted includes search the file's directory first,
+    /// system includes only the include directories.
+    pub fn resolve(&self, import: &str, file_id: &str) -> Option<Resolution> {
+        if let Some(header) = import.strip_prefix('<').and_then(|h| h.strip_suffix('>')) {
+            return self.in_include_dirs(header);
+        }
+
+        let relative = normalize_relative(dir_of(file_id), import);
+        if self.files.contains(&relative) {
+            return Some(Resolution::new(&relative, Confidence::High, "relative"));
+        }
+        self.in_include_dirs(import).or_else(|| {
+            // No build configuration mentions the directory: a unique file with that path suffix
+            let suffix = format!("/{}", import.trim_start_matches("./"));
+            let mut matches = self.files.iter().filter(|f| f.ends_with(&suffix) || **f == suffix[1..]);
+            match (matches.next(), matches.next()) {
+                (Some(only), None) => Some(Resolution::new(only, Confidence::Medium, "path_suffix")),
+                _ => None,
+            }
+        })
+    }
+
+    fn in_include_dirs(&self, header: &str) -> Option<Resolution> {
+        self.include_dirs
+            .iter()
+            .map(|dir| normalize_relative(dir, header))
+            .find(|candidate| self.files.contains(candidate))
+            .map(|file| Resolution::new(&file, Confidence::High, "include_dir"))
+    }
+
+    /// Pairs each prototype with the Room defining it: the same qualified name
+    /// (`parse`, `Parser::parse`, `ns::parse`) in another file, preferring the
+    /// source file named after the header, then files that include it.
+    pub fn link_declarations(&self, symbol_table: &SymbolTable) -> Vec<(String, Resolution)> {
+        let mut includers: HashMap<String, HashSet<&str>> = HashMap::new();
+        for file in &self.files {
+            for import in symbol_table.imports_of(file) {
+                if let Some(header) = self.resolve(import, file) {
+                    includers.entry(header.id).or_default().insert(file.as_str());
+                }
+            }
+        }
+
+        let mut declarations: Vec<&String> = symbol_table.declarations().filter(|id| is_c_family(file_of(id))).collect();
+        declarations.sort_unstable();
+
+        let mut links = Vec::new();
+        for declaration in declarations {
+            let Some((header, qualified)) = declaration.split_once("::") else { continue };
+            // Definitions are named as written: `parse`, `Parser::parse` or `ns::Parser::parse`
+            let names = std::iter::once(qualified).chain(qualified.match_indices("::").map(|(i, _)| &qualified[i + 2..]));
+
+            let mut candidates: Vec<&String> = names
+                .flat_map(|name| symbol_table.ids_named(name))
+                .filter(|id| {
+                    let (file, rest) = id.split_once("::").unwrap_or((id, ""));
+                    file != header && rest == qualified && is_c_family(file) && !symbol_table.is_declaration(id)
+                })
+                .collect();
+            candidates.sort_unstable();
+            candidates.dedup();
+
+            let stem = file_stem(header);
+            let same_stem: Vec<&String> = candidates.iter().copied().filter(|id| file_stem(file_of(id)) == stem).collect();
+            let including: Vec<&String> = candidates
+                .iter()
+                .copied()
+                .filter(|id| includers.get(header).is_some_and(|files| files.contains(file_of(id))))
+                .collect();
+
+            let resolution = match (same_stem.as_slice(), including.as_slice(), candidates.as_slice()) {
+                ([only], _, _) => Resolution::new(only, Confidence::High, "same_stem"),
+                (many, _, _) if many.len() > 1 => {
+                    let Some(nearest) = many.iter().find(|id| dir_of(file_of(id)) == dir_of(header)) else { continue };
+                    Resolution::new(nearest, Confidence::Medium, "same_stem")
+                }
+                (_, [only], _) => Resolution::new(only, Confidence::High, "includes_header"),
+                (_, _, [only]) => Resolution::new(only, Confidence::Medium, "unique_definition"),
+                _ => continue,
+            };
+            links.push((declaration.clone(), resolution));
+        }
+        links
+    }
+}
+
+fn file_stem(file: &str) -> &str {
+    let name = file.rsplit('/').next().unwrap_or(file);
+    name.split('.').next().unwrap_or(name)
+}
+
+/// Include directories named by the `-I`-style flags of every command in a
+/// `compile_commands.json` at the repository root or one level below (`build/`).
+fn compile_commands_dirs(repo_root: &Path) -> Vec<String> {
+    let mut databases = vec![repo_root.join("compile_commands.json")];
+    if let Ok(entries) = fs::read_dir(repo_root) {
+        let mut subdirs: Vec<PathBuf> = entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect();
+        subdirs.sort();
+        databases.extend(subdirs.into_iter().map(|dir| dir.join("compile_commands.json")));
+    }
+    let canonical_root = repo_root.canonicalize().unwrap_or_else(|_| repo_root.to_path_buf());
+
+    let mut dirs = Vec::new();
+    for database in databases {
+        let Ok(text) = fs::read_to_string(&database) else { continue };
+        let Ok(serde_json::Value::Array(commands)) = serde_json::from_str::<serde_json::Value>(&text) else { continue };
+        for command in &commands {
+            let directory = PathBuf::from(command.get("directory").and_then(|d| d.as_str()).unwrap_or_default());
+            let arguments: Vec<String> = match (command.get("arguments").and_then(|a| a.as_array()), command.get("command").and_then(|c| c.as_str())) {
+                (Some(arguments), _) => arguments.iter().filter_map(|a| a.as_str()).map(str::to_string).collect(),
+                (None, Some(command)) => command.split_whitespace().map(|a| a.trim_matches('"').to_string()).collect(),
+                (None, None) => continue,
+            };
+            for flag in include_flags(&arguments) {
+                let path = directory.join(flag);
+                let relative = path
+                    .strip_prefix(&canonical_root)
+                    .or_else(|_| path.strip_prefix(repo_root))
+                    .ok()
+                    .map(|p| normalize_relative("", &p.to_string_lossy()));
+                dirs.extend(relative);
+            }
+        }
+    }
+    dirs
+}
+
+/// The values of `-I<dir>` / `-I <dir>` (and `-isystem`, `-iquote`, `-idirafter`) flags.
+fn include_flags<S: AsRef<str>>(arguments: &[S]) -> Vec<String> {
+    let mut dirs = Vec::new();
+    let mut arguments = arguments.iter().map(AsRef::as_ref);
+    while let Some(argument) = arguments.next() {
+        for flag in INCLUDE_FLAGS {
+            if argument == *flag {
+                dirs.extend(arguments.next().map(str::to_string));
+                break;
+            }
+            if let Some(dir) = argument.strip_prefix(flag)
+                && !dir.is_empty()
+            {
+                dirs.push(dir.to_string());
+                break;
+            }
+        }
+    }
+    dirs
+}
+
+/// Directories passed to `include_directories(...)` and
+/// `target_include_directories(target ...)` in the CMakeLists.txt of `dir`.
+fn cmake_include_dirs(cmake: &str, dir: &str) -> Vec<String> {
+    let lower = cmake.to_lowercase();
+    let mut dirs = Vec::new();
+    for (command, skip_target) in [("target_include_directories(", true), ("include_directories(", false)] {
+        let mut from = 0;
+        while let Some(found) = lower[from..].find(command) {
+            let start = from + found;
+            from = start + command.len();
+            // `include_directories(` also matches inside `target_include_directories(`
+            if !skip_target && lower[..start].ends_with("target_") {
+                continue;
+            }
+            let Some(len) = cmake[from..].find(')') else { break };
+            let arguments = cmake[from..from + len].split_whitespace().skip(usize::from(skip_target));
+            for argument in arguments {
+                if let Some(path) = cmake_path(argument, dir) {
+                    dirs.push(path);
+                }
+            }
+        }
+    }
+    dirs
+}
+
+fn cmake_path(argument: &str, dir: &str) -> Option<String> {
+    const KEYWORDS: &[&str] = &["PUBLIC", "PRIVATE", "INTERFACE", "SYSTEM", "BEFORE", "AFTER"];
+    let argument = argument.trim_matches('"');
+    if KEYWORDS.contains(&argument) || argument.starts_with("$<INSTALL_INTERFACE") {
+        return None;
+    }
+    let argument = argument
+        .strip_prefix("$<BUILD_INTERFACE:")
+        .map(|a| a.trim_end_matches('>'))
+        .unwrap_or(argument);
+    let (base, path) = if let Some(path) = argument
+        .strip_prefix("${CMAKE_CURRENT_SOURCE_DIR}")
+        .or_else(|| argument.strip_prefix("${CMAKE_CURRENT_LIST_DIR}"))
+    {
+        (dir, path.trim_start_matches('/'))
+    } else if let Some(path) = argument
+        .strip_prefix("${PROJECT_SOURCE_DIR}")
+        .or_else(|| argument.strip_prefix("${CMAKE_SOURCE_DIR}"))
+    {
+        ("", path.trim_start_matches('/'))
+    } else {
+        (dir, argument)
+    };
+    (!path.contains('$') && !path.starts_with('/')).then(|| normalize_relative(base, path))
+}
+
+/// Directories passed with `-I` anywhere in the Makefile of `dir`.
+fn makefile_include_dirs(makefile: &str, dir: &str) -> Vec<String> {
+    let arguments: Vec<&str> = makefile.split_whitespace().collect();
+    include_flags(&arguments)
+        .into_iter()
+        .map(|path| path.trim_start_matches("$(CURDIR)/").trim_start_matches("$(srcdir)/").to_string())
+        .filter(|path| !path.contains('$') && !path.starts_with('/'))
+        .map(|path| normalize_relative(dir, &path))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GameEntity;
+    use crate::test_support;
+
+    #[test]
+    fn test_resolves_includes_and_links_declarations() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("CMakeLists.txt", "project(demo)\ntarget_include_directories(demo PUBLIC ${CMAKE_CURRENT_SOURCE_DIR}/include)\n"),
+            ("include/geo/shape.h", "#include \"point.h\"\nint area(int w, int h);\n"),
+            ("include/geo/point.h", "struct point { int x; int y; };\n"),
+            ("src/shape.c", "#include <geo/shape.h>\nint area(int w, int h) { return w * h; }\n"),
+            ("src/main.c", "#include <stdio.h>\n#include <geo/shape.h>\n#include \"missing.h\"\nint main(void) { return area(2, 3); }\n"),
+        ];
+        for (path, contents) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let buildings: Vec<GameEntity> = files
+            .iter()
+            .filter(|(path, _)| is_c_family(path))
+            .map(|(path, source)| test_support::parsed_file(path, source))
+            .collect();
+        let mut table = SymbolTable::new();
+        table.index_cities(&buildings);
+        let resolver = CIncludeResolver::new(dir.path(), &table);
+
+        let resolve = |import: &str, from: &str| resolver.resolve(import, from).map(|r| (r.id, r.strategy));
+        assert_eq!(resolve("point.h", "include/geo/shape.h"), Some(("include/geo/point.h".to_string(), "relative")));
+        assert_eq!(resolve("<geo/shape.h>", "src/main.c"), Some(("include/geo/shape.h".to_string(), "include_dir")));
+        assert_eq!(resolve("<stdio.h>", "src/main.c"), None);
+        assert_eq!(resolve("missing.h", "src/main.c"), None);
+
+        let links = resolver.link_declarations(&table);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].0, "include/geo/shape.h::area");
+        assert_eq!((links[0].1.id.as_str(), links[0].1.strategy), ("src/shape.c::area", "same_stem"));
+
+        for (declaration, definition) in &links {
+            table.add_definition(declaration, &definition.id);
+        }
+        table.add_resolved_import("src/main.c", "include/geo/shape.h");
+        assert_eq!(table.resolve("area", "src/main.c::main").map(|r| r.id), Some("src/shape.c::area".to_string()));
+    }
+}
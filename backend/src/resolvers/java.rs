@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use tracing::debug;
+
+use crate::languages::paths::dir_of;
+use crate::hierarchy;
+use crate::symbol_table::{Confidence, Resolution, SymbolTable};
+
+/// Resolves Java imports (`a.b.Type`, `a.b.*`, static `a.b.Type::member`)
+/// through the packages files declare rather than their directories, so
+/// Maven-style `src/main/java` roots need no configuration.
+pub struct JavaPackageResolver {
+    /// Fully-qualified type name -> type Building id, nested types included
+    /// (`com.example.Outer.Inner`)
+    types: HashMap<String, String>,
+    /// Package -> path of the District holding it
+    packages: HashMap<String, String>,
+}
+
+impl JavaPackageResolver {
+    pub fn new(symbol_table: &SymbolTable) -> Self {
+        let mut packages = HashMap::new();
+        let mut files: Vec<&String> = symbol_table.files().filter(|f| f.ends_with(".java")).collect();
+        files.sort_unstable();
+        for file in files {
+            if let Some(package) = symbol_table.package_of(file) {
+                packages
+                    .entry(package.to_string())
+                    .or_insert_with(|| hierarchy::package_path(dir_of(file), package));
+            }
+        }
+
+        let types = symbol_table
+            .type_ids()
+            .filter_map(|id| {
+                let (file, path) = id.split_once("::")?;
+                let package = symbol_table.package_of(file)?;
+                Some((format!("{package}.{}", path.replace("::", ".")), id.clone()))
+            })
+            .collect();
+
+        debug!(packages = packages.len(), "Indexed Java packages");
+        Self { types, packages }
+    }
+
+    /// Resolve an import recorded by `java_parser`.
+    pub fn resolve(&self, import: &str, symbol_table: &SymbolTable) -> Option<Resolution> {
+        if let Some((owner, member)) = import.split_once("::") {
+            let owner = self.types.get(owner)?;
+            let member_id = format!("{owner}::{member}");
+            return Some(if member != "*" && symbol_table.contains(&member_id) {
+                Resolution::new(&member_id, Confidence::High, "static_import")
+            } else {
+                Resolution::new(owner, Confidence::High, "static_import")
+            });
+        }
+        if let Some(package) = import.strip_suffix(".*") {
+            // On-demand imports name a package, or a type's nested types
+            if let Some(path) = self.packages.get(package) {
+                return Some(Resolution::new(&hierarchy::district_id(path), Confidence::High, "package_wildcard"));
+            }
+            return self.types.get(package).map(|id| Resolution::new(id, Confidence::High, "fully_qualified"));
+        }
+        self.types.get(import).map(|id| Resolution::new(id, Confidence::High, "fully_qualified"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::{java_parser, registry};
+    use crate::models::GameEntity;
+    use crate::test_support;
+
+    #[test]
+    fn test_resolves_imports_through_packages() {
+        let files = [
+            ("core/src/main/java/com/acme/model/User.java", "package com.acme.model;\npublic class User { public static User of(String n) { return null; } public static class Id {} }\n"),
+            ("core/src/main/java/com/acme/model/Order.java", "package com.acme.model;\npublic class Order { User owner() { return User.of(\"x\"); } }\n"),
+            ("app/src/main/java/com/acme/App.java", "package com.acme;\nimport com.acme.model.User;\nimport com.acme.model.*;\nimport static com.acme.model.User.of;\nimport java.util.List;\npublic class App { void run() { of(\"a\"); Helper.help(); } }\n"),
+            ("app/src/main/java/com/acme/Helper.java", "package com.acme;\nclass Helper { static void help() {} }\n"),
+            ("core/src/main/java/com/acme/model/Helper.java", "package com.acme.model;\nclass Helper { static void help() {} }\n"),
+        ];
+        let buildings: Vec<GameEntity> = files
+            .iter()
+            .map(|(path, source)| {
+                let mut building = test_support::parsed_file(path, source);
+                let package = java_parser::package_name(&registry::parse_tree("java", source).unwrap(), source).unwrap();
+                if let GameEntity::Building { metadata, .. } = &mut building {
+                    *metadata = Some(HashMap::from([("package".to_string(), package)]));
+                }
+                building
+            })
+            .collect();
+        let mut table = SymbolTable::new();
+        table.index_cities(&buildings);
+        let resolver = JavaPackageResolver::new(&table);
+
+        let app = "app/src/main/java/com/acme/App.java";
+        let user = "core/src/main/java/com/acme/model/User.java::User";
+        let resolve = |import: &str| resolver.resolve(import, &table).map(|r| (r.id, r.strategy));
+        assert_eq!(table.imports_of(app), ["com.acme.model.User", "com.acme.model.*", "com.acme.model.User::of", "java.util.List"]);
+        assert_eq!(resolve("com.acme.model.User"), Some((user.to_string(), "fully_qualified")));
+        assert_eq!(resolve("com.acme.model.User.Id"), Some((format!("{user}::Id"), "fully_qualified")));
+        assert_eq!(resolve("com.acme.model.*"), Some(("district_core_src_main_java_com_acme_model".to_string(), "package_wildcard")));
+        assert_eq!(resolve("com.acme.model.User::of"), Some((format!("{user}::of"), "static_import")));
+        assert_eq!(resolve("java.util.List"), None);
+
+        // Same-package references need no import
+        let helper = table.resolve("Helper.help", &format!("{app}::App::run")).unwrap();
+        assert_eq!((helper.id.as_str(), helper.strategy), ("app/src/main/java/com/acme/Helper.java::Helper::help", "qualified_type"));
+        assert_eq!(table.resolve_type("Helper", app).map(|r| r.strategy), Some("same_package"));
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+use tracing::debug;
+
+use crate::languages::paths::{dir_of, normalize_relative};
+use crate::symbol_table::{Confidence, Resolution, SymbolTable};
+
+/// Extensions tried, in order, for a specifier written without one.
+const EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+
+/// package.json fields that may point at a package's entry file.
+const ENTRY_FIELDS: &[&str] = &["source", "module", "main", "types"];
+
+/// How many `export ... from` hops to follow for a named import.
+const MAX_REEXPORT_DEPTH: usize = 4;
+
+/// `compilerOptions` of a tsconfig.json / jsconfig.json, with paths made repo-relative.
+#[derive(Default, Clone)]
+struct TsConfig {
+    dir: String,
+    base_url: Option<String>,
+    /// (pattern, targets) with at most one `*` in each, longest prefix first
+    paths: Vec<(String, Vec<String>)>,
+}
+
+/// A package that is part of a workspace, importable by its name.
+struct WorkspacePackage {
+    name: String,
+    dir: String,
+    entry: Option<String>,
+}
+
+/// Resolves JS/TS module specifiers the way Node and the TypeScript compiler
+/// do: relative paths with extension and index candidates, tsconfig
+/// `baseUrl`/`paths`, and packages of npm/yarn/pnpm workspaces.
+pub struct JsModuleResolver {
+    files: HashSet<String>,
+    /// Configs by directory, applied to files below them (nearest wins)
+    configs: Vec<TsConfig>,
+    packages: Vec<WorkspacePackage>,
+}
+
+impl JsModuleResolver {
+    pub fn new(repo_root: &Path, symbol_table: &SymbolTable) -> Self {
+        let files: HashSet<String> = symbol_table
+            .files()
+            .filter(|f| EXTENSIONS.iter().any(|ext| f.ends_with(&format!(".{ext}"))))
+            .cloned()
+            .collect();
+
+        let mut dirs: Vec<&str> = Vec::new();
+        for file in &files {
+            let mut dir = dir_of(file);
+            loop {
+                if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+                if dir.is_empty() {
+                    break;
+                }
+                dir = dir_of(dir);
+            }
+        }
+        // Deepest first, so the nearest config is found first
+        dirs.sort_unstable_by_key(|d| std::cmp::Reverse(d.len()));
+
+        let configs: Vec<TsConfig> = dirs
+            .iter()
+            .filter_map(|dir| {
+                ["tsconfig.json", "jsconfig.json"]
+                    .iter()
+                    .find_map(|name| read_tsconfig(repo_root, &normalize_relative(dir, name), 0))
+            })
+            .collect();
+
+        let manifests: Vec<(String, Value)> = dirs
+            .iter()
+            .filter_map(|dir| Some((dir.to_string(), read_json(repo_root, &normalize_relative(dir, "package.json"))?)))
+            .collect();
+        let packages = workspace_packages(&manifests, &files);
+
+        debug!(configs = configs.len(), packages = packages.len(), "Loaded JS/TS resolution config");
+        Self { files, configs, packages }
+    }
+
+    /// Resolve a specifier recorded by the JS/TS parsers (`./m`, `@/lib/m`,
+    /// `pkg/sub`, or `spec::name` for a named import) made from `file_id`.
+    pub fn resolve(&self, import: &str, file_id: &str, symbol_table: &SymbolTable) -> Option<Resolution> {
+        self.resolve_import(import, file_id, symbol_table, 0)
+    }
+
+    fn resolve_import(&self, import: &str, file_id: &str, symbol_table: &SymbolTable, depth: usize) -> Option<Resolution> {
+        let (specifier, name) = match import.split_once("::") {
+            Some((specifier, name)) => (specifier, Some(name)),
+            None => (import, None),
+        };
+        let (file, strategy) = self.resolve_specifier(specifier, file_id)?;
+
+        let Some(name) = name else {
+            return Some(Resolution::new(&file, Confidence::High, strategy));
+        };
+        let item = format!("{file}::{name}");
+        if symbol_table.contains(&item) {
+            return Some(Resolution::new(&item, Confidence::High, "named_import"));
+        }
+
+        // `export { name } from "./x"` or `export * from "./x"` in the target module
+        if depth < MAX_REEXPORT_DEPTH {
+            for reexport in symbol_table.imports_of(&file) {
+                let candidate = match reexport.split_once("::") {
+                    Some((_, exported)) if exported == name => reexport.clone(),
+                    Some(_) => continue,
+                    None => format!("{reexport}::{name}"),
+                };
+                if let Some(found) = self.resolve_import(&candidate, &file, symbol_table, depth + 1)
+                    && found.id.contains("::")
+                {
+                    return Some(Resolution::new(&found.id, found.confidence, "reexport"));
+                }
+            }
+        }
+        Some(Resolution::new(&file, Confidence::Medium, strategy))
+    }
+
+    /// The file a specifier points at and which rule found it.
+    fn resolve_specifier(&self, specifier: &str, file_id: &str) -> Option<(String, &'static str)> {
+        if specifier.starts_with("./") || specifier.starts_with("../") || specifier == "." || specifier == ".." {
+            let base = normalize_relative(dir_of(file_id), specifier);
+            return self.find_file(&base).map(|f| (f, "relative"));
+        }
+        if let Some(absolute) = specifier.strip_prefix('/') {
+            return self.find_file(absolute).map(|f| (f, "relative"));
+        }
+
+        if let Some(config) = self.config_for(file_id) {
+            for (pattern, targets) in &config.paths {
+                let Some(matched) = match_pattern(pattern, specifier) else {
+                    continue;
+                };
+                let base_dir = config.base_url.as_deref().unwrap_or(&config.dir);
+                for target in targets {
+                    if let Some(file) = self.find_file(&normalize_relative(base_dir, &target.replacen('*', matched, 1))) {
+                        return Some((file, "tsconfig_paths"));
+                    }
+                }
+            }
+            if let Some(base_url) = &config.base_url
+                && let Some(file) = self.find_file(&normalize_relative(base_url, specifier))
+            {
+                return Some((file, "base_url"));
+            }
+        }
+
+        for package in &self.packages {
+            let subpath = match specifier.strip_prefix(package.name.as_str()) {
+                Some("") => None,
+                Some(rest) if rest.starts_with('/') => Some(&rest[1..]),
+                _ => continue,
+            };
+            let file = match subpath {
+                Some(subpath) => self
+                    .find_file(&normalize_relative(&package.dir, subpath))
+                    .or_else(|| self.find_file(&normalize_relative(&package.dir, &format!("src/{subpath}")))),
+                None => package.entry.clone(),
+            };
+            return file.map(|f| (f, "workspace_package"));
+        }
+        None
+    }
+
+    /// `base` itself, with an extension, or as a directory index. A `.js`
+    /// specifier may name a `.ts` source, as TypeScript ESM imports do.
+    fn find_file(&self, base: &str) -> Option<String> {
+        if self.files.contains(base) {
+            return Some(base.to_string());
+        }
+        let stem = [".js", ".jsx", ".mjs", ".cjs"].iter().find_map(|ext| base.strip_suffix(ext));
+        let candidates = EXTENSIONS
+            .iter()
+            .map(|ext| format!("{base}.{ext}"))
+            .chain(stem.into_iter().flat_map(|stem| ["ts", "tsx"].map(|ext| format!("{stem}.{ext}"))))
+            .chain(EXTENSIONS.iter().map(|ext| normalize_relative(base, &format!("index.{ext}"))));
+        candidates.into_iter().find(|candidate| self.files.contains(candidate))
+    }
+
+    fn config_for(&self, file_id: &str) -> Option<&TsConfig> {
+        self.configs
+            .iter()
+            .find(|config| config.dir.is_empty() || file_id.starts_with(&format!("{}/", config.dir)))
+    }
+}
+
+/// The part of `specifier` matched by `*` in `pattern`, or `""` for an exact match.
+fn match_pattern<'a>(pattern: &str, specifier: &'a str) -> Option<&'a str> {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => specifier
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+            .filter(|_| specifier.len() >= prefix.len() + suffix.len()),
+        None => (pattern == specifier).then_some(""),
+    }
+}
+
+/// Reads a tsconfig, merging a relative `extends` chain. Paths become repo-relative.
+fn read_tsconfig(repo_root: &Path, path: &str, depth: usize) -> Option<TsConfig> {
+    let json = read_json(repo_root, path)?;
+    let dir = dir_of(path).to_string();
+
+    let mut config = json
+        .get("extends")
+        .and_then(Value::as_str)
+        .filter(|parent| parent.starts_with('.') && depth < 3)
+        .and_then(|parent| {
+            let parent = if parent.ends_with(".json") { parent.to_string() } else { format!("{parent}.json") };
+            read_tsconfig(repo_root, &normalize_relative(&dir, &parent), depth + 1)
+        })
+        .unwrap_or_default();
+    config.dir = dir.clone();
+
+    let options = json.get("compilerOptions");
+    if let Some(base_url) = options.and_then(|o| o.get("baseUrl")).and_then(Value::as_str) {
+        config.base_url = Some(normalize_relative(&dir, base_url));
+    }
+    if let Some(paths) = options.and_then(|o| o.get("paths")).and_then(Value::as_object) {
+        config.paths = paths
+            .iter()
+            .map(|(pattern, targets)| {
+                let targets = targets
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect();
+                (pattern.clone(), targets)
+            })
+            .collect();
+        // Without baseUrl, paths are relative to the config's own directory
+        if config.base_url.is_none() {
+            config.base_url = Some(dir);
+        }
+    }
+    config.paths.sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.split('*').next().unwrap_or("").len()));
+    Some(config)
+}
+
+/// Named packages matched by the `workspaces` globs of any package.json.
+fn workspace_packages(manifests: &[(String, Value)], files: &HashSet<String>) -> Vec<WorkspacePackage> {
+    let mut member_globs = Vec::new();
+    for (dir, manifest) in manifests {
+        let globs = match manifest.get("workspaces") {
+            Some(Value::Array(globs)) => globs.clone(),
+            Some(Value::Object(workspaces)) => workspaces.get("packages").and_then(Value::as_array).cloned().unwrap_or_default(),
+            _ => continue,
+        };
+        member_globs.extend(globs.iter().filter_map(Value::as_str).map(|glob| normalize_relative(dir, glob)));
+    }
+
+    manifests
+        .iter()
+        .filter(|(dir, _)| member_globs.iter().any(|glob| glob_matches(glob, dir)))
+        .filter_map(|(dir, manifest)| {
+            let name = manifest.get("name")?.as_str()?.to_string();
+            let entry = ENTRY_FIELDS
+                .iter()
+                .filter_map(|field| manifest.get(*field).and_then(Value::as_str))
+                .map(|path| normalize_relative(dir, path))
+                .chain(["src/index", "index"].iter().flat_map(|stem| {
+                    EXTENSIONS.iter().map(move |ext| normalize_relative(dir, &format!("{stem}.{ext}")))
+                }))
+                .find(|candidate| files.contains(candidate));
+            Some(WorkspacePackage { name, dir: dir.clone(), entry })
+        })
+        .collect()
+}
+
+/// Workspace globs: `packages/*`, `packages/**` or an exact directory.
+fn glob_matches(glob: &str, dir: &str) -> bool {
+    if let Some(parent) = glob.strip_suffix("/**") {
+        dir.starts_with(&format!("{parent}/"))
+    } else if let Some(parent) = glob.strip_suffix("/*") {
+        dir_of(dir) == parent && !dir.is_empty()
+    } else {
+        glob == dir
+    }
+}
+
+fn read_json(repo_root: &Path, path: &str) -> Option<Value> {
+    let text = fs::read_to_string(repo_root.join(path)).ok()?;
+    match serde_json::from_str(&strip_jsonc(&text)) {
+        Ok(json) => Some(json),
+        Err(e) => {
+            debug!(path, "Skipping unreadable JSON config: {e}");
+            None
+        }
+    }
+}
+
+/// Drops `//` and `/* */` comments and trailing commas, which tsconfig files allow.
+fn strip_jsonc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            (',', _) => {
+                let rest: String = chars.clone().take_while(|c| c.is_whitespace()).collect();
+                let next = chars.clone().nth(rest.chars().count());
+                if !matches!(next, Some('}' | ']')) {
+                    out.push(c);
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{class, district, file};
+
+    #[test]
+    fn test_resolves_relative_alias_and_workspace_specifiers() {
+        let dir = tempfile::tempdir().unwrap();
+        let configs = [
+            ("package.json", r#"{ "workspaces": ["packages/*"] }"#),
+            ("web/tsconfig.json", "{\n  // aliases\n  \"compilerOptions\": { \"baseUrl\": \".\", \"paths\": { \"@/*\": [\"src/*\"], }, },\n}"),
+            ("packages/ui/package.json", r#"{ "name": "@acme/ui", "main": "src/index.ts" }"#),
+        ];
+        for (path, contents) in configs {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let cities = vec![district("", vec![
+            file("web/src/app.tsx", vec![], &[]),
+            file("web/src/models/index.ts", vec![class("web/src/models/index.ts::User")], &[]),
+            file("web/src/lib/api.ts", vec![], &["../models::User"]),
+            file("packages/ui/src/index.ts", vec![], &["./button::Button"]),
+            file("packages/ui/src/button.tsx", vec![class("packages/ui/src/button.tsx::Button")], &[]),
+        ])];
+        let mut table = SymbolTable::new();
+        table.index_cities(&cities);
+        let resolver = JsModuleResolver::new(dir.path(), &table);
+
+        let resolve = |import: &str| resolver.resolve(import, "web/src/app.tsx", &table).map(|r| (r.id, r.strategy));
+        assert_eq!(resolve("./lib/api.js"), Some(("web/src/lib/api.ts".to_string(), "relative")));
+        assert_eq!(resolve("./models::User"), Some(("web/src/models/index.ts::User".to_string(), "named_import")));
+        assert_eq!(resolve("@/lib/api"), Some(("web/src/lib/api.ts".to_string(), "tsconfig_paths")));
+        assert_eq!(resolve("src/lib/api"), Some(("web/src/lib/api.ts".to_string(), "base_url")));
+        assert_eq!(resolve("@acme/ui"), Some(("packages/ui/src/index.ts".to_string(), "workspace_package")));
+        assert_eq!(resolve("@acme/ui::Button"), Some(("packages/ui/src/button.tsx::Button".to_string(), "reexport")));
+        assert_eq!(resolve("react"), None);
+    }
+}
@@ -0,0 +1,55 @@
+//! Language-aware import resolution. Parsers record imports as written; the
+//! resolvers here map them onto the file, Building or Room ids they refer to,
+//! using the project layout on disk (manifests, module declarations).
+
+pub mod c;
+pub mod java;
+pub mod javascript;
+pub mod python;
+pub mod rust;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::languages::paths::{self, Family};
+use crate::symbol_table::{Resolution, SymbolTable};
+
+/// Import resolvers for every language that has one, built once per world.
+pub struct ModuleResolver {
+    c: c::CIncludeResolver,
+    java: java::JavaPackageResolver,
+    rust: rust::RustModuleResolver,
+    javascript: javascript::JsModuleResolver,
+    python: python::PythonModuleResolver,
+}
+
+impl ModuleResolver {
+    /// `rust_modules` holds the `mod` items of every Rust file, collected when it was parsed.
+    pub fn new(root: &Path, symbol_table: &SymbolTable, rust_modules: &HashMap<String, Vec<rust::ModDeclaration>>) -> Self {
+        Self {
+            c: c::CIncludeResolver::new(root, symbol_table),
+            java: java::JavaPackageResolver::new(symbol_table),
+            rust: rust::RustModuleResolver::new(root, symbol_table, rust_modules),
+            javascript: javascript::JsModuleResolver::new(root, symbol_table),
+            python: python::PythonModuleResolver::new(root, symbol_table),
+        }
+    }
+
+    /// Resolve `import` as recorded for `file_id`. Languages without a resolver
+    /// fall back to matching the import against file paths.
+    pub fn resolve_import(&self, import: &str, file_id: &str, symbol_table: &SymbolTable) -> Option<Resolution> {
+        match paths::family(file_id) {
+            Family::Rust => self.rust.resolve(import, file_id, symbol_table),
+            Family::Js => self.javascript.resolve(import, file_id, symbol_table),
+            Family::Python => self.python.resolve(import, file_id, symbol_table),
+            Family::C => self.c.resolve(import, file_id),
+            Family::Java => self.java.resolve(import, symbol_table),
+            Family::Other => symbol_table.resolve_import(import, file_id),
+        }
+    }
+
+    /// C/C++ prototypes paired with the Rooms that define them.
+    pub fn link_declarations(&self, symbol_table: &SymbolTable) -> Vec<(String, Resolution)> {
+        self.c.link_declarations(symbol_table)
+    }
+}
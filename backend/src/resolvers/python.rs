@@ -0,0 +1,308 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use tracing::debug;
+
+use crate::languages::paths::{dir_of, normalize_relative};
+use crate::symbol_table::{Confidence, Resolution, SymbolTable};
+
+/// How many `from x import y` hops in `__init__.py` files to follow.
+const MAX_REEXPORT_DEPTH: usize = 4;
+
+/// A module as found on disk: its file (None for a namespace package) and its directory path.
+struct ModuleTarget {
+    file: Option<String>,
+    path: String,
+}
+
+/// Resolves Python imports against the repository's import roots: the
+/// parents of top-level packages and the source directories declared in
+/// `pyproject.toml` / `setup.cfg` (e.g. `src` layouts).
+pub struct PythonModuleResolver {
+    files: HashSet<String>,
+    /// Directories holding at least one Python file, for namespace packages
+    dirs: HashSet<String>,
+    roots: Vec<String>,
+    /// `__all__` of each module that declares one
+    all_names: HashMap<String, Vec<String>>,
+}
+
+impl PythonModuleResolver {
+    pub fn new(repo_root: &Path, symbol_table: &SymbolTable) -> Self {
+        let files: HashSet<String> = symbol_table.files().filter(|f| f.ends_with(".py")).cloned().collect();
+
+        let mut dirs = HashSet::new();
+        for file in &files {
+            let mut dir = dir_of(file);
+            while dirs.insert(dir.to_string()) && !dir.is_empty() {
+                dir = dir_of(dir);
+            }
+        }
+
+        let mut roots: Vec<String> = Vec::new();
+        // The parent of every top-level package
+        for file in &files {
+            if let Some(package) = file.strip_suffix("/__init__.py")
+                && !files.contains(&normalize_relative(dir_of(package), "__init__.py"))
+            {
+                roots.push(dir_of(package).to_string());
+            }
+        }
+        // Project directories and the source directories their config declares
+        for dir in &dirs {
+            roots.extend(configured_roots(repo_root, dir, &dirs));
+        }
+        roots.push(String::new());
+        roots.sort_unstable();
+        roots.dedup();
+        // Deepest first, so the most specific root wins
+        roots.sort_by_key(|r| std::cmp::Reverse(r.matches('/').count() + usize::from(!r.is_empty())));
+
+        let all_names = files
+            .iter()
+            .filter_map(|file| {
+                let source = fs::read_to_string(repo_root.join(file)).ok()?;
+                Some((file.clone(), dunder_all(&source)?))
+            })
+            .collect();
+
+        debug!(roots = ?roots, "Found Python import roots");
+        Self { files, dirs, roots, all_names }
+    }
+
+    /// Resolve an import recorded by `py_parser` (`pkg.mod`, `.mod::Name`,
+    /// `..::mod`, `pkg::*`) made from `file_id`.
+    pub fn resolve(&self, import: &str, file_id: &str, symbol_table: &SymbolTable) -> Option<Resolution> {
+        self.resolve_import(import, file_id, symbol_table, 0)
+    }
+
+    fn resolve_import(&self, import: &str, file_id: &str, symbol_table: &SymbolTable, depth: usize) -> Option<Resolution> {
+        let (module, name) = match import.split_once("::") {
+            Some((module, name)) => (module, Some(name)),
+            None => (import, None),
+        };
+        let strategy = if module.starts_with('.') { "relative" } else { "package_root" };
+        let target = self.find_module(module, file_id)?;
+
+        let name = match name {
+            None | Some("*") => return target.file.map(|file| Resolution::new(&file, Confidence::High, strategy)),
+            Some(name) => name,
+        };
+
+        if let Some(file) = &target.file {
+            let item = format!("{file}::{name}");
+            if symbol_table.contains(&item) {
+                return Some(Resolution::new(&item, Confidence::High, "named_import"));
+            }
+        }
+        // `from pkg import module`
+        if let Some(submodule) = self.module_at(&normalize_relative(&target.path, name)).and_then(|m| m.file) {
+            return Some(Resolution::new(&submodule, Confidence::High, "submodule"));
+        }
+
+        let file = target.file?;
+        if depth < MAX_REEXPORT_DEPTH
+            && let Some(found) = self.follow_reexport(&file, name, symbol_table, depth)
+        {
+            return Some(found);
+        }
+        Some(Resolution::new(&file, Confidence::Medium, strategy))
+    }
+
+    /// A name that `file` (typically an `__init__.py`) brings in from another
+    /// module: `from .models import User` or a `*` import that exports it.
+    fn follow_reexport(&self, file: &str, name: &str, symbol_table: &SymbolTable, depth: usize) -> Option<Resolution> {
+        for import in symbol_table.imports_of(file) {
+            let candidate = match import.split_once("::") {
+                Some((_, imported)) if imported == name => import.clone(),
+                Some((module, "*")) => {
+                    // A star import only brings in the source module's `__all__`, when it has one
+                    let source = self.find_module(module, file).and_then(|m| m.file);
+                    let exported = source
+                        .and_then(|s| self.all_names.get(&s))
+                        .is_none_or(|names| names.iter().any(|n| n == name));
+                    if !exported {
+                        continue;
+                    }
+                    format!("{module}::{name}")
+                }
+                _ => continue,
+            };
+            if let Some(found) = self.resolve_import(&candidate, file, symbol_table, depth + 1)
+                && found.confidence == Confidence::High
+            {
+                return Some(Resolution::new(&found.id, found.confidence, "reexport"));
+            }
+        }
+        None
+    }
+
+    /// The module a dotted (optionally relative) name refers to from `file_id`.
+    fn find_module(&self, module: &str, file_id: &str) -> Option<ModuleTarget> {
+        let rest = module.trim_start_matches('.');
+        let level = module.len() - rest.len();
+        let relative = rest.replace('.', "/");
+
+        if level > 0 {
+            let mut base = dir_of(file_id);
+            for _ in 1..level {
+                base = dir_of(base);
+            }
+            return self.module_at(&normalize_relative(base, &relative));
+        }
+
+        // Roots the importing file lives under, then its own directory (scripts), then any other root
+        let (containing, others): (Vec<&String>, Vec<&String>) = self
+            .roots
+            .iter()
+            .partition(|root| root.is_empty() || file_id.starts_with(&format!("{root}/")));
+        let script_dir = dir_of(file_id).to_string();
+        containing
+            .into_iter()
+            .take_while(|root| !root.is_empty())
+            .chain(std::iter::once(&script_dir))
+            .chain(others)
+            .chain(self.roots.iter().filter(|r| r.is_empty()))
+            .find_map(|root| self.module_at(&normalize_relative(root, &relative)))
+    }
+
+    fn module_at(&self, path: &str) -> Option<ModuleTarget> {
+        let module_file = format!("{path}.py");
+        let init = normalize_relative(path, "__init__.py");
+        let file = if self.files.contains(&module_file) {
+            Some(module_file)
+        } else if self.files.contains(&init) {
+            Some(init)
+        } else if self.dirs.contains(path) && !path.is_empty() {
+            None
+        } else {
+            return None;
+        };
+        Some(ModuleTarget { file, path: path.to_string() })
+    }
+}
+
+/// Import roots declared by the project config in `dir`: the directory itself
+/// plus source directories such as `src`.
+fn configured_roots(repo_root: &Path, dir: &str, dirs: &HashSet<String>) -> Vec<String> {
+    let project = repo_root.join(dir);
+    let pyproject = fs::read_to_string(project.join("pyproject.toml")).ok();
+    let setup_cfg = fs::read_to_string(project.join("setup.cfg")).ok();
+    if pyproject.is_none() && setup_cfg.is_none() && !project.join("setup.py").exists() {
+        return Vec::new();
+    }
+
+    let mut source_dirs = Vec::new();
+    if let Some(table) = pyproject.and_then(|text| text.parse::<toml::Table>().ok()) {
+        let tool = table.get("tool");
+        let setuptools = tool.and_then(|t| t.get("setuptools"));
+        // [tool.setuptools] package-dir = { "" = "src" }
+        if let Some(package_dir) = setuptools.and_then(|s| s.get("package-dir")).and_then(|p| p.get("")).and_then(|p| p.as_str()) {
+            source_dirs.push(package_dir.to_string());
+        }
+        // [tool.setuptools.packages.find] where = ["src"]
+        if let Some(wheres) = setuptools
+            .and_then(|s| s.get("packages"))
+            .and_then(|p| p.get("find"))
+            .and_then(|f| f.get("where"))
+            .and_then(|w| w.as_array())
+        {
+            source_dirs.extend(wheres.iter().filter_map(|w| w.as_str()).map(str::to_string));
+        }
+        // [tool.poetry] packages = [{ include = "pkg", from = "src" }]
+        if let Some(packages) = tool.and_then(|t| t.get("poetry")).and_then(|p| p.get("packages")).and_then(|p| p.as_array()) {
+            source_dirs.extend(packages.iter().filter_map(|p| p.get("from")?.as_str()).map(str::to_string));
+        }
+        // [tool.hatch.build.targets.wheel] packages = ["src/pkg"]
+        if let Some(packages) = tool
+            .and_then(|t| t.get("hatch"))
+            .and_then(|h| h.get("build"))
+            .and_then(|b| b.get("targets"))
+            .and_then(|t| t.get("wheel"))
+            .and_then(|w| w.get("packages"))
+            .and_then(|p| p.as_array())
+        {
+            source_dirs.extend(packages.iter().filter_map(|p| p.as_str()).map(|p| dir_of(p).to_string()));
+        }
+    }
+    if let Some(cfg) = setup_cfg {
+        // package_dir = =src  /  where = src
+        for line in cfg.lines().map(str::trim) {
+            let Some((key, value)) = line.split_once('=') else { continue };
+            match key.trim() {
+                "package_dir" => source_dirs.push(value.trim().trim_start_matches('=').trim().to_string()),
+                "where" => source_dirs.push(value.trim().to_string()),
+                _ => {}
+            }
+        }
+    }
+    // A bare src layout needs no configuration
+    if source_dirs.is_empty() && dirs.contains(&normalize_relative(dir, "src")) {
+        source_dirs.push("src".to_string());
+    }
+
+    std::iter::once(dir.to_string())
+        .chain(source_dirs.iter().filter(|s| !s.is_empty()).map(|s| normalize_relative(dir, s)))
+        .collect()
+}
+
+/// The names in a module-level `__all__ = [...]` (or tuple) assignment.
+fn dunder_all(source: &str) -> Option<Vec<String>> {
+    let start = source.lines().position(|line| line.starts_with("__all__"))?;
+    let text: String = source.lines().skip(start).collect::<Vec<_>>().join("\n");
+    let open = text.find(['[', '('])?;
+    let close = text[open..].find([']', ')'])? + open;
+    let names = text[open + 1..close]
+        .split(',')
+        .map(|item| item.trim().trim_matches(|c| c == '"' || c == '\''))
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect();
+    Some(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GameEntity;
+    use crate::test_support;
+
+    #[test]
+    fn test_resolves_absolute_relative_and_reexported_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("api/pyproject.toml", "[tool.setuptools.packages.find]\nwhere = [\"src\"]\n"),
+            ("api/src/shop/__init__.py", "from .models import *\nfrom .views import render\n"),
+            ("api/src/shop/models.py", "__all__ = ['User']\nclass User:\n    pass\nclass Secret:\n    pass\n"),
+            ("api/src/shop/views.py", "from . import models\nfrom .models import User\ndef render():\n    pass\n"),
+            ("api/src/shop/cli.py", "import shop.views\nfrom shop import User, Secret, render\nimport requests\n"),
+        ];
+        for (path, contents) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let buildings: Vec<GameEntity> = files
+            .iter()
+            .filter(|(path, _)| path.ends_with(".py"))
+            .map(|(path, source)| test_support::parsed_file(path, source))
+            .collect();
+        let mut table = SymbolTable::new();
+        table.index_cities(&buildings);
+        let resolver = PythonModuleResolver::new(dir.path(), &table);
+
+        let resolve = |import: &str, from: &str| resolver.resolve(import, from, &table).map(|r| (r.id, r.strategy));
+        let views = "api/src/shop/views.py";
+        let cli = "api/src/shop/cli.py";
+        assert_eq!(resolve(".::models", views), Some(("api/src/shop/models.py".to_string(), "submodule")));
+        assert_eq!(resolve(".models::User", views), Some(("api/src/shop/models.py::User".to_string(), "named_import")));
+        assert_eq!(resolve("shop.views", cli), Some((views.to_string(), "package_root")));
+        assert_eq!(resolve("shop::User", cli), Some(("api/src/shop/models.py::User".to_string(), "reexport")));
+        assert_eq!(resolve("shop::render", cli), Some(("api/src/shop/views.py::render".to_string(), "reexport")));
+        // Not in models.__all__, so `from .models import *` does not re-export it
+        assert_eq!(resolve("shop::Secret", cli), Some(("api/src/shop/__init__.py".to_string(), "package_root")));
+        assert_eq!(resolve("requests", cli), None);
+    }
+}
@@ -0,0 +1,520 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use tracing::debug;
+use tree_sitter::{Node, Tree};
+
+use crate::languages::paths::{dir_of, file_of, normalize_relative};
+use crate::languages::parser_utils;
+use crate::symbol_table::{Confidence, Resolution, SymbolTable};
+
+/// How many `use` hops to follow when an imported item is re-exported.
+const MAX_REEXPORT_DEPTH: usize = 4;
+
+/// Target directories whose files are each a crate root of their own.
+const TARGET_DIRS: &[&str] = &["src/bin", "tests", "examples", "benches"];
+
+/// Where a file sits in its crate's module tree.
+struct ModulePos {
+    /// Root file of the crate (`src/lib.rs`, `src/main.rs`, ...)
+    root: String,
+    /// Module path below the root, e.g. `["db", "world"]`
+    path: Vec<String>,
+    /// Directory of the Cargo package the file belongs to
+    package_dir: String,
+}
+
+/// The parts of a `Cargo.toml` that matter for resolution.
+struct Package {
+    dir: String,
+    /// Library crate name as used in code (`-` replaced by `_`)
+    name: Option<String>,
+    lib_root: Option<String>,
+    /// Explicit `[[bin]]` paths
+    bin_roots: Vec<String>,
+    /// Path dependencies: name used in code -> dependency package dir
+    path_deps: HashMap<String, String>,
+}
+
+/// Resolves Rust `use` paths by rebuilding each crate's module tree from its
+/// `mod` declarations, starting at the targets of every `Cargo.toml`.
+pub struct RustModuleResolver {
+    file_modules: HashMap<String, ModulePos>,
+    /// (crate root, "a::b") -> file declaring that module, or the module
+    /// Building for an inline `mod a { .. }`
+    modules: HashMap<(String, String), String>,
+    /// Library crate name -> root file, for every package in the repo
+    crates: HashMap<String, String>,
+    /// Package dir -> dependency name -> root file
+    dependencies: HashMap<String, HashMap<String, String>>,
+}
+
+impl RustModuleResolver {
+    /// `declarations` holds each file's `mod` items, as [`mod_declarations`]
+    /// extracted them while the file was parsed.
+    pub fn new(repo_root: &Path, symbol_table: &SymbolTable, declarations: &HashMap<String, Vec<ModDeclaration>>) -> Self {
+        let mut files: Vec<&str> =
+            symbol_table.files().map(String::as_str).filter(|f| f.ends_with(".rs")).collect();
+        files.sort_unstable();
+        let file_set: HashSet<&str> = files.iter().copied().collect();
+
+        let mut resolver = Self {
+            file_modules: HashMap::new(),
+            modules: HashMap::new(),
+            crates: HashMap::new(),
+            dependencies: HashMap::new(),
+        };
+
+        let packages = discover_packages(repo_root, &files);
+        for package in &packages {
+            if let (Some(name), Some(lib_root)) = (&package.name, &package.lib_root) {
+                resolver.crates.insert(name.clone(), lib_root.clone());
+            }
+            let deps = package
+                .path_deps
+                .iter()
+                .filter_map(|(name, dir)| {
+                    let dep = packages.iter().find(|p| &p.dir == dir)?;
+                    Some((name.clone(), dep.lib_root.clone()?))
+                })
+                .collect();
+            resolver.dependencies.insert(package.dir.clone(), deps);
+        }
+
+        let mut roots: Vec<(String, String)> = packages
+            .iter()
+            .flat_map(|package| {
+                target_roots(package, &files).into_iter().map(|root| (root, package.dir.clone()))
+            })
+            .collect();
+        if packages.is_empty() {
+            // No manifest: treat every lib.rs / main.rs as a crate of its own
+            roots = files
+                .iter()
+                .filter(|f| matches!(f.rsplit('/').next(), Some("lib.rs" | "main.rs")))
+                .map(|f| (f.to_string(), dir_of(f).to_string()))
+                .collect();
+        }
+
+        for (root, package_dir) in &roots {
+            resolver.walk_file(declarations, root, root, Vec::new(), package_dir, &file_set);
+        }
+        resolver.place_orphans(&files, &roots);
+
+        debug!(crates = resolver.crates.len(), modules = resolver.modules.len(), "Built Rust module trees");
+        resolver
+    }
+
+    /// Resolve a `use` path recorded by `rs_parser` for `file_id` to the module
+    /// file, or the item inside it when the item is indexed.
+    pub fn resolve(&self, import: &str, file_id: &str, symbol_table: &SymbolTable) -> Option<Resolution> {
+        self.resolve_path(import, file_id, symbol_table, 0)
+    }
+
+    fn resolve_path(&self, import: &str, file_id: &str, symbol_table: &SymbolTable, depth: usize) -> Option<Resolution> {
+        let pos = self.file_modules.get(file_id)?;
+        let segments: Vec<&str> = import.trim_start_matches("::").split("::").collect();
+        let (root, mut path, mut rest) = self.anchor(pos, file_id, &segments, symbol_table)?;
+
+        // Descend through the module segments
+        let mut file = self.modules.get(&(root.clone(), path.join("::")))?.clone();
+        while let Some(&segment) = rest.first() {
+            if matches!(segment, "self" | "*") {
+                rest = &rest[1..];
+                continue;
+            }
+            path.push(segment.to_string());
+            match self.modules.get(&(root.clone(), path.join("::"))) {
+                Some(module_file) => {
+                    file = module_file.clone();
+                    rest = &rest[1..];
+                }
+                None => break,
+            }
+        }
+        if rest.is_empty() {
+            return Some(Resolution::new(&file, Confidence::High, "rust_module"));
+        }
+
+        // The longest indexed item path (`Enum::Variant` resolves to the enum)
+        for len in (1..=rest.len()).rev() {
+            let id = format!("{file}::{}", rest[..len].join("::"));
+            if symbol_table.contains(&id) {
+                return Some(Resolution::new(&id, Confidence::High, "rust_item"));
+            }
+        }
+
+        // An item the module brings in with its own `use` (re-exports, globs)
+        if depth < MAX_REEXPORT_DEPTH {
+            let item = rest[0];
+            let suffix = rest[1..].iter().map(|s| format!("::{s}")).collect::<String>();
+            // Inline modules share their file's imports
+            let module_file = file_of(&file);
+            let aliased = symbol_table.alias(module_file, item).map(|path| format!("{path}{suffix}"));
+            let reexports = symbol_table.imports_of(module_file).iter().filter_map(|reexport| match reexport.strip_suffix("::*") {
+                Some(glob) => Some(format!("{glob}::{item}{suffix}")),
+                None if reexport.rsplit("::").next() == Some(item) => Some(format!("{reexport}{suffix}")),
+                None => None,
+            });
+            for candidate in aliased.into_iter().chain(reexports) {
+                if let Some(found) = self.resolve_path(&candidate, module_file, symbol_table, depth + 1)
+                    && found.strategy != "rust_module"
+                {
+                    return Some(Resolution::new(&found.id, found.confidence, "rust_reexport"));
+                }
+            }
+        }
+
+        Some(Resolution::new(&file, Confidence::Medium, "rust_module"))
+    }
+
+    /// Where a path starts: its crate root, the module it is relative to, and
+    /// the segments left to walk. None for external crates.
+    fn anchor<'a>(
+        &self,
+        pos: &ModulePos,
+        file_id: &str,
+        segments: &'a [&'a str],
+        symbol_table: &SymbolTable,
+    ) -> Option<(String, Vec<String>, &'a [&'a str])> {
+        let first = *segments.first()?;
+        match first {
+            "crate" => Some((pos.root.clone(), Vec::new(), &segments[1..])),
+            "self" | "super" => {
+                let mut path = pos.path.clone();
+                let mut i = 0;
+                while let Some(&segment) = segments.get(i) {
+                    match segment {
+                        "self" => {}
+                        "super" => {
+                            path.pop()?;
+                        }
+                        _ => break,
+                    }
+                    i += 1;
+                }
+                Some((pos.root.clone(), path, &segments[i..]))
+            }
+            _ => {
+                // Since the 2018 edition a path may start at a child module or an item in scope
+                let mut local = pos.path.clone();
+                local.push(first.to_string());
+                if self.modules.contains_key(&(pos.root.clone(), local.join("::")))
+                    || symbol_table.contains(&format!("{file_id}::{first}"))
+                {
+                    return Some((pos.root.clone(), pos.path.clone(), segments));
+                }
+                let lib_root = self
+                    .dependencies
+                    .get(&pos.package_dir)
+                    .and_then(|deps| deps.get(first))
+                    .or_else(|| self.crates.get(first))?;
+                Some((lib_root.clone(), Vec::new(), &segments[1..]))
+            }
+        }
+    }
+
+    fn walk_file(
+        &mut self,
+        declarations: &HashMap<String, Vec<ModDeclaration>>,
+        crate_root: &str,
+        file: &str,
+        module_path: Vec<String>,
+        package_dir: &str,
+        files: &HashSet<&str>,
+    ) {
+        if self.file_modules.contains_key(file) {
+            return;
+        }
+        self.modules.insert((crate_root.to_string(), module_path.join("::")), file.to_string());
+        self.file_modules.insert(
+            file.to_string(),
+            ModulePos { root: crate_root.to_string(), path: module_path.clone(), package_dir: package_dir.to_string() },
+        );
+
+        // `mod foo;` in lib.rs, main.rs or mod.rs looks next to the file, elsewhere in a dir named after it
+        let owns_dir = file == crate_root || file.rsplit('/').next() == Some("mod.rs");
+        let mod_dir = if owns_dir { dir_of(file).to_string() } else { file.trim_end_matches(".rs").to_string() };
+
+        for declaration in declarations.get(file).into_iter().flatten() {
+            let mut path = module_path.clone();
+            path.extend(declaration.path.iter().cloned());
+            match declaration.candidates(&mod_dir, dir_of(file)).iter().find(|c| files.contains(c.as_str())) {
+                Some(child) => self.walk_file(declarations, crate_root, child, path, package_dir, files),
+                // Inline module: the module Building inside this file
+                None if declaration.inline => {
+                    let module_id = format!("{file}::{}", declaration.path.join("::"));
+                    self.modules.insert((crate_root.to_string(), path.join("::")), module_id);
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Files no `mod` declaration reaches (generated or cfg'd module lists) get
+    /// the module path their location implies under the nearest crate root.
+    fn place_orphans(&mut self, files: &[&str], roots: &[(String, String)]) {
+        for file in files {
+            if self.file_modules.contains_key(*file) {
+                continue;
+            }
+            let Some((root, package_dir)) = roots
+                .iter()
+                .filter(|(root, _)| {
+                    let dir = dir_of(root);
+                    dir.is_empty() || file.starts_with(&format!("{dir}/"))
+                })
+                .max_by_key(|(root, _)| dir_of(root).len())
+            else {
+                continue;
+            };
+
+            let relative = file[dir_of(root).len()..].trim_start_matches('/').trim_end_matches(".rs");
+            let mut path: Vec<String> = relative.split('/').map(str::to_string).collect();
+            if path.last().is_some_and(|last| last == "mod") {
+                path.pop();
+            }
+            self.modules.entry((root.clone(), path.join("::"))).or_insert_with(|| file.to_string());
+            self.file_modules.insert(
+                file.to_string(),
+                ModulePos { root: root.clone(), path, package_dir: package_dir.clone() },
+            );
+        }
+    }
+}
+
+/// A `mod` item: its path relative to the declaring file's module, and where
+/// its body is. Which files it may live in depends on whether the declaring
+/// file is a crate root, so [`ModDeclaration::candidates`] works that out
+/// once the module tree is known.
+#[derive(Debug, Clone)]
+pub struct ModDeclaration {
+    pub path: Vec<String>,
+    /// Inline `mod name { ... }`
+    pub inline: bool,
+    /// `#[path = "..."]`
+    pub custom_path: Option<String>,
+}
+
+impl ModDeclaration {
+    /// Files the module may live in, given the directory the declaring file's
+    /// child modules live in; empty for an inline module.
+    fn candidates(&self, mod_dir: &str, file_dir: &str) -> Vec<String> {
+        if self.inline {
+            return Vec::new();
+        }
+        let (name, inline_parents) = self.path.split_last().expect("mod path has a name");
+        let mod_dir = inline_parents.iter().fold(mod_dir.to_string(), |dir, parent| normalize_relative(&dir, parent));
+        match &self.custom_path {
+            // #[path] is relative to the declaring file, or to the inline module's directory
+            Some(custom) => vec![normalize_relative(if inline_parents.is_empty() { file_dir } else { &mod_dir }, custom)],
+            None => vec![normalize_relative(&mod_dir, &format!("{name}.rs")), normalize_relative(&mod_dir, &format!("{name}/mod.rs"))],
+        }
+    }
+}
+
+/// The `mod` items of a Rust file, including those nested in inline modules.
+pub fn mod_declarations(tree: &Tree, source: &str) -> Vec<ModDeclaration> {
+    let mut declarations = Vec::new();
+    collect_mod_declarations(tree.root_node(), source.as_bytes(), &[], &mut declarations);
+    declarations
+}
+
+fn collect_mod_declarations(node: Node, source: &[u8], inline: &[String], out: &mut Vec<ModDeclaration>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind() != "mod_item" {
+            continue;
+        }
+        let Some(name) = child.child_by_field_name("name").map(|n| parser_utils::get_text(n, source)) else {
+            continue;
+        };
+        let mut path = inline.to_vec();
+        path.push(name);
+
+        if let Some(body) = child.child_by_field_name("body") {
+            collect_mod_declarations(body, source, &path, out);
+            out.push(ModDeclaration { path, inline: true, custom_path: None });
+        } else {
+            out.push(ModDeclaration { path, inline: false, custom_path: path_attribute(child, source) });
+        }
+    }
+}
+
+/// The value of a `#[path = "..."]` attribute on `mod_item`.
+fn path_attribute(mod_item: Node, source: &[u8]) -> Option<String> {
+    let mut sibling = mod_item.prev_named_sibling();
+    while let Some(node) = sibling {
+        match node.kind() {
+            "attribute_item" => {
+                let text = parser_utils::get_text(node, source);
+                let inner = text.trim_start_matches("#[").trim_end_matches(']');
+                if let Some(value) = inner.strip_prefix("path")
+                    && let Some(value) = value.trim_start().strip_prefix('=')
+                {
+                    return Some(value.trim().trim_matches('"').to_string());
+                }
+            }
+            "line_comment" | "block_comment" => {}
+            _ => break,
+        }
+        sibling = node.prev_named_sibling();
+    }
+    None
+}
+
+/// Reads every `Cargo.toml` in a directory that contains (or is an ancestor of) a Rust file.
+fn discover_packages(repo_root: &Path, files: &[&str]) -> Vec<Package> {
+    let mut dirs: Vec<&str> = Vec::new();
+    for file in files {
+        let mut dir = dir_of(file);
+        loop {
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+            if dir.is_empty() {
+                break;
+            }
+            dir = dir_of(dir);
+        }
+    }
+    dirs.sort_unstable();
+
+    dirs.into_iter()
+        .filter_map(|dir| {
+            let manifest = fs::read_to_string(repo_root.join(dir).join("Cargo.toml")).ok()?;
+            match manifest.parse::<toml::Table>() {
+                Ok(table) => Some(parse_package(dir, &table, files)),
+                Err(e) => {
+                    debug!(dir, "Skipping unreadable Cargo.toml: {e}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn parse_package(dir: &str, manifest: &toml::Table, files: &[&str]) -> Package {
+    let package_name = manifest.get("package").and_then(|p| p.get("name")).and_then(|n| n.as_str());
+    let lib = manifest.get("lib");
+    let name = lib
+        .and_then(|l| l.get("name"))
+        .and_then(|n| n.as_str())
+        .or(package_name)
+        .map(|n| n.replace('-', "_"));
+
+    let lib_path = lib.and_then(|l| l.get("path")).and_then(|p| p.as_str()).unwrap_or("src/lib.rs");
+    let lib_root = Some(normalize_relative(dir, lib_path)).filter(|root| files.contains(&root.as_str()));
+
+    let bin_roots = manifest
+        .get("bin")
+        .and_then(|b| b.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|bin| bin.get("path").and_then(|p| p.as_str()))
+        .map(|path| normalize_relative(dir, path))
+        .collect();
+
+    let mut path_deps = HashMap::new();
+    for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+        let Some(deps) = manifest.get(section).and_then(|d| d.as_table()) else {
+            continue;
+        };
+        for (name, spec) in deps {
+            if let Some(path) = spec.get("path").and_then(|p| p.as_str()) {
+                path_deps.insert(name.replace('-', "_"), normalize_relative(dir, path));
+            }
+        }
+    }
+
+    Package { dir: dir.to_string(), name, lib_root, bin_roots, path_deps }
+}
+
+/// Crate roots of a package: library first, then binaries, tests, examples and benches.
+fn target_roots(package: &Package, files: &[&str]) -> Vec<String> {
+    let mut roots: Vec<String> = package.lib_root.iter().chain(&package.bin_roots).cloned().collect();
+    let prefix = if package.dir.is_empty() { String::new() } else { format!("{}/", package.dir) };
+
+    for file in files {
+        let Some(relative) = file.strip_prefix(prefix.as_str()) else {
+            continue;
+        };
+        let dir = dir_of(relative);
+        let is_target = matches!(relative, "src/main.rs" | "build.rs")
+            || TARGET_DIRS.contains(&dir)
+            || (relative.ends_with("/main.rs") && TARGET_DIRS.contains(&dir_of(dir)));
+        if is_target && !roots.iter().any(|r| r == file) {
+            roots.push(file.to_string());
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::registry;
+    use crate::models::GameEntity;
+    use crate::test_support::{file, function};
+
+    #[test]
+    fn test_resolves_use_paths_through_mod_tree_and_workspace_crates() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("Cargo.toml", "[workspace]\nmembers = [\"app\", \"core-lib\"]\n"),
+            ("app/Cargo.toml", "[package]\nname = \"app\"\n[dependencies]\ncore = { package = \"core-lib\", path = \"../core-lib\" }\n"),
+            ("app/src/main.rs", "mod db;\n#[path = \"legacy/util.rs\"]\nmod util;\n"),
+            ("app/src/db/mod.rs", "pub mod world;\n"),
+            ("app/src/db/world.rs", ""),
+            ("app/src/legacy/util.rs", ""),
+            ("core-lib/Cargo.toml", "[package]\nname = \"core-lib\"\n"),
+            ("core-lib/src/lib.rs", "mod models;\npub use models::User;\n"),
+            ("core-lib/src/models.rs", ""),
+        ];
+        for (path, contents) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let cities = vec![GameEntity::District {
+            id: "district_".to_string(),
+            name: "root".to_string(),
+            path: String::new(),
+            children: vec![
+                file("app/src/main.rs", vec![], &["crate::db::world::store", "self::util", "core::User"]),
+                file("app/src/db/mod.rs", vec![], &[]),
+                file("app/src/db/world.rs", vec![function("app/src/db/world.rs::store")], &["super::super::util::*"]),
+                file("app/src/legacy/util.rs", vec![], &[]),
+                file("core-lib/src/lib.rs", vec![], &["models::User"]),
+                file("core-lib/src/models.rs", vec![function("core-lib/src/models.rs::User")], &[]),
+            ],
+            metadata: None,
+        }];
+        let declarations: HashMap<String, Vec<ModDeclaration>> = files
+            .iter()
+            .filter(|(path, _)| path.ends_with(".rs"))
+            .map(|(path, source)| (path.to_string(), mod_declarations(&registry::parse_tree("rs", source).unwrap(), source)))
+            .collect();
+        let mut table = SymbolTable::new();
+        table.index_cities(&cities);
+        let resolver = RustModuleResolver::new(dir.path(), &table, &declarations);
+
+        let resolve = |import: &str, from: &str| resolver.resolve(import, from, &table).map(|r| (r.id, r.strategy));
+        assert_eq!(
+            resolve("crate::db::world::store", "app/src/main.rs"),
+            Some(("app/src/db/world.rs::store".to_string(), "rust_item"))
+        );
+        assert_eq!(resolve("self::util", "app/src/main.rs"), Some(("app/src/legacy/util.rs".to_string(), "rust_module")));
+        assert_eq!(
+            resolve("super::super::util::*", "app/src/db/world.rs"),
+            Some(("app/src/legacy/util.rs".to_string(), "rust_module"))
+        );
+        assert_eq!(
+            resolve("core::User", "app/src/main.rs"),
+            Some(("core-lib/src/models.rs::User".to_string(), "rust_reexport"))
+        );
+        assert_eq!(resolve("serde::Deserialize", "app/src/main.rs"), None);
+    }
+}
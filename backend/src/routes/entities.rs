@@ -0,0 +1,260 @@
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use mongodb::bson::oid::ObjectId;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::instrument;
+
+use crate::auth::AuthUser;
+use crate::dead_code;
+use crate::db::models::EntityDoc;
+use crate::db::world;
+use crate::error::AppError;
+use crate::models::{CoverageQuery, CoverageSummary, DeadCodeReport, EntityNode, EntityPage, Route, RoutesQuery, UnresolvedSummary, ViolationReport, WorldSummary};
+use crate::rules;
+use crate::services::{coverage_service, world_service};
+use crate::state::AppState;
+use crate::unresolved;
+
+const DEFAULT_PAGE_SIZE: u64 = 100;
+const MAX_PAGE_SIZE: u64 = 500;
+const MAX_ROUTE_QUERY_IDS: usize = 500;
+
+#[derive(Deserialize, Debug)]
+pub struct PageParams {
+    #[serde(default)]
+    pub offset: u64,
+    #[serde(default = "default_page_size")]
+    pub limit: u64,
+}
+
+fn default_page_size() -> u64 {
+    DEFAULT_PAGE_SIZE
+}
+
+impl PageParams {
+    /// The requested page size, kept between 1 and `MAX_PAGE_SIZE`.
+    fn clamped_limit(&self) -> u64 {
+        self.limit.clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+/// Offset of the page after one that returned `returned` items, if any remain.
+fn next_offset(offset: u64, returned: usize, total: u64) -> Option<u64> {
+    let next = offset + returned as u64;
+    (next < total).then_some(next)
+}
+
+#[instrument(skip(state, auth_user))]
+pub async fn world_summary(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(world_id): Path<String>,
+) -> Result<Json<WorldSummary>, AppError> {
+    let world_doc = world_service::authorize_world(&state, &auth_user, &world_id).await?;
+    let world_oid = world_service::world_oid(&world_doc)?;
+
+    let (cities, _) =
+        world::get_child_entities(&state.db, world_oid, None, 0, MAX_PAGE_SIZE as i64).await?;
+
+    Ok(Json(WorldSummary {
+        world_id: world_oid.to_hex(),
+        commit_hash: world_doc.commit_hash.clone(),
+        parsed_at: world_doc.parsed_at.clone(),
+        world_meta: world::world_meta_from_doc(&world_doc),
+        entity_count: world_doc.entity_count,
+        route_count: world_doc.route_count,
+        cities: to_nodes(&state, world_oid, cities).await?,
+    }))
+}
+
+/// Unresolved calls and imports grouped by District and package.
+#[instrument(skip(state, auth_user))]
+pub async fn unresolved_summary(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(world_id): Path<String>,
+) -> Result<Json<UnresolvedSummary>, AppError> {
+    let world_doc = world_service::authorize_world(&state, &auth_user, &world_id).await?;
+    let world_oid = world_service::world_oid(&world_doc)?;
+
+    let refs = world::get_unresolved(&state.db, world_oid).await?;
+    let cities = world::get_entity_tree(&state.db, world_oid).await?;
+    Ok(Json(unresolved::summarize(world_oid.to_hex(), &refs, &cities)))
+}
+
+/// Routes breaking the repository's architecture rules (`.nilsbohr/rules.toml`).
+/// A rules file that failed to load is reported in `rules_error`.
+#[instrument(skip(state, auth_user))]
+pub async fn rule_violations(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(world_id): Path<String>,
+) -> Result<Json<ViolationReport>, AppError> {
+    let world_doc = world_service::authorize_world(&state, &auth_user, &world_id).await?;
+    let world_oid = world_service::world_oid(&world_doc)?;
+
+    let routes = world::get_routes_with_violations(&state.db, world_oid).await?;
+    let violations = rules::violations(&routes);
+    Ok(Json(ViolationReport {
+        world_id: world_oid.to_hex(),
+        violation_count: violations.len() as u32,
+        violations,
+        rules_error: world_doc.world_meta_rules_error.clone(),
+    }))
+}
+
+/// Private Rooms and Buildings no entry point reaches, largest first.
+#[instrument(skip(state, auth_user))]
+pub async fn dead_code(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(world_id): Path<String>,
+) -> Result<Json<DeadCodeReport>, AppError> {
+    let world_doc = world_service::authorize_world(&state, &auth_user, &world_id).await?;
+    let world_oid = world_service::world_oid(&world_doc)?;
+
+    let docs = world::get_dead_entities(&state.db, world_oid).await?;
+    let mut entities: Vec<_> = docs.iter().filter_map(|doc| dead_code::dead_entity(&doc.entity)).collect();
+    entities.sort_by_key(|e| std::cmp::Reverse(e.loc));
+    Ok(Json(DeadCodeReport {
+        world_id: world_oid.to_hex(),
+        dead_count: entities.len() as u32,
+        dead_loc: dead_code::total_loc(&entities),
+        entities,
+    }))
+}
+
+/// Imports an lcov, Cobertura or llvm-cov JSON report sent as the request
+/// body and attaches its coverage to the world's entities.
+#[instrument(skip(state, auth_user, report))]
+pub async fn import_coverage(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(world_id): Path<String>,
+    Query(query): Query<CoverageQuery>,
+    report: String,
+) -> Result<Json<CoverageSummary>, AppError> {
+    Ok(Json(coverage_service::import_coverage(&state, &auth_user, &world_id, query, &report).await?))
+}
+
+/// One entity with its child count. Entity ids contain `/` and `::`, so
+/// `:entity_id` must be percent-encoded as a single path segment
+/// (`src%2Fmain.rs%3A%3Amain` for `src/main.rs::main`).
+#[instrument(skip(state, auth_user))]
+pub async fn entity(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((world_id, entity_id)): Path<(String, String)>,
+) -> Result<Json<EntityNode>, AppError> {
+    let world_doc = world_service::authorize_world(&state, &auth_user, &world_id).await?;
+    let world_oid = world_service::world_oid(&world_doc)?;
+
+    let entity = world::get_entity(&state.db, world_oid, &entity_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Entity {entity_id} not found")))?;
+
+    let node = to_nodes(&state, world_oid, vec![entity])
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::Internal("entity conversion produced no node".into()))?;
+    Ok(Json(node))
+}
+
+/// A page of an entity's direct children in source order; `:entity_id` is
+/// percent-encoded as for [`entity`]. Follow `next_offset` for the next page.
+#[instrument(skip(state, auth_user))]
+pub async fn entity_children(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((world_id, entity_id)): Path<(String, String)>,
+    Query(params): Query<PageParams>,
+) -> Result<Json<EntityPage>, AppError> {
+    let world_doc = world_service::authorize_world(&state, &auth_user, &world_id).await?;
+    let world_oid = world_service::world_oid(&world_doc)?;
+    let limit = params.clamped_limit();
+
+    let (children, total) = world::get_child_entities(
+        &state.db,
+        world_oid,
+        Some(&entity_id),
+        params.offset,
+        limit as i64,
+    )
+    .await?;
+
+    let items = to_nodes(&state, world_oid, children).await?;
+    Ok(Json(EntityPage {
+        next_offset: next_offset(params.offset, items.len(), total),
+        items,
+        total,
+        offset: params.offset,
+        limit,
+    }))
+}
+
+#[instrument(skip(state, auth_user, query))]
+pub async fn routes_for_entities(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(world_id): Path<String>,
+    Json(query): Json<RoutesQuery>,
+) -> Result<Json<Vec<Route>>, AppError> {
+    if query.entity_ids.len() > MAX_ROUTE_QUERY_IDS {
+        return Err(AppError::BadRequest(format!(
+            "At most {MAX_ROUTE_QUERY_IDS} entity ids can be queried at once"
+        )));
+    }
+
+    let world_doc = world_service::authorize_world(&state, &auth_user, &world_id).await?;
+    let world_oid = world_service::world_oid(&world_doc)?;
+
+    let routes = world::get_routes_touching(&state.db, world_oid, &query.entity_ids).await?;
+    Ok(Json(routes))
+}
+
+async fn to_nodes(
+    state: &AppState,
+    world_oid: ObjectId,
+    docs: Vec<EntityDoc>,
+) -> Result<Vec<EntityNode>, AppError> {
+    let ids: Vec<String> = docs.iter().map(|d| d.entity_id.clone()).collect();
+    let counts = world::count_children(&state.db, world_oid, &ids).await?;
+
+    Ok(docs
+        .into_iter()
+        .map(|doc| EntityNode {
+            child_count: counts.get(&doc.entity_id).copied().unwrap_or(0),
+            entity_id: doc.entity_id,
+            parent_entity_id: doc.parent_entity_id,
+            entity: doc.entity,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Uri;
+
+    fn params(query: &str) -> PageParams {
+        Query::<PageParams>::try_from_uri(&Uri::try_from(format!("/children?{query}")).unwrap()).unwrap().0
+    }
+
+    #[test]
+    fn test_page_params_are_bounded() {
+        let default = params("");
+        assert_eq!((default.offset, default.clamped_limit()), (0, DEFAULT_PAGE_SIZE));
+        assert_eq!(params("limit=0").clamped_limit(), 1);
+        assert_eq!(params("limit=100000").clamped_limit(), MAX_PAGE_SIZE);
+        assert_eq!(params("offset=40&limit=20").offset, 40);
+    }
+
+    #[test]
+    fn test_next_offset_stops_at_the_last_page() {
+        assert_eq!(next_offset(0, 100, 250), Some(100));
+        assert_eq!(next_offset(200, 50, 250), None);
+        assert_eq!(next_offset(300, 0, 250), None);
+        assert_eq!(next_offset(0, 0, 0), None);
+    }
+}
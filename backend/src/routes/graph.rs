@@ -0,0 +1,170 @@
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use mongodb::bson::oid::ObjectId;
+use std::sync::Arc;
+use tracing::instrument;
+
+use crate::auth::AuthUser;
+use crate::clones;
+use crate::db::world;
+use crate::error::AppError;
+use crate::graph::{CallGraph, Direction};
+use crate::models::{CallPath, CloneReport, CycleReport, CyclesQuery, GraphQuery, ImpactDirection, ImpactQuery, ImpactResponse, PathQuery, Route, RouteType};
+use crate::services::world_service;
+use crate::state::AppState;
+
+const DEFAULT_IMPACT_DEPTH: u32 = 3;
+const MAX_IMPACT_DEPTH: u32 = 10;
+const MAX_IMPACT_NODES: usize = 2000;
+const DEFAULT_PATH_DEPTH: u32 = 10;
+const MAX_PATH_DEPTH: u32 = 20;
+
+#[instrument(skip(state, auth_user))]
+pub async fn callers(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((world_id, entity_id)): Path<(String, String)>,
+    Query(query): Query<GraphQuery>,
+) -> Result<Json<Vec<Route>>, AppError> {
+    neighbors(&state, &auth_user, &world_id, &entity_id, query.route_types.as_deref(), Direction::Callers).await
+}
+
+#[instrument(skip(state, auth_user))]
+pub async fn callees(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((world_id, entity_id)): Path<(String, String)>,
+    Query(query): Query<GraphQuery>,
+) -> Result<Json<Vec<Route>>, AppError> {
+    neighbors(&state, &auth_user, &world_id, &entity_id, query.route_types.as_deref(), Direction::Callees).await
+}
+
+/// Everything that can reach, or be reached from, an entity within `depth` hops.
+#[instrument(skip(state, auth_user))]
+pub async fn impact(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((world_id, entity_id)): Path<(String, String)>,
+    Query(query): Query<ImpactQuery>,
+) -> Result<Json<ImpactResponse>, AppError> {
+    let world_oid = authorized_oid(&state, &auth_user, &world_id).await?;
+    ensure_entity(&state, world_oid, &entity_id).await?;
+    let depth = query.depth.unwrap_or(DEFAULT_IMPACT_DEPTH).clamp(1, MAX_IMPACT_DEPTH);
+
+    let routes = world::get_routes_of_types(&state.db, world_oid, &route_types(query.route_types.as_deref())?).await?;
+    let graph = CallGraph::new(&routes);
+    let walk = |wanted: bool, direction| {
+        if wanted { graph.impact(&entity_id, direction, depth, MAX_IMPACT_NODES) } else { (Vec::new(), false) }
+    };
+    let (callers, callers_truncated) = walk(query.direction != ImpactDirection::Callees, Direction::Callers);
+    let (callees, callees_truncated) = walk(query.direction != ImpactDirection::Callers, Direction::Callees);
+
+    Ok(Json(ImpactResponse {
+        entity_id: entity_id.clone(),
+        depth,
+        callers,
+        callees,
+        truncated: callers_truncated || callees_truncated,
+    }))
+}
+
+/// The fewest routes leading from `from` to `to`, e.g. how `main` reaches `store_world`.
+#[instrument(skip(state, auth_user))]
+pub async fn shortest_path(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(world_id): Path<String>,
+    Query(query): Query<PathQuery>,
+) -> Result<Json<CallPath>, AppError> {
+    let world_oid = authorized_oid(&state, &auth_user, &world_id).await?;
+    ensure_entity(&state, world_oid, &query.from).await?;
+    ensure_entity(&state, world_oid, &query.to).await?;
+    let max_depth = query.max_depth.unwrap_or(DEFAULT_PATH_DEPTH).clamp(1, MAX_PATH_DEPTH);
+
+    let routes = world::get_routes_of_types(&state.db, world_oid, &route_types(query.route_types.as_deref())?).await?;
+    let path = CallGraph::new(&routes)
+        .shortest_path(&query.from, &query.to, max_depth)
+        .ok_or_else(|| AppError::NotFound(format!("No path from {} to {} within {max_depth} hops", query.from, query.to)))?;
+
+    let mut entity_ids: Vec<String> = path.iter().map(|r| r.from_id.clone()).collect();
+    entity_ids.extend(path.last().map(|r| r.to_id.clone()));
+    Ok(Json(CallPath {
+        from: query.from,
+        to: query.to,
+        entity_ids,
+        routes: path.into_iter().cloned().collect(),
+    }))
+}
+
+/// Import cycles ("cursed loops") of a world, optionally at a single level.
+#[instrument(skip(state, auth_user))]
+pub async fn cycles(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(world_id): Path<String>,
+    Query(query): Query<CyclesQuery>,
+) -> Result<Json<CycleReport>, AppError> {
+    let world_oid = authorized_oid(&state, &auth_user, &world_id).await?;
+
+    let cycles = world::get_cycles(&state.db, world_oid, query.level).await?;
+    Ok(Json(CycleReport { world_id: world_oid.to_hex(), cycle_count: cycles.len() as u32, cycles }))
+}
+
+/// Clone classes of a world ("twin buildings"), largest first.
+#[instrument(skip(state, auth_user))]
+pub async fn clones(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path(world_id): Path<String>,
+) -> Result<Json<CloneReport>, AppError> {
+    let world_oid = authorized_oid(&state, &auth_user, &world_id).await?;
+
+    let routes = world::get_routes_of_types(&state.db, world_oid, &["CodeClone".to_string()]).await?;
+    let mut classes = clones::classes_from_routes(&routes);
+    classes.sort_by_key(|c| std::cmp::Reverse(c.members.len() as u32 * c.tokens));
+    Ok(Json(CloneReport { world_id: world_oid.to_hex(), class_count: classes.len() as u32, classes }))
+}
+
+async fn neighbors(
+    state: &AppState,
+    auth_user: &AuthUser,
+    world_id: &str,
+    entity_id: &str,
+    route_types_param: Option<&str>,
+    direction: Direction,
+) -> Result<Json<Vec<Route>>, AppError> {
+    let world_oid = authorized_oid(state, auth_user, world_id).await?;
+    ensure_entity(state, world_oid, entity_id).await?;
+
+    let routes = world::get_routes_of_types(&state.db, world_oid, &route_types(route_types_param)?).await?;
+    let found = CallGraph::new(&routes).neighbors(entity_id, direction).into_iter().cloned().collect();
+    Ok(Json(found))
+}
+
+async fn authorized_oid(state: &AppState, auth_user: &AuthUser, world_id: &str) -> Result<ObjectId, AppError> {
+    let world_doc = world_service::authorize_world(state, auth_user, world_id).await?;
+    world_service::world_oid(&world_doc)
+}
+
+async fn ensure_entity(state: &AppState, world_oid: ObjectId, entity_id: &str) -> Result<(), AppError> {
+    world::get_entity(&state.db, world_oid, entity_id)
+        .await?
+        .map(|_| ())
+        .ok_or_else(|| AppError::NotFound(format!("Entity {entity_id} not found")))
+}
+
+/// Validate a comma-separated `route_types` parameter, defaulting to calls only.
+fn route_types(param: Option<&str>) -> Result<Vec<String>, AppError> {
+    let Some(param) = param.filter(|p| !p.trim().is_empty()) else {
+        return Ok(vec!["FunctionCall".to_string()]);
+    };
+    param
+        .split(',')
+        .map(str::trim)
+        .map(|name| {
+            serde_json::from_value::<RouteType>(serde_json::Value::String(name.to_string()))
+                .map(|_| name.to_string())
+                .map_err(|_| AppError::BadRequest(format!("Unknown route type {name}")))
+        })
+        .collect()
+}
@@ -0,0 +1,66 @@
+pub mod entities;
+pub mod graph;
+pub mod source;
+
+use axum::{
+    extract::{Json, Query},
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::{error, instrument};
+
+use crate::auth::AuthUser;
+use crate::encoding::{self, WorldEncoding};
+use crate::models::{RepoRequest, TimelineRequest};
+use crate::services::{parse_service, timeline_service};
+use crate::state::AppState;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct FormatParams {
+    pub format: Option<WorldEncoding>,
+}
+
+#[instrument(skip(state, auth_user, headers))]
+pub async fn parse_repo_handler(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    auth_user: AuthUser,
+    headers: HeaderMap,
+    Query(params): Query<FormatParams>,
+    Json(payload): Json<RepoRequest>,
+) -> impl IntoResponse {
+    let accept = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok());
+    let world_encoding = WorldEncoding::negotiate(params.format, accept);
+
+    match parse_service::parse_repository(&state, &auth_user, &payload.url).await {
+        Ok(result) => match encoding::encode_world(&result, world_encoding) {
+            Ok(body) => (
+                StatusCode::OK,
+                [
+                    (header::CONTENT_TYPE, world_encoding.content_type()),
+                    (header::VARY, "Accept, Accept-Encoding"),
+                ],
+                body,
+            )
+                .into_response(),
+            Err(e) => {
+                error!("World serialization failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+            }
+        },
+        Err(app_err) => app_err.into_response(),
+    }
+}
+
+#[instrument(skip(state, auth_user))]
+pub async fn timeline_handler(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Json(payload): Json<TimelineRequest>,
+) -> impl IntoResponse {
+    match timeline_service::build_timeline(&state, &auth_user, payload).await {
+        Ok(timeline) => (StatusCode::OK, Json(timeline)).into_response(),
+        Err(app_err) => app_err.into_response(),
+    }
+}
@@ -0,0 +1,49 @@
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::instrument;
+
+use crate::auth::AuthUser;
+use crate::db::{sources as source_store, world};
+use crate::error::AppError;
+use crate::services::world_service;
+use crate::sources::{self, Snippet};
+use crate::state::AppState;
+
+#[derive(Deserialize, Debug)]
+pub struct SourceParams {
+    /// Extra lines to include before and after the entity.
+    #[serde(default = "default_context")]
+    pub context: u32,
+}
+
+fn default_context() -> u32 {
+    3
+}
+
+#[instrument(skip(state, auth_user))]
+pub async fn entity_source(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    Path((world_id, entity_id)): Path<(String, String)>,
+    Query(params): Query<SourceParams>,
+) -> Result<Json<Snippet>, AppError> {
+    let world_doc = world_service::authorize_world(&state, &auth_user, &world_id).await?;
+    let world_oid = world_service::world_oid(&world_doc)?;
+
+    let entity = world::get_entity(&state.db, world_oid, &entity_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Entity {entity_id} not found")))?;
+
+    let span = entity
+        .span
+        .or_else(|| entity.entity.span().cloned())
+        .ok_or_else(|| AppError::NotFound(format!("Entity {entity_id} has no source span")))?;
+
+    let (content, language) = source_store::get_source(&state.db, world_oid, &span.file)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Source for {} is not available", span.file)))?;
+
+    Ok(Json(sources::snippet(&content, &span, &language, params.context)))
+}
@@ -0,0 +1,238 @@
+//! Architecture rules: layering constraints a repository declares in
+//! `.nilsbohr/rules.toml`, checked against its dependency routes.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::{debug, warn};
+
+use crate::models::{GameEntity, Route, RouteType, RuleViolation};
+
+/// Where a repository keeps its architecture rules.
+pub const RULES_PATH: &str = ".nilsbohr/rules.toml";
+
+/// Architecture rules read from [`RULES_PATH`]:
+///
+/// ```toml
+/// [[rule]]
+/// name = "db-not-routes"
+/// from = ["src/db/**"]
+/// forbid = ["src/routes/**"]
+/// reason = "Storage must not know about HTTP"
+///
+/// [[rule]]
+/// name = "pure-domain"
+/// from = ["district_src_domain"]
+/// allow = ["src/models.rs", "src/error.rs"]
+/// ```
+///
+/// Patterns are path globs (`*`, `?` and `**`) matched against the file an
+/// entity lives in, or District ids matching everything inside that District.
+/// With `forbid`, dependencies on matching entities are violations; with
+/// `allow`, dependencies on anything else outside `from` are.
+#[derive(Deserialize, Debug, Default)]
+pub struct RuleSet {
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Rule {
+    pub name: String,
+    pub from: Vec<String>,
+    #[serde(default)]
+    pub forbid: Vec<String>,
+    #[serde(default)]
+    pub allow: Vec<String>,
+    pub reason: Option<String>,
+}
+
+/// Load the repository's rules; `Ok(None)` when it has no rules file.
+pub fn load(repo_root: &Path) -> Result<Option<RuleSet>, String> {
+    let path = repo_root.join(RULES_PATH);
+    let Ok(text) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let rule_set: RuleSet = toml::from_str(&text).map_err(|e| format!("{RULES_PATH}: {e}"))?;
+    for rule in &rule_set.rules {
+        if rule.forbid.is_empty() == rule.allow.is_empty() {
+            return Err(format!("{RULES_PATH}: rule `{}` needs exactly one of `forbid` or `allow`", rule.name));
+        }
+    }
+    Ok(Some(rule_set))
+}
+
+/// Where an entity sits, for pattern matching.
+#[derive(Default, Clone)]
+struct Location {
+    /// The file it lives in, or the directory of a District
+    path: String,
+    /// Enclosing District ids, outermost first
+    districts: Vec<String>,
+    in_file: bool,
+}
+
+impl Location {
+    fn matches(&self, pattern: &str) -> bool {
+        if pattern.starts_with("district_") {
+            self.districts.iter().any(|d| d == pattern)
+        } else {
+            glob_matches(pattern, &self.path)
+        }
+    }
+
+    fn matches_any(&self, patterns: &[String]) -> bool {
+        patterns.iter().any(|p| self.matches(p))
+    }
+}
+
+/// Check Import and FunctionCall routes against the rules, recording each
+/// broken rule under `violations` in the route's metadata.
+pub fn apply(rule_set: &RuleSet, cities: &[GameEntity], routes: &mut [Route]) {
+    let mut locations = HashMap::new();
+    for city in cities {
+        index_locations(city, &Location::default(), &mut locations);
+    }
+
+    let mut count = 0;
+    for route in routes.iter_mut().filter(|r| matches!(r.route_type, RouteType::Import | RouteType::FunctionCall)) {
+        let (Some(from), Some(to)) = (locations.get(&route.from_id), locations.get(&route.to_id)) else { continue };
+        let broken: Vec<Value> = rule_set
+            .rules
+            .iter()
+            .filter(|rule| from.matches_any(&rule.from) && !to.matches_any(&rule.from))
+            .filter(|rule| if rule.allow.is_empty() { to.matches_any(&rule.forbid) } else { !to.matches_any(&rule.allow) })
+            .map(|rule| json!({ "rule": rule.name, "reason": rule.reason }))
+            .collect();
+        if broken.is_empty() {
+            continue;
+        }
+        count += broken.len();
+        match route.metadata.get_or_insert_with(|| json!({})) {
+            Value::Object(map) => {
+                map.insert("violations".into(), Value::Array(broken));
+            }
+            other => warn!(route = %route.id, "Route metadata is not an object: {other}"),
+        }
+    }
+    debug!(violations = count, "Checked architecture rules");
+}
+
+fn index_locations(entity: &GameEntity, parent: &Location, out: &mut HashMap<String, Location>) {
+    let mut location = parent.clone();
+    let (id, children) = match entity {
+        GameEntity::City { id, children, .. } => (id, children.as_slice()),
+        GameEntity::District { id, path, children, .. } => {
+            // Namespaces are Districts inside a file and keep its path
+            if !parent.in_file {
+                location.path = path.clone();
+                location.districts.push(id.clone());
+            }
+            (id, children.as_slice())
+        }
+        GameEntity::Building { id, building_type, children, .. } => {
+            if building_type == "file" {
+                location.path = id.clone();
+                location.in_file = true;
+            }
+            (id, children.as_slice())
+        }
+        GameEntity::Room { id, children, .. } => (id, children.as_slice()),
+        GameEntity::Artifact { id, .. } => (id, &[][..]),
+    };
+    for child in children {
+        index_locations(child, &location, out);
+    }
+    out.insert(id.clone(), location);
+}
+
+/// Glob over `/`-separated paths: `*` and `?` stay within a segment, `**`
+/// spans any number of segments (including none).
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match_segments(&pattern, &path)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_segments(rest, &path[skip..])),
+        Some((segment, rest)) => {
+            path.split_first().is_some_and(|(first, tail)| match_segment(segment.as_bytes(), first.as_bytes()) && match_segments(rest, tail))
+        }
+    }
+}
+
+fn match_segment(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| match_segment(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && match_segment(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && match_segment(rest, &text[1..]),
+    }
+}
+
+/// Violations recorded on `routes` by [`apply`], one per broken rule.
+pub fn violations(routes: &[Route]) -> Vec<RuleViolation> {
+    routes
+        .iter()
+        .flat_map(|route| {
+            let broken = route.metadata.as_ref().and_then(|m| m.get("violations")).and_then(Value::as_array);
+            broken.into_iter().flatten().map(move |v| RuleViolation {
+                rule: v.get("rule").and_then(Value::as_str).unwrap_or_default().to_string(),
+                reason: v.get("reason").and_then(Value::as_str).map(str::to_string),
+                route_id: route.id.clone(),
+                route_type: route.route_type.clone(),
+                from_id: route.from_id.clone(),
+                to_id: route.to_id.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::file_with_functions;
+
+    fn route(id: &str, from: &str, to: &str, route_type: RouteType) -> Route {
+        Route { id: id.into(), from_id: from.into(), to_id: to.into(), route_type, bidirectional: false, metadata: None }
+    }
+
+    #[test]
+    fn test_forbid_and_allow_rules() {
+        assert!(glob_matches("src/**/*.rs", "src/db/world.rs"));
+        assert!(glob_matches("src/db/**", "src/db"));
+        assert!(!glob_matches("src/*.rs", "src/db/world.rs"));
+
+        let rule_set: RuleSet = toml::from_str(
+            "[[rule]]\nname = \"db-not-routes\"\nfrom = [\"src/db/**\"]\nforbid = [\"src/routes/**\"]\nreason = \"layering\"\n\n\
+             [[rule]]\nname = \"pure-domain\"\nfrom = [\"district_src_domain\"]\nallow = [\"src/models.rs\"]\n",
+        )
+        .unwrap();
+        let district = |id: &str, path: &str, children| GameEntity::District { id: id.into(), name: path.into(), path: path.into(), children, metadata: None };
+        let cities = vec![district("district_src", "src", vec![
+            file_with_functions("src/models.rs", &[]),
+            district("district_src_db", "src/db", vec![file_with_functions("src/db/world.rs", &["store"])]),
+            district("district_src_routes", "src/routes", vec![file_with_functions("src/routes/entities.rs", &["entity"])]),
+            district("district_src_domain", "src/domain", vec![file_with_functions("src/domain/tree.rs", &["flatten"]), file_with_functions("src/domain/mod.rs", &[])]),
+        ])];
+        let mut routes = vec![
+            route("r0", "src/db/world.rs::store", "src/routes/entities.rs::entity", RouteType::FunctionCall),
+            route("r1", "src/routes/entities.rs", "src/db/world.rs", RouteType::Import),
+            route("r2", "src/domain/tree.rs", "src/models.rs", RouteType::Import),
+            route("r3", "src/domain/tree.rs", "src/domain/mod.rs", RouteType::Import),
+            route("r4", "src/domain/tree.rs::flatten", "src/db/world.rs::store", RouteType::FunctionCall),
+        ];
+
+        apply(&rule_set, &cities, &mut routes);
+        let found = violations(&routes);
+        let summary: Vec<(&str, &str)> = found.iter().map(|v| (v.route_id.as_str(), v.rule.as_str())).collect();
+        assert_eq!(summary, [("r0", "db-not-routes"), ("r4", "pure-domain")]);
+        assert_eq!(found[0].reason.as_deref(), Some("layering"));
+    }
+}
@@ -54,7 +54,7 @@ pub async fn handle_callback(
     let now = Utc::now().to_rfc3339();
     let existing = auth_redis::get_user(&state.redis, gh_user.id)
         .await
-        .map_err(|e| AppError::Internal(e))?
+        .map_err(AppError::Internal)?
         .unwrap_or_else(|| User {
             github_id: gh_user.id,
             username: gh_user.login.clone(),
@@ -208,7 +208,7 @@ pub async fn get_user_profile(
 ) -> Result<Option<User>, AppError> {
     auth_redis::get_user(redis, auth_user.github_id)
         .await
-        .map_err(|e| AppError::Internal(e))
+        .map_err(AppError::Internal)
 }
 
 pub async fn delete_user_session(
@@ -217,7 +217,7 @@ pub async fn delete_user_session(
 ) -> Result<(), AppError> {
     auth_redis::delete_session(redis, session_id)
         .await
-        .map_err(|e| AppError::Internal(e))
+        .map_err(AppError::Internal)
 }
 
 pub async fn list_user_repos(
@@ -226,7 +226,7 @@ pub async fn list_user_repos(
 ) -> Result<Vec<serde_json::Value>, AppError> {
     let gh_token = auth_redis::get_github_token(&state.redis, auth_user.github_id)
         .await
-        .map_err(|e| AppError::Internal(e))?
+        .map_err(AppError::Internal)?
         .ok_or_else(|| AppError::Unauthorized("GitHub token expired — please re-login".into()))?;
 
     github_service::fetch_user_repos(
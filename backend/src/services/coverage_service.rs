@@ -0,0 +1,57 @@
+use tracing::{info, instrument};
+
+use crate::auth::AuthUser;
+use crate::coverage::{self, CoverageReport};
+use crate::db::world;
+use crate::error::AppError;
+use crate::models::{CoverageQuery, CoverageSummary};
+use crate::services::world_service;
+use crate::state::AppState;
+
+/// Maps a coverage report onto a stored world and saves line and branch
+/// coverage into its entities' metadata, replacing any earlier import.
+/// Requires push access, since the upload changes what every viewer sees.
+#[instrument(skip(state, auth_user, report))]
+pub async fn import_coverage(
+    state: &AppState,
+    auth_user: &AuthUser,
+    world_id: &str,
+    query: CoverageQuery,
+    report: &str,
+) -> Result<CoverageSummary, AppError> {
+    let world_doc = world_service::authorize_world_push(state, auth_user, world_id).await?;
+    let world_oid = world_service::world_oid(&world_doc)?;
+
+    // Short hashes are fine, as long as they identify the world's commit.
+    if let Some(commit) = query.commit.as_deref()
+        && !(commit.len() >= 7 && world_doc.commit_hash.starts_with(commit))
+    {
+        return Err(AppError::BadRequest(format!(
+            "Coverage report is for commit {commit} but the world was parsed at {}",
+            world_doc.commit_hash
+        )));
+    }
+
+    let (format, report) = CoverageReport::parse(report, query.format).map_err(AppError::BadRequest)?;
+
+    let mut cities = world::get_entity_tree(&state.db, world_oid).await?;
+    let (files, unmatched_files) = coverage::match_files(&report, &coverage::file_ids(&cities));
+    if files.is_empty() {
+        return Err(AppError::BadRequest("No file in the coverage report matches a file in this world".into()));
+    }
+    let total = coverage::annotate(&mut cities, &files);
+    let entities = coverage::collect(&cities);
+    world::store_coverage(&state.db, world_oid, &entities).await?;
+
+    info!(world_id = %world_oid, files = files.len(), unmatched = unmatched_files.len(), "Imported {format:?} coverage");
+    Ok(CoverageSummary {
+        world_id: world_oid.to_hex(),
+        commit_hash: world_doc.commit_hash.clone(),
+        format,
+        files_matched: files.len() as u32,
+        unmatched_files,
+        entities_updated: entities.len() as u32,
+        line_coverage: total.line_coverage(),
+        branch_coverage: total.branch_coverage(),
+    })
+}
@@ -10,6 +10,32 @@ pub async fn fetch_repo_metadata(
     repo_name: &str,
     github_token: Option<&str>,
 ) -> Result<(GitHubRepoMetadata, String), AppError> {
+    let repo_info = fetch_repo(http_client, owner, repo_name, github_token).await?;
+    let default_branch = repo_info.default_branch.unwrap_or_else(|| "main".to_string());
+
+    let metadata = GitHubRepoMetadata {
+        description: repo_info.description,
+        stars: repo_info.stargazers_count,
+        language: repo_info.language,
+        topics: repo_info.topics,
+        fork: repo_info.fork,
+        archived: repo_info.archived,
+        pushed_at: repo_info.pushed_at,
+    };
+
+    info!("Fetched GitHub metadata for {owner}/{repo_name}");
+    Ok((metadata, default_branch))
+}
+
+/// `GET /repos/{owner}/{repo}`, including the user's permissions when a token is given.
+/// GitHub answers 404 (or 403 without a rate limit) when the repository does not
+/// exist or the token cannot see it; that is `NotFound`, any other failure `ExternalApi`.
+pub async fn fetch_repo(
+    http_client: &reqwest::Client,
+    owner: &str,
+    repo_name: &str,
+    github_token: Option<&str>,
+) -> Result<GitHubRepoResponse, AppError> {
     let url = format!("{GITHUB_API_BASE}/repos/{owner}/{repo_name}");
 
     let mut request = http_client
@@ -26,33 +52,23 @@ pub async fn fetch_repo_metadata(
         .await
         .map_err(|e| AppError::ExternalApi(format!("GitHub API request failed: {e}")))?;
 
-    if !response.status().is_success() {
+    let status = response.status();
+    let rate_limited = response.headers().get("x-ratelimit-remaining").is_some_and(|v| v == "0");
+    if status == reqwest::StatusCode::NOT_FOUND || (status == reqwest::StatusCode::FORBIDDEN && !rate_limited) {
+        return Err(AppError::NotFound(format!("GitHub repository {owner}/{repo_name} not found")));
+    }
+    if !status.is_success() {
         return Err(AppError::ExternalApi(format!(
             "GitHub API returned {}: {}",
-            response.status(),
+            status,
             response.text().await.unwrap_or_default()
         )));
     }
 
-    let repo_info: GitHubRepoResponse = response
+    response
         .json()
         .await
-        .map_err(|e| AppError::ExternalApi(format!("Failed to parse GitHub response: {e}")))?;
-
-    let default_branch = repo_info.default_branch.unwrap_or_else(|| "main".to_string());
-
-    let metadata = GitHubRepoMetadata {
-        description: repo_info.description,
-        stars: repo_info.stargazers_count,
-        language: repo_info.language,
-        topics: repo_info.topics,
-        fork: repo_info.fork,
-        archived: repo_info.archived,
-        pushed_at: repo_info.pushed_at,
-    };
-
-    info!("Fetched GitHub metadata for {owner}/{repo_name}");
-    Ok((metadata, default_branch))
+        .map_err(|e| AppError::ExternalApi(format!("Failed to parse GitHub response: {e}")))
 }
 
 pub async fn fetch_latest_commit_hash(
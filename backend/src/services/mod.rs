@@ -1,3 +1,6 @@
 pub mod auth_service;
+pub mod coverage_service;
 pub mod github_service;
 pub mod parse_service;
+pub mod timeline_service;
+pub mod world_service;
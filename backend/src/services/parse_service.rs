@@ -1,14 +1,16 @@
 use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
 use std::path::Path;
 use tokio::task;
 use tracing::{error, info, instrument, warn};
 
 use crate::auth::AuthUser;
-use crate::db::{repository, world};
+use crate::db::{repository, sources as source_store, world};
 use crate::error::AppError;
 use crate::models::{WorldResponse, WorldSeed};
 use crate::parser::generate_world;
 use crate::services::github_service;
+use crate::sources;
 use crate::state::AppState;
 
 #[instrument(skip(state, auth_user))]
@@ -95,10 +97,11 @@ pub async fn parse_repository(
     {
         info!(commit = %latest_commit_hash, "Checking for cached world");
         match world::get_cached_world(&state.db, repo_id, &latest_commit_hash).await {
-            Ok(Some(seed)) => {
+            Ok(Some((world_id, seed))) => {
                 info!("Cache hit for {owner}/{repo_name}@{latest_commit_hash}");
                 return Ok(WorldResponse {
                     project_name,
+                    world_id: Some(world_id.to_hex()),
                     generated_at: Utc::now().to_rfc3339(),
                     seed,
                 });
@@ -143,14 +146,19 @@ pub async fn parse_repository(
 
     info!("Starting AST traversal for {owner}/{repo_name}");
 
-    let world_seed =
-        match task::spawn_blocking(move || generate_world(&repo_path)).await {
-            Ok(seed) => seed,
-            Err(e) => {
-                error!("Parsing task failed: {e}");
-                return Err(AppError::Parse(format!("Parsing task failed: {e}")));
-            }
-        };
+    let (world_seed, source_files) = match task::spawn_blocking(move || {
+        let seed = generate_world(&repo_path);
+        let source_files = sources::collect_sources(&repo_path, &seed);
+        (seed, source_files)
+    })
+    .await
+    {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            error!("Parsing task failed: {e}");
+            return Err(AppError::Parse(format!("Parsing task failed: {e}")));
+        }
+    };
 
     info!(
         cities = world_seed.world_meta.total_cities,
@@ -159,39 +167,35 @@ pub async fn parse_repository(
         "Parsing complete for {owner}/{repo_name}"
     );
 
+    // Stored before responding: clients fetch entities, sources and reports
+    // by `world_id` straight away.
+    let mut world_id = None;
     if !latest_commit_hash.is_empty() {
-        let hash = latest_commit_hash.clone();
-        let seed = world_seed.clone();
-        let db = state.db.clone();
-        let repo_id_clone = repo_id;
-        let metadata_clone = gh_metadata.clone();
-        let branch_clone = default_branch.clone();
-
-        tokio::spawn(async move {
-            let total_loc = calculate_total_loc(&seed);
-            match world::store_world(&db, repo_id_clone, &hash, &seed, total_loc).await {
-                Ok(_) => info!("Stored parsed world in MongoDB for {hash}"),
-                Err(e) => warn!("Failed to store world in MongoDB: {e}"),
+        let id = ObjectId::new();
+        let total_loc = calculate_total_loc(&world_seed);
+        match world::store_world(&state.db, id, repo_id, &latest_commit_hash, &world_seed, total_loc).await {
+            Ok(_) => {
+                info!("Stored parsed world in MongoDB for {latest_commit_hash}");
+                world_id = Some(id);
+                if let Err(e) = source_store::store_sources(&state.db, id, source_files).await {
+                    warn!("Failed to store sources for {latest_commit_hash}: {e}");
+                }
             }
+            Err(e) => warn!("Failed to store world in MongoDB: {e}"),
+        }
 
-            if let Err(e) = repository::update_repo_after_parse(
-                &db,
-                repo_id_clone,
-                &hash,
-                &branch_clone,
-                metadata_clone,
-            )
-            .await
-            {
-                warn!("Failed to update repo after parse: {e}");
-            }
-        });
+        if let Err(e) =
+            repository::update_repo_after_parse(&state.db, repo_id, &latest_commit_hash, &default_branch, gh_metadata).await
+        {
+            warn!("Failed to update repo after parse: {e}");
+        }
     }
 
     drop(temp_dir);
 
     Ok(WorldResponse {
         project_name,
+        world_id: world_id.map(|id| id.to_hex()),
         generated_at: Utc::now().to_rfc3339(),
         seed: world_seed,
     })
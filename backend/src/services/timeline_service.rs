@@ -0,0 +1,167 @@
+use chrono::{TimeZone, Utc};
+use mongodb::bson::oid::ObjectId;
+use std::path::{Path, PathBuf};
+use tokio::task;
+use tracing::{error, info, instrument, warn};
+
+use crate::auth::AuthUser;
+use crate::db::{repository, sources as source_store, world};
+use crate::error::AppError;
+use crate::git_layer::{CommitSample, GitLayer};
+use crate::models::{
+    CityFrame, DistrictFrame, GameEntity, TimelineFrame, TimelineRequest, TimelineResponse,
+    WorldSeed,
+};
+use crate::parser::generate_world;
+use crate::services::github_service;
+use crate::sources;
+use crate::state::AppState;
+
+const MAX_TIMELINE_SAMPLES: usize = 50;
+
+#[instrument(skip(state, auth_user, request), fields(url = %request.url))]
+pub async fn build_timeline(
+    state: &AppState,
+    auth_user: &AuthUser,
+    request: TimelineRequest,
+) -> Result<TimelineResponse, AppError> {
+    let repo_url = request.url.as_str();
+    let project_name = repo_url
+        .split('/')
+        .next_back()
+        .unwrap_or("project")
+        .replace(".git", "");
+
+    let (owner, repo_name) = github_service::parse_github_url(repo_url)
+        .ok_or_else(|| AppError::Git("Invalid GitHub URL".into()))?;
+
+    let repo_doc = repository::find_or_create_repo(
+        &state.db,
+        repo_url,
+        &project_name,
+        &owner,
+        &repo_name,
+        &auth_user.github_id.to_string(),
+    )
+    .await?;
+    let repo_id = repo_doc.id.ok_or_else(|| AppError::Internal("Repository document missing ID".into()))?;
+
+    let temp_dir = tempfile::tempdir()
+        .map_err(|e| AppError::Internal(format!("Failed to create temp directory: {e}")))?;
+    let clone_path = temp_dir.path().join("repo");
+
+    info!("Cloning full history of {owner}/{repo_name} for timeline");
+    let clone_url = repo_url.to_string();
+    let strategy = request.strategy.clone();
+    let max_samples = request.max_samples.clamp(1, MAX_TIMELINE_SAMPLES);
+    let path = clone_path.clone();
+    let samples = task::spawn_blocking(move || -> Result<Vec<CommitSample>, git2::Error> {
+        GitLayer::full_clone(&clone_url, &path)?;
+        GitLayer::new(&path).sample_commits(&strategy, max_samples)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Git clone task failed: {e}")))?
+    .map_err(|e| {
+        error!("Timeline clone failed: {e}");
+        AppError::Git(format!("Git clone failed: {e}"))
+    })?;
+
+    info!(samples = samples.len(), "Sampled commits for timeline");
+
+    let mut frames = Vec::with_capacity(samples.len());
+    for sample in samples {
+        let seed = world_for_commit(state, repo_id, &clone_path, &sample.hash).await?;
+        frames.push(build_frame(&sample, &seed));
+    }
+
+    drop(temp_dir);
+
+    Ok(TimelineResponse {
+        project_name,
+        generated_at: Utc::now().to_rfc3339(),
+        frames,
+    })
+}
+
+/// Returns the cached world for `hash`, or checks the commit out, parses it
+/// and caches the result so later timelines only parse new samples.
+async fn world_for_commit(
+    state: &AppState,
+    repo_id: ObjectId,
+    repo_path: &Path,
+    hash: &str,
+) -> Result<WorldSeed, AppError> {
+    match world::get_cached_world(&state.db, repo_id, hash).await {
+        Ok(Some((_, seed))) => return Ok(seed),
+        Ok(None) => {}
+        Err(e) => warn!("Error checking cache for {hash}: {e}, will parse"),
+    }
+
+    let path: PathBuf = repo_path.to_path_buf();
+    let commit = hash.to_string();
+    let (seed, source_files) = task::spawn_blocking(move || -> Result<_, git2::Error> {
+        GitLayer::new(&path).checkout_commit(&commit)?;
+        let seed = generate_world(&path);
+        let source_files = sources::collect_sources(&path, &seed);
+        Ok((seed, source_files))
+    })
+    .await
+    .map_err(|e| AppError::Parse(format!("Parsing task failed: {e}")))?
+    .map_err(|e| AppError::Git(format!("Checkout of {hash} failed: {e}")))?;
+
+    let total_loc = seed.cities.iter().map(|c| c.count_entities().3).sum();
+    let world_id = ObjectId::new();
+    match world::store_world(&state.db, world_id, repo_id, hash, &seed, total_loc).await {
+        Ok(_) => {
+            if let Err(e) = source_store::store_sources(&state.db, world_id, source_files).await {
+                warn!("Failed to store sources for {hash}: {e}");
+            }
+        }
+        Err(e) => warn!("Failed to store timeline world for {hash}: {e}"),
+    }
+
+    Ok(seed)
+}
+
+fn build_frame(sample: &CommitSample, seed: &WorldSeed) -> TimelineFrame {
+    let mut cities = Vec::new();
+    let mut districts = Vec::new();
+
+    for city in &seed.cities {
+        if let GameEntity::City { id, language, stats, children, .. } = city {
+            cities.push(CityFrame { id: id.clone(), language: language.clone(), loc: stats.loc });
+            collect_district_frames(children, id, &mut districts);
+        }
+    }
+
+    TimelineFrame {
+        commit_hash: sample.hash.clone(),
+        committed_at: Utc
+            .timestamp_opt(sample.committed_at, 0)
+            .single()
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default(),
+        label: sample.label.clone(),
+        world_meta: seed.world_meta.clone(),
+        cities,
+        districts,
+    }
+}
+
+fn collect_district_frames(children: &[GameEntity], city_id: &str, out: &mut Vec<DistrictFrame>) {
+    for child in children {
+        if let GameEntity::District { id, path, children, .. } = child {
+            let (building_count, room_count, artifact_count, loc) = child.count_entities();
+            out.push(DistrictFrame {
+                id: id.clone(),
+                city_id: city_id.to_string(),
+                path: path.clone(),
+                building_count,
+                room_count,
+                artifact_count,
+                loc,
+            });
+            collect_district_frames(children, city_id, out);
+        }
+    }
+}
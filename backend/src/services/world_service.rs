@@ -0,0 +1,124 @@
+use mongodb::bson::oid::ObjectId;
+use tracing::{instrument, warn};
+
+use crate::auth::AuthUser;
+use crate::auth::redis;
+use crate::db::models::ParsedWorldDoc;
+use crate::db::{repository, world};
+use crate::error::AppError;
+use crate::services::github_service;
+use crate::state::AppState;
+
+/// What a user may do with a repository, as far as GitHub tells us.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Access {
+    None,
+    Read,
+    Push,
+}
+
+impl Access {
+    /// Value cached in Redis.
+    fn as_str(self) -> &'static str {
+        match self {
+            Access::None => "none",
+            Access::Read => "read",
+            Access::Push => "push",
+        }
+    }
+
+    fn from_cached(value: &str) -> Self {
+        match value {
+            "push" => Access::Push,
+            "read" => Access::Read,
+            _ => Access::None,
+        }
+    }
+}
+
+/// Loads a stored world, checking that `auth_user` can see the repository it
+/// was parsed from. GitHub only answers `/repos/{owner}/{repo}` for private
+/// repositories when the token has access, so a successful lookup is the check.
+/// The answer is cached in Redis for a few minutes so that a game session's
+/// stream of lazy-load requests does not hit GitHub's rate limit.
+/// Missing and forbidden worlds both report "not found" to avoid leaking which
+/// private repositories have been parsed.
+#[instrument(skip(state, auth_user))]
+pub async fn authorize_world(
+    state: &AppState,
+    auth_user: &AuthUser,
+    world_id: &str,
+) -> Result<ParsedWorldDoc, AppError> {
+    authorize(state, auth_user, world_id, Access::Read).await
+}
+
+/// Like `authorize_world`, but for writes to a world (e.g. uploading
+/// coverage): the user needs push permission on the repository.
+#[instrument(skip(state, auth_user))]
+pub async fn authorize_world_push(
+    state: &AppState,
+    auth_user: &AuthUser,
+    world_id: &str,
+) -> Result<ParsedWorldDoc, AppError> {
+    authorize(state, auth_user, world_id, Access::Push).await
+}
+
+async fn authorize(
+    state: &AppState,
+    auth_user: &AuthUser,
+    world_id: &str,
+    required: Access,
+) -> Result<ParsedWorldDoc, AppError> {
+    let not_found = || AppError::NotFound("World not found".into());
+
+    let world_oid = ObjectId::parse_str(world_id).map_err(|_| not_found())?;
+    let world_doc = world::get_world_doc(&state.db, world_oid).await?.ok_or_else(not_found)?;
+
+    let access = repo_access(state, auth_user, world_doc.repository_id).await?;
+    if access < Access::Read {
+        warn!(user = %auth_user.username, "Denied access to world {world_id}");
+        return Err(not_found());
+    }
+    if access < required {
+        warn!(user = %auth_user.username, "Denied write to world {world_id}");
+        return Err(AppError::Unauthorized("Push access to the repository is required".into()));
+    }
+    Ok(world_doc)
+}
+
+/// What `auth_user` may do with a repository, from the cache or GitHub. Only
+/// successful checks are cached; GitHub failures other than a denial are
+/// returned as `ExternalApi` errors.
+async fn repo_access(state: &AppState, auth_user: &AuthUser, repository_id: ObjectId) -> Result<Access, AppError> {
+    let repository_key = repository_id.to_hex();
+    match redis::get_repo_access(&state.redis, auth_user.github_id, &repository_key).await {
+        Ok(Some(access)) => return Ok(Access::from_cached(&access)),
+        Ok(None) => {}
+        Err(e) => warn!("Repository access cache unavailable: {e}"),
+    }
+
+    let Some(repo_doc) = repository::find_repo_by_id(&state.db, repository_id).await? else {
+        return Ok(Access::None);
+    };
+    let gh_token = redis::get_github_token(&state.redis, auth_user.github_id).await.unwrap_or(None);
+    let repo_info =
+        match github_service::fetch_repo(&state.http, &repo_doc.owner, &repo_doc.repo_name, gh_token.as_deref()).await {
+            Ok(repo_info) => repo_info,
+            Err(AppError::NotFound(e)) => {
+                warn!(user = %auth_user.username, "GitHub denied {}/{}: {e}", repo_doc.owner, repo_doc.repo_name);
+                return Ok(Access::None);
+            }
+            // A rate limit or outage is not a denial: surface it instead of reporting a missing world
+            Err(e) => return Err(e),
+        };
+
+    let access = if repo_info.permissions.is_some_and(|p| p.push) { Access::Push } else { Access::Read };
+    if let Err(e) = redis::store_repo_access(&state.redis, auth_user.github_id, &repository_key, access.as_str()).await {
+        warn!("Failed to cache repository access: {e}");
+    }
+    Ok(access)
+}
+
+pub fn world_oid(world_doc: &ParsedWorldDoc) -> Result<ObjectId, AppError> {
+    world_doc.id.ok_or_else(|| AppError::Internal("world doc has no id".into()))
+}
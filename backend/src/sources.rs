@@ -0,0 +1,148 @@
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::Serialize;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::models::{GameEntity, SourceSpan, WorldSeed};
+
+/// Files larger than this are not archived; their entities simply have no snippet.
+const MAX_SOURCE_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Upper bound on the context window a client can request around a span.
+pub const MAX_CONTEXT_LINES: u32 = 50;
+
+/// A gzip-compressed copy of one parsed file, stored alongside its world.
+pub struct SourceFile {
+    pub path: String,
+    pub language: String,
+    pub compressed: Vec<u8>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Snippet {
+    pub file: String,
+    pub language: String,
+    /// First and last line of `code` (1-based, inclusive).
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Lines belonging to the entity itself, within `code`.
+    pub highlight_start_line: u32,
+    pub highlight_end_line: u32,
+    pub code: String,
+}
+
+/// Reads and compresses every file Building in `seed` from the checkout at `root`.
+pub fn collect_sources(root: &Path, seed: &WorldSeed) -> Vec<SourceFile> {
+    let mut paths = Vec::new();
+    for city in &seed.cities {
+        collect_file_ids(city, &mut paths);
+    }
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let full_path = root.join(&path);
+            let size = fs::metadata(&full_path).ok()?.len();
+            if size > MAX_SOURCE_BYTES {
+                return None;
+            }
+            let content = fs::read_to_string(&full_path).ok()?;
+            Some(SourceFile {
+                language: highlight_language(&path).to_string(),
+                compressed: compress(&content).ok()?,
+                path,
+            })
+        })
+        .collect()
+}
+
+fn collect_file_ids(entity: &GameEntity, out: &mut Vec<String>) {
+    match entity {
+        GameEntity::Building { id, building_type, .. } if building_type == "file" => {
+            out.push(id.clone());
+        }
+        GameEntity::City { children, .. } | GameEntity::District { children, .. } => {
+            for child in children {
+                collect_file_ids(child, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn compress(content: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content.as_bytes())?;
+    encoder.finish()
+}
+
+pub fn decompress(bytes: &[u8]) -> std::io::Result<String> {
+    let mut content = String::new();
+    GzDecoder::new(bytes).read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Extracts the lines of `span` plus up to `context` lines either side.
+pub fn snippet(content: &str, span: &SourceSpan, language: &str, context: u32) -> Snippet {
+    let context = context.min(MAX_CONTEXT_LINES);
+    let lines: Vec<&str> = content.lines().collect();
+    let total = lines.len().max(1) as u32;
+
+    let highlight_start_line = span.start_line.clamp(1, total);
+    let highlight_end_line = span.end_line.clamp(highlight_start_line, total);
+    let start_line = highlight_start_line.saturating_sub(context).max(1);
+    let end_line = (highlight_end_line + context).min(total);
+
+    let code = lines
+        .get((start_line - 1) as usize..end_line as usize)
+        .map(|l| l.join("\n"))
+        .unwrap_or_default();
+
+    Snippet {
+        file: span.file.clone(),
+        language: language.to_string(),
+        start_line,
+        end_line,
+        highlight_start_line,
+        highlight_end_line,
+        code,
+    }
+}
+
+/// Language tag understood by common syntax highlighters.
+pub fn highlight_language(path: &str) -> &'static str {
+    match path.rsplit('.').next().unwrap_or_default() {
+        "rs" => "rust",
+        "ts" => "typescript",
+        "tsx" => "tsx",
+        "js" => "javascript",
+        "jsx" => "jsx",
+        "py" => "python",
+        "cpp" | "cc" | "cxx" | "hpp" => "cpp",
+        "c" | "h" => "c",
+        "java" => "java",
+        _ => "plaintext",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_adds_context_within_file_bounds() {
+        let content = "line1\nline2\nfn a() {\n}\nline5";
+        let compressed = compress(content).unwrap();
+        let restored = decompress(&compressed).unwrap();
+        let span = SourceSpan { file: "a.rs".into(), start_line: 3, end_line: 4, ..Default::default() };
+
+        let snippet = snippet(&restored, &span, "rust", 1);
+
+        assert_eq!((snippet.start_line, snippet.end_line), (2, 5));
+        assert_eq!((snippet.highlight_start_line, snippet.highlight_end_line), (3, 4));
+        assert_eq!(snippet.code, "line2\nfn a() {\n}\nline5");
+    }
+}
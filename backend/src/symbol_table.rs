@@ -1,5 +1,40 @@
+use crate::languages::parser_utils::{call_target_name, split_import_alias};
+use crate::languages::paths::{self, dir_of, file_of, normalize_relative};
 use crate::models::GameEntity;
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// How sure a resolution is. Serialized into route metadata.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// A resolved target plus how it was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolution {
+    pub id: String,
+    pub confidence: Confidence,
+    /// e.g. "exact", "enclosing_type", "same_file", "import", "unique_name"
+    pub strategy: &'static str,
+}
+
+impl Resolution {
+    pub fn new(id: &str, confidence: Confidence, strategy: &'static str) -> Self {
+        Self { id: id.to_string(), confidence, strategy }
+    }
+
+    /// `{"confidence": ..., "strategy": ...}` for a Route's metadata.
+    pub fn metadata(&self) -> serde_json::Value {
+        serde_json::json!({ "confidence": self.confidence, "strategy": self.strategy })
+    }
+}
+
+/// Receivers that refer to the enclosing type.
+const SELF_RECEIVERS: &[&str] = &["self", "Self", "this", "cls"];
 
 /// Global symbol table to resolve function calls and imports
 pub struct SymbolTable {
@@ -8,6 +43,53 @@ pub struct SymbolTable {
 
     /// Map of "short_name" -> List of "entity_id" (Fuzzy / Short name match)
     index: HashMap<String, Vec<String>>,
+
+    /// Map of "type_name" -> List of "entity_id" for type-like Buildings
+    /// (not files or impl blocks), used for inheritance and type lookups
+    types: HashMap<String, Vec<String>>,
+
+    /// Map of "self_type_name" -> List of impl block ids (Rust)
+    impls: HashMap<String, Vec<String>>,
+
+    /// Map of impl block id -> its self type as written
+    impl_self_types: HashMap<String, String>,
+
+    /// Map of "entity_id" -> supertypes as written, for type Buildings
+    supertypes: HashMap<String, Vec<String>>,
+
+    /// Map of "file_id" -> imports recorded by the parser, without aliases
+    file_imports: HashMap<String, Vec<String>>,
+
+    /// Ids of inline Rust modules
+    modules: HashSet<String>,
+
+    /// Map of "file_id" -> local alias -> imported path (`use a::b as c`)
+    aliases: HashMap<String, HashMap<String, String>>,
+
+    /// Map of "file_id" -> entity ids its imports resolved to
+    resolved_imports: HashMap<String, Vec<String>>,
+
+    /// Map of "file_id" -> declared package (Java)
+    packages: HashMap<String, String>,
+
+    /// Ids of function prototypes (C/C++ declarations without a body)
+    declarations: HashSet<String>,
+
+    /// Ids of Rooms that are not prototypes; a prototype followed by its
+    /// definition in the same file shares its id
+    defined_rooms: HashSet<String>,
+
+    /// Map of prototype id -> the Room holding its definition
+    definitions: HashMap<String, String>,
+
+    /// Map of definition id -> the prototypes linked to it
+    declared_by: HashMap<String, Vec<String>>,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SymbolTable {
@@ -15,6 +97,19 @@ impl SymbolTable {
         Self {
             symbols: HashMap::new(),
             index: HashMap::new(),
+            types: HashMap::new(),
+            impls: HashMap::new(),
+            impl_self_types: HashMap::new(),
+            supertypes: HashMap::new(),
+            file_imports: HashMap::new(),
+            modules: HashSet::new(),
+            aliases: HashMap::new(),
+            resolved_imports: HashMap::new(),
+            packages: HashMap::new(),
+            declarations: HashSet::new(),
+            defined_rooms: HashSet::new(),
+            definitions: HashMap::new(),
+            declared_by: HashMap::new(),
         }
     }
 
@@ -26,6 +121,50 @@ impl SymbolTable {
     }
 
     fn index_entity(&mut self, entity: &GameEntity) {
+        if let GameEntity::Building { id, name, building_type, imports, supertypes, metadata, .. } = entity {
+            match building_type.as_str() {
+                "file" => {
+                    let mut paths = Vec::with_capacity(imports.len());
+                    for import in imports {
+                        let (path, alias) = split_import_alias(import);
+                        if let Some(alias) = alias {
+                            self.aliases.entry(id.clone()).or_default().insert(alias.to_string(), path.to_string());
+                        }
+                        paths.push(path.to_string());
+                    }
+                    self.file_imports.insert(id.clone(), paths);
+                    if let Some(package) = metadata.as_ref().and_then(|m| m.get("package")) {
+                        self.packages.insert(id.clone(), package.clone());
+                    }
+                }
+                // Inline Rust modules are namespaces, not types
+                "module" => {
+                    self.modules.insert(id.clone());
+                }
+                // supertypes of an impl are [trait, self type] or [self type]
+                "impl" => {
+                    if let Some(self_type) = supertypes.last() {
+                        self.impls.entry(short_name(self_type).to_string()).or_default().push(id.clone());
+                        self.impl_self_types.insert(id.clone(), self_type.clone());
+                    }
+                }
+                _ => {
+                    self.types.entry(name.clone()).or_default().push(id.clone());
+                    if !supertypes.is_empty() {
+                        self.supertypes.insert(id.clone(), supertypes.clone());
+                    }
+                }
+            }
+        }
+
+        if let GameEntity::Room { id, metadata, .. } = entity {
+            if metadata.as_ref().and_then(|m| m.get("declaration")).is_some_and(|v| v == "true") {
+                self.declarations.insert(id.clone());
+            } else {
+                self.defined_rooms.insert(id.clone());
+            }
+        }
+
         match entity {
             GameEntity::Room {
                 id, name, children, ..
@@ -62,47 +201,440 @@ impl SymbolTable {
         }
     }
 
-    /// Resolve a potential function call or import to a definitive ID
-    pub fn resolve(&self, symbol: &str, context_file_id: &str) -> Option<String> {
+    /// Ids of all indexed file Buildings.
+    pub fn files(&self) -> impl Iterator<Item = &String> {
+        self.file_imports.keys()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.symbols.contains_key(id)
+    }
+
+    /// Records that an import of `file_id` resolved to `target_id`, so calls
+    /// and types from that file prefer it over same-named candidates.
+    pub fn add_resolved_import(&mut self, file_id: &str, target_id: &str) {
+        let targets = self.resolved_imports.entry(file_id.to_string()).or_default();
+        if !targets.iter().any(|t| t == target_id) {
+            targets.push(target_id.to_string());
+        }
+    }
+
+    /// The package `file_id` declares (Java).
+    pub fn package_of(&self, file_id: &str) -> Option<&str> {
+        self.packages.get(file_id).map(String::as_str)
+    }
+
+    /// Ids of all type-like Buildings (classes, structs, interfaces, ...).
+    pub fn type_ids(&self) -> impl Iterator<Item = &String> {
+        self.types.values().flatten()
+    }
+
+    /// Ids of all function prototypes.
+    pub fn declarations(&self) -> impl Iterator<Item = &String> {
+        self.declarations.iter().filter(|id| !self.defined_rooms.contains(*id))
+    }
+
+    pub fn is_declaration(&self, id: &str) -> bool {
+        self.declarations.contains(id) && !self.defined_rooms.contains(id)
+    }
+
+    /// Ids of Rooms and Buildings named `name`.
+    pub fn ids_named(&self, name: &str) -> &[String] {
+        self.index.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Links the prototype `declaration_id` to the Room that defines it, so
+    /// calls resolving to the prototype land on the definition instead.
+    pub fn add_definition(&mut self, declaration_id: &str, definition_id: &str) {
+        self.definitions.insert(declaration_id.to_string(), definition_id.to_string());
+        self.declared_by.entry(definition_id.to_string()).or_default().push(declaration_id.to_string());
+    }
+
+    /// The path a file imports under a local alias.
+    pub fn alias(&self, file_id: &str, alias: &str) -> Option<&str> {
+        self.aliases.get(file_id)?.get(alias).map(String::as_str)
+    }
+
+    /// Imports recorded by the parser for `file_id`.
+    pub fn imports_of(&self, file_id: &str) -> &[String] {
+        self.file_imports.get(file_id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Whether any indexed file lives at or below the module `path`
+    /// (`"utils"` matches `utils.py`, `src/utils/mod.rs` and `lib/utils/x.py`).
+    pub fn has_module(&self, path: &str) -> bool {
+        self.file_imports.keys().any(|file| {
+            let module = module_path(file);
+            module == path
+                || module.ends_with(&format!("/{path}"))
+                || module.starts_with(&format!("{path}/"))
+                || module.contains(&format!("/{path}/"))
+        })
+    }
+
+    /// Resolve a call recorded by a parser (`name`, `receiver.name` or
+    /// `receiver::name`) made from `caller_id`. Returns None when nothing
+    /// matches or several candidates are equally plausible.
+    pub fn resolve(&self, symbol: &str, caller_id: &str) -> Option<Resolution> {
+        let mut resolution = self.resolve_symbol(symbol, caller_id)?;
+        if let Some(definition) = self.definitions.get(&resolution.id) {
+            resolution.id = definition.clone();
+        }
+        Some(resolution)
+    }
+
+    fn resolve_symbol(&self, symbol: &str, caller_id: &str) -> Option<Resolution> {
         // 1. Exact match
         if let Some(id) = self.symbols.get(symbol) {
-            return Some(id.clone());
+            return Some(Resolution::new(id, Confidence::High, "exact"));
         }
 
-        // 2. Resolve relative to current file (same module)
-        // e.g. calling "helper" inside "src/main.rs" -> check "src/main.rs::helper"
-        let local_id = format!("{}::{}", context_file_id, symbol);
-        if let Some(id) = self.symbols.get(&local_id) {
-            return Some(id.clone());
+        // `use a::b as c; c()` and `c::f()`: resolve as the imported name
+        let head_len = symbol.find([':', '.']).unwrap_or(symbol.len());
+        if let Some(path) = self.alias(file_of(caller_id), &symbol[..head_len]) {
+            // Prefer the module the alias came from (`csv::parse`), then the bare name
+            let mut segments: Vec<&str> = path.split("::").filter(|s| !matches!(*s, "crate" | "self" | "super")).collect();
+            let original = segments.pop().unwrap_or(path);
+            if original != &symbol[..head_len] {
+                let rest = &symbol[head_len..];
+                let qualified = segments.last().map(|module| format!("{module}::{original}{rest}"));
+                let mut resolution = qualified
+                    .and_then(|qualified| self.resolve_symbol(&qualified, caller_id))
+                    .or_else(|| self.resolve_symbol(&format!("{original}{rest}"), caller_id))?;
+                resolution.strategy = "alias";
+                return Some(resolution);
+            }
+        }
+
+        let name = call_target_name(symbol);
+        let receiver = (name.len() < symbol.len())
+            .then(|| symbol[..symbol.len() - name.len()].trim_end_matches([':', '.']));
+        let caller_file = file_of(caller_id);
+
+        if let Some(receiver) = receiver {
+            // 2. self.method() / this.method(): only the enclosing type and its supertypes
+            if SELF_RECEIVERS.contains(&receiver) {
+                let owner = self.enclosing_type(caller_id)?;
+                return self
+                    .find_member(&owner, name, &mut Vec::new())
+                    .map(|id| Resolution::new(&id, Confidence::High, "enclosing_type"));
+            }
+
+            // 3. Type::method() / Type.staticMethod()
+            if let Some(owner) = self.resolve_type(receiver, caller_id)
+                && let Some(id) = self.find_member(&owner.id, name, &mut Vec::new())
+            {
+                return Some(Resolution::new(&id, owner.confidence, "qualified_type"));
+            }
+
+            // 4. module::function() / module.function()
+            if let Some(resolution) = self.resolve_in_module(receiver, name, caller_file) {
+                return Some(resolution);
+            }
         }
 
-        // 3. Resolve via short name index
-        if let Some(candidates) = self.index.get(symbol) {
-            // Disambiguation strategy:
-            // - If only 1 candidate, use it.
-            // - If context file ID shares prefix (same dir), prefer that.
+        // A call on some other object can only be matched by name, so it is capped at medium
+        let cap = if receiver.is_some() { Confidence::Medium } else { Confidence::High };
+        // A prototype and its definition are one candidate: the definition
+        let mut candidates: Vec<&String> = Vec::new();
+        for id in self.index.get(name)?.iter().filter(|id| paths::same_family(file_of(id), caller_file)) {
+            let id = self.definitions.get(id).unwrap_or(id);
+            if !candidates.contains(&id) {
+                candidates.push(id);
+            }
+        }
 
-            if candidates.len() == 1 {
-                return Some(candidates[0].clone());
+        // 5. Same file, preferring a definition in the innermost enclosing module
+        let local: Vec<&String> = candidates.iter().copied().filter(|id| file_of(id) == caller_file).collect();
+        if local.len() == 1 {
+            return Some(Resolution::new(local[0], cap, "same_file"));
+        }
+        let mut scope = caller_id;
+        loop {
+            let in_scope = format!("{scope}::{name}");
+            if (scope == caller_file || self.modules.contains(scope)) && local.contains(&&in_scope) {
+                return Some(Resolution::new(&in_scope, cap, "same_file"));
             }
+            let Some((parent, _)) = scope.rsplit_once("::") else { break };
+            scope = parent;
+        }
 
-            // Simple proximity heuristic: longest common prefix with context
-            let best_match = candidates
-                .iter()
-                .max_by_key(|cand_id| common_prefix_len(cand_id, context_file_id));
+        // 6. Defined in a file the caller imports
+        let imported = self.filter_imported(&candidates, caller_file);
+        if imported.len() == 1 {
+            return Some(Resolution::new(imported[0], cap, "import"));
+        }
+
+        // 7. Defined in the caller's package, visible without an import
+        let same_package = self.filter_same_package(&candidates, caller_file);
+        if same_package.len() == 1 {
+            return Some(Resolution::new(same_package[0], cap, "same_package"));
+        }
+
+        // 8. The only definition with that name
+        if candidates.len() == 1 {
+            return Some(Resolution::new(candidates[0], Confidence::Medium.min(cap), "unique_name"));
+        }
+
+        // 9. The only definition in the caller's directory
+        let caller_dir = dir_of(caller_file);
+        let nearby: Vec<&String> = candidates.iter().copied().filter(|id| dir_of(file_of(id)) == caller_dir).collect();
+        if nearby.len() == 1 {
+            return Some(Resolution::new(nearby[0], Confidence::Low, "same_directory"));
+        }
+
+        None
+    }
+
+    /// Resolve an import recorded by a parser to a file Building.
+    pub fn resolve_import(&self, import: &str, file_id: &str) -> Option<Resolution> {
+        if self.file_imports.contains_key(import) {
+            return Some(Resolution::new(import, Confidence::High, "exact"));
+        }
 
-            if let Some(best) = best_match {
-                return Some(best.clone());
+        let matches: Vec<&String> = self
+            .file_imports
+            .keys()
+            .filter(|candidate| *candidate != file_id && import_matches(import, file_id, candidate))
+            .collect();
+        match matches.as_slice() {
+            [only] => Some(Resolution::new(only, Confidence::High, "module_path")),
+            _ => None,
+        }
+    }
+
+    /// Resolve a type name (e.g. a base class) to a type Building.
+    /// Path qualifiers (`fmt::Display`, `React.Component`) are ignored.
+    pub fn resolve_type(&self, type_name: &str, context_id: &str) -> Option<Resolution> {
+        let short = short_name(type_name);
+        let context_file = file_of(context_id);
+        let candidates: Vec<&String> = self
+            .types
+            .get(short)?
+            .iter()
+            .filter(|id| paths::same_family(file_of(id), context_file))
+            .collect();
+
+        if let [only] = candidates.as_slice() {
+            return Some(Resolution::new(only, Confidence::High, "unique_name"));
+        }
+
+        // Prefer a type declared in the same file, then one the file imports
+        let local_id = format!("{context_file}::{short}");
+        if candidates.contains(&&local_id) {
+            return Some(Resolution::new(&local_id, Confidence::High, "same_file"));
+        }
+        // Java: a type in the same package shadows one from an on-demand import
+        if let [only] = self.filter_same_package(&candidates, context_file).as_slice() {
+            return Some(Resolution::new(only, Confidence::High, "same_package"));
+        }
+        let imported = self.filter_imported(&candidates, context_file);
+        if let [only] = imported.as_slice() {
+            return Some(Resolution::new(only, Confidence::High, "import"));
+        }
+
+        let context_dir = dir_of(context_file);
+        let nearby: Vec<&String> = candidates.iter().copied().filter(|id| dir_of(file_of(id)) == context_dir).collect();
+        if let [only] = nearby.as_slice() {
+            return Some(Resolution::new(only, Confidence::Low, "same_directory"));
+        }
+        None
+    }
+
+    /// The type Building a Room belongs to: the nearest enclosing type, or
+    /// for a Rust impl block, the type it is for.
+    fn enclosing_type(&self, room_id: &str) -> Option<String> {
+        let mut current = room_id;
+        while let Some((parent, _)) = current.rsplit_once("::") {
+            // An impl for a type outside the repo still has its own methods
+            if let Some(self_type) = self.impl_self_types.get(parent) {
+                let owner = self.resolve_type(self_type, parent).map(|r| r.id);
+                return Some(owner.unwrap_or_else(|| parent.to_string()));
             }
+            if self.types.get(short_name(parent)).is_some_and(|ids| ids.iter().any(|i| i == parent)) {
+                return Some(parent.to_string());
+            }
+            current = parent;
+        }
+        None
+    }
+
+    /// A method named `name` on `type_id`, its impl blocks, or (recursively) its supertypes.
+    fn find_member(&self, type_id: &str, name: &str, visited: &mut Vec<String>) -> Option<String> {
+        if visited.iter().any(|v| v == type_id) {
+            return None;
         }
+        visited.push(type_id.to_string());
 
+        let direct = format!("{type_id}::{name}");
+        if self.symbols.contains_key(&direct) {
+            return Some(direct);
+        }
+        let short = short_name(type_id);
+        for impl_id in self.impls.get(short).into_iter().flatten() {
+            let member = format!("{impl_id}::{name}");
+            if paths::same_family(file_of(impl_id), file_of(type_id)) && self.symbols.contains_key(&member) {
+                return Some(member);
+            }
+        }
+        for supertype in self.supertypes.get(type_id).into_iter().flatten() {
+            if let Some(parent) = self.resolve_type(supertype, type_id)
+                && let Some(found) = self.find_member(&parent.id, name, visited)
+            {
+                return Some(found);
+            }
+        }
         None
     }
+
+    /// `utils::parse` / `utils.parse`: a top-level `parse` in a file named `utils`.
+    fn resolve_in_module(&self, module: &str, name: &str, caller_file: &str) -> Option<Resolution> {
+        let module = short_name(module);
+        let candidates: Vec<&String> = self
+            .index
+            .get(name)?
+            .iter()
+            .filter(|id| paths::same_family(file_of(id), caller_file))
+            .filter(|id| {
+                let (file, rest) = id.split_once("::").unwrap_or((id, ""));
+                rest == name && module_name(file) == module
+            })
+            .collect();
+
+        match candidates.as_slice() {
+            [] => None,
+            [only] => Some(Resolution::new(only, Confidence::High, "module")),
+            _ => match self.filter_imported(&candidates, caller_file).as_slice() {
+                [only] => Some(Resolution::new(only, Confidence::High, "module")),
+                _ => None,
+            },
+        }
+    }
+
+    fn filter_imported<'a>(&self, candidates: &[&'a String], file_id: &str) -> Vec<&'a String> {
+        let imports = self.imports_of(file_id);
+        let resolved = self.resolved_imports.get(file_id).map(Vec::as_slice).unwrap_or_default();
+        candidates
+            .iter()
+            .copied()
+            .filter(|id| {
+                // A definition is also reachable through an imported header declaring it
+                let declared_by = self.declared_by.get(id.as_str()).map(Vec::as_slice).unwrap_or_default();
+                std::iter::once(*id).chain(declared_by).any(|id| {
+                    resolved.iter().any(|target| id == target || id.starts_with(&format!("{target}::")))
+                        || imports.iter().any(|import| import_matches(import, file_id, file_of(id)))
+                })
+                    // Java `import com.example.*;`
+                    || self.package_of(file_of(id)).is_some_and(|package| {
+                        imports.iter().any(|import| import.strip_suffix(".*") == Some(package))
+                    })
+            })
+            .collect()
+    }
+
+    fn filter_same_package<'a>(&self, candidates: &[&'a String], file_id: &str) -> Vec<&'a String> {
+        let Some(package) = self.package_of(file_id) else { return Vec::new() };
+        candidates.iter().copied().filter(|id| self.package_of(file_of(id)) == Some(package)).collect()
+    }
+}
+
+fn short_name(path: &str) -> &str {
+    path.rsplit(['.', ':']).next().unwrap_or(path)
+}
+
+/// Path without extension or a trailing `mod`/`index`/`__init__` ("src/utils/mod.rs" -> "src/utils").
+fn module_path(path: &str) -> &str {
+    let (dir, file) = path.rsplit_once('/').map(|(d, f)| (Some(d), f)).unwrap_or((None, path));
+    let stem = file.split('.').next().unwrap_or(file);
+    match (dir, stem) {
+        (Some(dir), "mod" | "index" | "__init__") => dir,
+        _ => &path[..path.len() - (file.len() - stem.len())],
+    }
 }
 
-fn common_prefix_len(a: &str, b: &str) -> usize {
-    a.char_indices()
-        .zip(b.chars())
-        .take_while(|((_, c1), c2)| c1 == c2)
-        .count()
+fn module_name(file: &str) -> &str {
+    let path = module_path(file);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Whether `import` (as recorded in `importer`) refers to `candidate` or an item inside it.
+fn import_matches(import: &str, importer: &str, candidate: &str) -> bool {
+    // Named JS imports are recorded as `specifier::name`
+    let import = match import.split_once("::") {
+        Some((specifier, _)) if specifier.starts_with('.') || specifier.contains('/') => specifier,
+        _ => import,
+    };
+    let resolved = if import.starts_with("./") || import.starts_with("../") {
+        normalize_relative(dir_of(importer), import)
+    } else {
+        import.to_string()
+    };
+    let import_path = module_path(&resolved);
+    let candidate_path = module_path(candidate);
+
+    candidate_path == import_path
+        || candidate_path.ends_with(&format!("/{import_path}"))
+        || import_path.starts_with(&format!("{candidate_path}/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn table(files: &[(&str, &str)]) -> SymbolTable {
+        let entities: Vec<GameEntity> = files.iter().map(|(path, source)| test_support::parsed_file(path, source)).collect();
+        let mut table = SymbolTable::new();
+        for entity in &entities {
+            table.index_entity(entity);
+        }
+        table
+    }
+
+    #[test]
+    fn test_self_calls_resolve_to_enclosing_type() {
+        let table = table(&[
+            ("src/a.rs", "struct A;\nimpl A { fn run(&self) { self.parse(); } fn parse(&self) {} }"),
+            ("src/b.rs", "struct B;\nimpl B { fn parse(&self) {} }"),
+        ]);
+
+        let resolution = table.resolve("self.parse", "src/a.rs::impl_A::run").unwrap();
+
+        assert_eq!(resolution.id, "src/a.rs::impl_A::parse");
+        assert_eq!(resolution.strategy, "enclosing_type");
+    }
+
+    #[test]
+    fn test_ambiguous_names_use_imports_or_are_dropped() {
+        let table = table(&[
+            ("web/main.ts", "import { parse } from './csv';\nfunction run() { parse(); }"),
+            ("web/other.ts", "function run() { parse(); }"),
+            ("web/csv.ts", "export function parse() {}"),
+            ("web/json.ts", "export function parse() {}"),
+        ]);
+
+        let imported = table.resolve("parse", "web/main.ts::run").unwrap();
+        assert_eq!((imported.id.as_str(), imported.strategy), ("web/csv.ts::parse", "import"));
+        assert_eq!(table.resolve("parse", "web/other.ts::run"), None);
+    }
+
+    #[test]
+    fn test_use_aliases_and_inline_modules() {
+        let table = table(&[
+            ("src/lib.rs", "use crate::csv::parse as read_csv;
+fn run() { read_csv(); helper(); }
+fn helper() {}
+mod tests {
+    fn helper() {}
+    fn checks() { helper(); }
+}"),
+            ("src/csv.rs", "pub fn parse() {}"),
+            ("src/json.rs", "pub fn parse() {}"),
+        ]);
+
+        let aliased = table.resolve("read_csv", "src/lib.rs::run").unwrap();
+        assert_eq!((aliased.id.as_str(), aliased.strategy), ("src/csv.rs::parse", "alias"));
+        assert_eq!(table.resolve("helper", "src/lib.rs::run").unwrap().id, "src/lib.rs::helper");
+        assert_eq!(table.resolve("helper", "src/lib.rs::tests::checks").unwrap().id, "src/lib.rs::tests::helper");
+    }
 }
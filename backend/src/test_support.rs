@@ -0,0 +1,111 @@
+//! Entity fixtures shared by unit tests.
+
+use tree_sitter::Tree;
+
+use crate::hierarchy;
+use crate::languages::registry;
+use crate::models::GameEntity;
+
+/// A City of `language` (id `city_{language}`) holding `children`.
+pub fn city(language: &str, children: Vec<GameEntity>) -> GameEntity {
+    GameEntity::City {
+        id: format!("city_{language}"),
+        name: language.to_string(),
+        language: language.to_string(),
+        theme: String::new(),
+        entry_point_id: None,
+        stats: Default::default(),
+        children,
+        metadata: None,
+    }
+}
+
+/// The District for the directory `path`, named after its last segment.
+pub fn district(path: &str, children: Vec<GameEntity>) -> GameEntity {
+    GameEntity::District {
+        id: hierarchy::district_id(path),
+        name: path.rsplit('/').next().unwrap().to_string(),
+        path: path.to_string(),
+        children,
+        metadata: None,
+    }
+}
+
+/// A file Building with `children` and the imports recorded for it.
+pub fn file(id: &str, children: Vec<GameEntity>, imports: &[&str]) -> GameEntity {
+    building(id, children, imports.iter().map(|i| i.to_string()).collect())
+}
+
+/// A public function Room with no calls.
+pub fn function(id: &str) -> GameEntity {
+    GameEntity::Room {
+        id: id.to_string(),
+        name: id.rsplit("::").next().unwrap().to_string(),
+        room_type: "function".to_string(),
+        is_main: false,
+        is_async: false,
+        is_test: false,
+        visibility: "public".to_string(),
+        complexity: 1,
+        loc: 1,
+        parameters: vec![],
+        return_type: None,
+        calls: vec![],
+        children: vec![],
+        span: None,
+        metadata: None,
+    }
+}
+
+/// A public class Building with no members.
+pub fn class(id: &str) -> GameEntity {
+    GameEntity::Building {
+        id: id.to_string(),
+        name: id.rsplit("::").next().unwrap().to_string(),
+        building_type: "class".to_string(),
+        is_public: true,
+        is_test: false,
+        loc: 1,
+        imports: vec![],
+        supertypes: vec![],
+        children: vec![],
+        span: None,
+        metadata: None,
+    }
+}
+
+/// A file Building holding one function Room per name.
+pub fn file_with_functions(id: &str, names: &[&str]) -> GameEntity {
+    file(id, names.iter().map(|name| function(&format!("{id}::{name}"))).collect(), &[])
+}
+
+/// A file Building for `source`, parsed by the parser for its extension.
+pub fn parsed_file(path: &str, source: &str) -> GameEntity {
+    annotated_file(path, source, |_, _, _, _, _| {})
+}
+
+/// Like [`parsed_file`], with `annotate` run over the parse tree the way
+/// `parser::parse_single_file` runs its passes (e.g. `ffi::annotate_ffi`).
+pub fn annotated_file(path: &str, source: &str, annotate: fn(&str, &Tree, &str, &str, &mut Vec<GameEntity>)) -> GameEntity {
+    let ext = path.rsplit('.').next().unwrap();
+    let tree = registry::parse_tree(ext, source).unwrap();
+    let (mut children, imports) = registry::parse_with_tree(ext, &tree, source, path).unwrap();
+    annotate(ext, &tree, source, path, &mut children);
+    building(path, children, imports)
+}
+
+fn building(id: &str, children: Vec<GameEntity>, imports: Vec<String>) -> GameEntity {
+    GameEntity::Building {
+        id: id.to_string(),
+        name: id.rsplit('/').next().unwrap().to_string(),
+        building_type: "file".to_string(),
+        is_public: true,
+        is_test: false,
+        loc: 1,
+        imports,
+        supertypes: vec![],
+        children,
+        span: None,
+        metadata: None,
+    }
+}
@@ -0,0 +1,339 @@
+//! Test detection and test-to-code links.
+//!
+//! Tests are recognised per language:
+//! - Rust: `#[test]`, `#[tokio::test]` and other attributes ending in `test`,
+//!   plus everything inside a `#[cfg(test)]` module
+//! - Python: pytest `test_*` functions, `Test*` and `unittest.TestCase` classes, fixtures
+//! - Java: JUnit `@Test` (and parameterised/repeated variants) and lifecycle methods
+//! - JavaScript/TypeScript: Jest/Mocha `it(...)` and `test(...)` calls, which
+//!   become Rooms named after their `describe` blocks
+//! - any file under a `tests/`, `test/`, `__tests__/` or `spec/` directory or
+//!   named like `test_*.py`, `*_test.*`, `*.test.*` or `*.spec.*`
+//!
+//! A class or struct holding a test is a test Building, and everything inside
+//! a test Building or Room is test code too.
+
+use std::collections::{HashMap, HashSet};
+
+use tree_sitter::{Node, Tree};
+
+use crate::languages::parser_utils::{self, has_attribute, preceding_attributes, walk};
+use crate::models::{GameEntity, Route, RouteType};
+
+/// JUnit annotations marking tests and their setup/teardown.
+const JUNIT_ANNOTATIONS: &[&str] = &[
+    "Test", "ParameterizedTest", "RepeatedTest", "TestFactory", "TestTemplate",
+    "BeforeEach", "AfterEach", "BeforeAll", "AfterAll", "Before", "After", "BeforeClass", "AfterClass",
+];
+
+/// Whether a file is test code by its path alone.
+pub fn is_test_path(path: &str) -> bool {
+    let mut segments = path.split('/');
+    let file = segments.next_back().unwrap_or_default();
+    segments.any(|dir| matches!(dir, "test" | "tests" | "__tests__" | "spec"))
+        || (file.starts_with("test_") && file.ends_with(".py"))
+        || file == "conftest.py"
+        || file.contains("_test.")
+        || file.contains(".test.")
+        || file.contains(".spec.")
+}
+
+/// Where test code sits in a file's source.
+#[derive(Default)]
+struct Marks {
+    /// Start bytes of test functions and classes
+    starts: HashSet<usize>,
+    /// Byte ranges holding only test code
+    ranges: Vec<(usize, usize)>,
+}
+
+impl Marks {
+    fn contains(&self, start: usize) -> bool {
+        self.starts.contains(&start) || self.ranges.iter().any(|&(from, to)| from <= start && start < to)
+    }
+}
+
+/// Sets `is_test` on the test Rooms and Buildings of a parsed file, adding a
+/// Room for each JavaScript/TypeScript `it`/`test` block.
+pub fn annotate_tests(ext: &str, tree: &Tree, source: &str, file_id: &str, entities: &mut Vec<GameEntity>) {
+    let whole_file = is_test_path(file_id);
+    let bytes = source.as_bytes();
+    let root = tree.root_node();
+
+    let mut marks = Marks::default();
+    match ext {
+        "rs" => walk(root, &mut |n| rust_marks(n, bytes, &mut marks)),
+        "py" => walk(root, &mut |n| python_marks(n, bytes, &mut marks)),
+        "java" => walk(root, &mut |n| java_marks(n, bytes, &mut marks)),
+        "js" | "jsx" | "ts" | "tsx" => entities.extend(jest_rooms(root, bytes, file_id)),
+        _ => {}
+    }
+    mark(entities, &marks, whole_file);
+}
+
+fn rust_marks(node: Node, source: &[u8], marks: &mut Marks) {
+    match node.kind() {
+        "function_item" => {
+            let attributes = preceding_attributes(node, source);
+            if has_attribute(&attributes, "test") || has_attribute(&attributes, "rstest") {
+                marks.starts.insert(node.start_byte());
+            }
+        }
+        "mod_item" => {
+            let attributes = preceding_attributes(node, source);
+            if attributes.iter().any(|a| a.replace(' ', "").contains("cfg(test)")) {
+                marks.ranges.push((node.start_byte(), node.end_byte()));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn python_marks(node: Node, source: &[u8], marks: &mut Marks) {
+    let name = || node.child_by_field_name("name").map(|n| parser_utils::get_text(n, source)).unwrap_or_default();
+    match node.kind() {
+        "function_definition" if name().starts_with("test") => {
+            marks.starts.insert(node.start_byte());
+        }
+        "class_definition" => {
+            let name = name();
+            let bases = node.child_by_field_name("superclasses").map(|n| parser_utils::get_text(n, source)).unwrap_or_default();
+            if name.starts_with("Test") || name.ends_with("Test") || name.ends_with("Tests") || bases.contains("TestCase") {
+                marks.ranges.push((node.start_byte(), node.end_byte()));
+            }
+        }
+        "decorated_definition" => {
+            let mut cursor = node.walk();
+            let is_fixture = node
+                .children(&mut cursor)
+                .any(|c| c.kind() == "decorator" && parser_utils::get_text(c, source).contains("fixture"));
+            if is_fixture && let Some(definition) = node.child_by_field_name("definition") {
+                marks.starts.insert(definition.start_byte());
+            }
+        }
+        _ => {}
+    }
+}
+
+fn java_marks(node: Node, source: &[u8], marks: &mut Marks) {
+    if node.kind() != "method_declaration" {
+        return;
+    }
+    let mut cursor = node.walk();
+    let Some(modifiers) = node.children(&mut cursor).find(|c| c.kind() == "modifiers") else { return };
+    let mut cursor = modifiers.walk();
+    let is_test = modifiers.children(&mut cursor).filter(|c| matches!(c.kind(), "marker_annotation" | "annotation")).any(|annotation| {
+        let name = annotation.child_by_field_name("name").map(|n| parser_utils::get_text(n, source)).unwrap_or_default();
+        JUNIT_ANNOTATIONS.contains(&name.rsplit('.').next().unwrap_or_default())
+    });
+    if is_test {
+        marks.starts.insert(node.start_byte());
+    }
+}
+
+/// Calls made inside tests that are test framework plumbing, not code under test.
+fn is_test_builtin(name: &str) -> bool {
+    matches!(name, "describe" | "it" | "test" | "expect" | "beforeEach" | "afterEach" | "beforeAll" | "afterAll" | "jest" | "vi" | "fn" | "spyOn" | "mock" | "each" | "only" | "skip")
+        || (name.starts_with("to") && name[2..].starts_with(char::is_uppercase))
+        || name.starts_with("mock")
+}
+
+/// The function a Jest/Mocha block calls (`it`, `describe`), its title and callback.
+fn test_block<'a>(node: Node<'a>, source: &[u8]) -> Option<(String, String, Node<'a>)> {
+    if node.kind() != "call_expression" {
+        return None;
+    }
+    let function = parser_utils::get_text(node.child_by_field_name("function")?, source);
+    // `it.only(...)`, `describe.skip(...)`
+    let function = function.split('.').next().unwrap_or_default().to_string();
+    if !matches!(function.as_str(), "describe" | "context" | "it" | "test") {
+        return None;
+    }
+    let arguments = node.child_by_field_name("arguments")?;
+    let title = arguments.named_child(0).filter(|a| matches!(a.kind(), "string" | "template_string"))?;
+    let title = parser_utils::get_text(title, source).trim_matches(['"', '\'', '`']).to_string();
+    let mut cursor = arguments.walk();
+    let callback = arguments.named_children(&mut cursor).find(|a| matches!(a.kind(), "arrow_function" | "function" | "function_expression"))?;
+    Some((function, title, callback))
+}
+
+fn jest_rooms(root: Node, source: &[u8], file_id: &str) -> Vec<GameEntity> {
+    let mut rooms = Vec::new();
+    let mut ids = HashSet::new();
+    walk(root, &mut |node| {
+        let Some((function, title, callback)) = test_block(node, source) else { return };
+        if !matches!(function.as_str(), "it" | "test") {
+            return;
+        }
+        let mut path = vec![title.clone()];
+        let mut parent = node.parent();
+        while let Some(ancestor) = parent {
+            if let Some((_, describe, _)) = test_block(ancestor, source) {
+                path.push(describe);
+            }
+            parent = ancestor.parent();
+        }
+        path.reverse();
+
+        let base = format!("{file_id}::{}", path.join(" > "));
+        let mut id = base.clone();
+        let mut n = 2;
+        while !ids.insert(id.clone()) {
+            id = format!("{base}#{n}");
+            n += 1;
+        }
+        let body = callback.child_by_field_name("body").unwrap_or(callback);
+        rooms.push(GameEntity::Room {
+            id,
+            name: title,
+            room_type: "test".to_string(),
+            is_main: false,
+            is_async: parser_utils::get_text(callback, source).starts_with("async"),
+            is_test: true,
+            visibility: "private".to_string(),
+            complexity: 1,
+            loc: parser_utils::count_lines(node),
+            parameters: vec![],
+            return_type: None,
+            calls: parser_utils::extract_function_calls(body, source, "call_expression", is_test_builtin),
+            children: vec![],
+            span: Some(parser_utils::span(node, file_id)),
+            metadata: None,
+        });
+    });
+    rooms
+}
+
+fn mark(entities: &mut [GameEntity], marks: &Marks, inherited: bool) {
+    for entity in entities {
+        match entity {
+            GameEntity::Room { is_test, span, children, .. } => {
+                *is_test |= inherited || span.as_ref().is_some_and(|s| marks.contains(s.start_byte as usize));
+                mark(children, marks, *is_test);
+            }
+            GameEntity::Building { building_type, is_test, span, children, .. } => {
+                *is_test |= inherited || span.as_ref().is_some_and(|s| marks.contains(s.start_byte as usize));
+                mark(children, marks, *is_test);
+                // A class holding tests is a test class, helpers included
+                if !*is_test && building_type != "file" && children.iter().any(|c| matches!(c, GameEntity::Room { is_test: true, .. })) {
+                    *is_test = true;
+                    mark(children, marks, true);
+                }
+            }
+            GameEntity::City { children, .. } | GameEntity::District { children, .. } => mark(children, marks, inherited),
+            GameEntity::Artifact { .. } => {}
+        }
+    }
+}
+
+/// Turns calls from test code into production code into `Tests` routes, so
+/// tests stay out of the call graph, and records on each production Room or
+/// Building how many tests call it directly (`test_count`). Returns the
+/// number of entities guarded by at least one test.
+pub fn link_tests(cities: &mut [GameEntity], routes: &mut [Route]) -> usize {
+    let mut tests = HashSet::new();
+    for city in cities.iter() {
+        collect_tests(city, &mut tests);
+    }
+
+    let mut callers: HashMap<String, HashSet<String>> = HashMap::new();
+    for route in routes.iter_mut().filter(|r| matches!(r.route_type, RouteType::FunctionCall)) {
+        if tests.contains(&route.from_id) && !tests.contains(&route.to_id) {
+            route.route_type = RouteType::Tests;
+            callers.entry(route.to_id.clone()).or_default().insert(route.from_id.clone());
+        }
+    }
+    for city in cities.iter_mut() {
+        annotate_guarded(city, &callers);
+    }
+    callers.len()
+}
+
+fn collect_tests(entity: &GameEntity, out: &mut HashSet<String>) {
+    match entity {
+        GameEntity::Room { id, is_test, children, .. } | GameEntity::Building { id, is_test, children, .. } => {
+            if *is_test {
+                out.insert(id.clone());
+            }
+            children.iter().for_each(|c| collect_tests(c, out));
+        }
+        GameEntity::City { children, .. } | GameEntity::District { children, .. } => children.iter().for_each(|c| collect_tests(c, out)),
+        GameEntity::Artifact { .. } => {}
+    }
+}
+
+fn annotate_guarded(entity: &mut GameEntity, callers: &HashMap<String, HashSet<String>>) {
+    let children = match entity {
+        GameEntity::Room { id, metadata, children, .. } | GameEntity::Building { id, metadata, children, .. } => {
+            if let Some(tests) = callers.get(id.as_str()) {
+                metadata.get_or_insert_with(HashMap::new).insert("test_count".into(), tests.len().to_string());
+            }
+            children
+        }
+        GameEntity::City { children, .. } | GameEntity::District { children, .. } => children,
+        GameEntity::Artifact { .. } => return,
+    };
+    for child in children {
+        annotate_guarded(child, callers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::registry;
+
+    fn parse(ext: &str, source: &str, file_id: &str) -> Vec<GameEntity> {
+        let tree = registry::parse_tree(ext, source).unwrap();
+        let (mut entities, _) = registry::parse_with_tree(ext, &tree, source, file_id).unwrap();
+        annotate_tests(ext, &tree, source, file_id, &mut entities);
+        entities
+    }
+
+    fn flagged(entities: &[GameEntity], out: &mut Vec<String>) {
+        for entity in entities {
+            if let GameEntity::Room { id, is_test, children, .. } | GameEntity::Building { id, is_test, children, .. } = entity {
+                if *is_test {
+                    out.push(id.clone());
+                }
+                flagged(children, out);
+            }
+        }
+    }
+
+    #[test]
+    fn test_detects_tests_per_language() {
+        let rust = "fn add(a: u32, b: u32) -> u32 { a + b }\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n    fn helper() -> u32 { 1 }\n    #[tokio::test]\n    async fn adds() { assert_eq!(add(helper(), 1), 2); }\n}\n";
+        let python = "def total(xs):\n    return sum(xs)\n\ndef test_total():\n    assert total([1]) == 1\n\nclass TestTotals:\n    def helper(self):\n        return total([])\n";
+        let java = "class CartTest {\n    private Cart cart;\n    @Test\n    void addsItems() { cart.add(1); }\n    void helper() {}\n}\nclass Cart {\n    void add(int n) {}\n}\n";
+        let jest = "import { total } from './cart';\ndescribe('cart', () => {\n  it('sums items', () => {\n    expect(total([1])).toBe(1);\n  });\n});\n";
+
+        let mut found = Vec::new();
+        flagged(&parse("rs", rust, "src/lib.rs"), &mut found);
+        flagged(&parse("py", python, "cart.py"), &mut found);
+        flagged(&parse("java", java, "Cart.java"), &mut found);
+        let jest_rooms = parse("ts", jest, "src/cart.ts");
+        flagged(&jest_rooms, &mut found);
+        assert_eq!(found, [
+            "src/lib.rs::tests", "src/lib.rs::tests::helper", "src/lib.rs::tests::adds",
+            "cart.py::test_total", "cart.py::TestTotals", "cart.py::TestTotals::helper",
+            "Cart.java::CartTest", "Cart.java::CartTest::addsItems", "Cart.java::CartTest::helper",
+            "src/cart.ts::cart > sums items",
+        ]);
+        let Some(GameEntity::Room { calls, .. }) = jest_rooms.last() else { panic!("no Jest room") };
+        assert_eq!(calls, &["total"]);
+
+        let mut cities = parse("rs", rust, "src/lib.rs");
+        let rooms: u32 = cities.iter().map(|entity| entity.count_entities().1).sum();
+        assert_eq!(rooms, 1, "test Rooms stay out of the City stats");
+        let mut routes = vec![
+            Route { id: "r0".into(), from_id: "src/lib.rs::tests::adds".into(), to_id: "src/lib.rs::add".into(), route_type: RouteType::FunctionCall, bidirectional: false, metadata: None },
+            Route { id: "r1".into(), from_id: "src/lib.rs::tests::adds".into(), to_id: "src/lib.rs::tests::helper".into(), route_type: RouteType::FunctionCall, bidirectional: false, metadata: None },
+        ];
+        assert_eq!(link_tests(&mut cities, &mut routes), 1);
+        assert!(matches!(routes[0].route_type, RouteType::Tests));
+        assert!(matches!(routes[1].route_type, RouteType::FunctionCall));
+        let GameEntity::Room { metadata, .. } = &cities[0] else { panic!("add is not a Room") };
+        assert_eq!(metadata.as_ref().unwrap()["test_count"], "1");
+    }
+}
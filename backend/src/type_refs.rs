@@ -0,0 +1,185 @@
+//! TypeReference routes from the types an entity names.
+//!
+//! Parameter and return types of Rooms, field types of Artifacts and the
+//! generic arguments inside them are split into type names, resolved through
+//! the symbol table, and linked from the Room or Building that declares them.
+
+use std::collections::HashMap;
+
+use crate::models::{GameEntity, Route, RouteType};
+use crate::symbol_table::{Resolution, SymbolTable};
+
+/// Primitive, standard-library and wrapper names that never point at a repo type.
+/// Wrappers (`Option`, `List`, `Promise`, ...) are skipped so their arguments are kept.
+fn is_builtin_type(name: &str) -> bool {
+    matches!(
+        name,
+        // Rust
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" |
+        "usize" | "f32" | "f64" | "bool" | "char" | "str" | "String" | "Self" | "self" |
+        "Vec" | "VecDeque" | "Option" | "Result" | "Box" | "Rc" | "Arc" | "Cell" | "RefCell" |
+        "Mutex" | "RwLock" | "HashMap" | "HashSet" | "BTreeMap" | "BTreeSet" | "Cow" | "Pin" |
+        "PhantomData" | "Fn" | "FnMut" | "FnOnce" | "Send" | "Sync" | "Sized" |
+        "mut" | "dyn" | "impl" | "const" | "inferred" |
+        // TypeScript / JavaScript
+        "string" | "number" | "boolean" | "any" | "unknown" | "never" | "void" | "undefined" |
+        "null" | "object" | "symbol" | "bigint" | "Array" | "ReadonlyArray" | "Promise" |
+        "Record" | "Partial" | "Required" | "Readonly" | "Pick" | "Omit" | "Map" | "Set" |
+        "keyof" | "typeof" | "readonly" |
+        // Python
+        "int" | "float" | "complex" | "bytes" | "list" | "dict" | "set" | "tuple" | "None" |
+        "Any" | "Optional" | "Union" | "List" | "Dict" | "Tuple" | "Callable" |
+        "Iterable" | "Iterator" | "Sequence" | "Mapping" | "Type" |
+        // Java
+        "byte" | "short" | "long" | "double" | "Integer" | "Long" | "Double" | "Float" |
+        "Boolean" | "Character" | "Byte" | "Short" | "Object" | "Collection" |
+        "ArrayList" | "LinkedList" | "Queue" | "Deque" | "extends" | "super" |
+        // C / C++
+        "signed" | "unsigned" | "struct" | "enum" | "union" | "auto" | "volatile" |
+        "size_t" | "std::string" | "std::vector" | "std::map" | "std::unordered_map" |
+        "std::shared_ptr" | "std::unique_ptr" | "std::optional"
+    )
+}
+
+/// Names of the types referenced by a written type, e.g. `&mut Vec<Option<User>>`
+/// -> `["User"]`, `Dict[str, List[Order]]` -> `["Order"]`, `const Node*` -> `["Node"]`.
+/// A name is listed once per occurrence (`HashMap<Id, Id>` -> `["Id", "Id"]`).
+/// Path qualifiers are kept (`models::User`) and lifetimes are dropped.
+pub fn referenced_type_names(written: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let tokens = written.split(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '.' | ':' | '\'')));
+
+    for token in tokens {
+        let token = token.trim_matches(|c| c == ':' || c == '.');
+        if token.is_empty()
+            || token.starts_with('\'')
+            || token.starts_with(|c: char| c.is_ascii_digit())
+            || is_builtin_type(token)
+        {
+            continue;
+        }
+        names.push(token.to_string());
+    }
+    names
+}
+
+/// Builds one TypeReference route per (referencing entity, referenced type)
+/// pair with the number of occurrences in its metadata. A Room or Building
+/// referencing itself or the type it is declared in is not reported.
+pub fn type_reference_routes(cities: &[GameEntity], symbol_table: &SymbolTable) -> Vec<Route> {
+    let mut refs = Vec::new();
+    for city in cities {
+        collect_type_refs(city, None, &mut refs);
+    }
+
+    let mut counts: HashMap<(String, String), (u32, Resolution)> = HashMap::new();
+    let mut order = Vec::new();
+    for (from, written) in refs {
+        for name in referenced_type_names(&written) {
+            let Some(resolution) = symbol_table.resolve_type(&name, &from) else {
+                continue;
+            };
+            let to = resolution.id.clone();
+            if from == to || from.starts_with(&format!("{to}::")) {
+                continue;
+            }
+            let key = (from.clone(), to);
+            let (count, _) = counts.entry(key.clone()).or_insert_with(|| {
+                order.push(key);
+                (0, resolution)
+            });
+            *count += 1;
+        }
+    }
+
+    order
+        .into_iter()
+        .map(|key| {
+            let (count, resolution) = &counts[&key];
+            let mut metadata = resolution.metadata();
+            metadata["count"] = serde_json::json!(count);
+            let (from_id, to_id) = key;
+            Route {
+                id: String::new(),
+                from_id,
+                to_id,
+                route_type: RouteType::TypeReference,
+                bidirectional: false,
+                metadata: Some(metadata),
+            }
+        })
+        .collect()
+}
+
+/// Collects (owner_id, written_type) pairs. Parameter and return types belong to
+/// their Room; an Artifact's type belongs to the Room or Building that holds it.
+fn collect_type_refs(entity: &GameEntity, owner: Option<&str>, out: &mut Vec<(String, String)>) {
+    match entity {
+        GameEntity::Room { id, parameters, return_type, children, .. } => {
+            for param in parameters {
+                out.push((id.clone(), param.datatype.clone()));
+            }
+            if let Some(return_type) = return_type {
+                out.push((id.clone(), return_type.clone()));
+            }
+            for child in children {
+                collect_type_refs(child, Some(id), out);
+            }
+        }
+        GameEntity::Building { id, children, .. } => {
+            for child in children {
+                collect_type_refs(child, Some(id), out);
+            }
+        }
+        GameEntity::Artifact { datatype, .. } => {
+            if let Some(owner) = owner {
+                out.push((owner.to_string(), datatype.clone()));
+            }
+        }
+        GameEntity::City { children, .. } | GameEntity::District { children, .. } => {
+            for child in children {
+                collect_type_refs(child, None, out);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[test]
+    fn test_referenced_type_names_strips_wrappers() {
+        assert_eq!(referenced_type_names("&'a mut Vec<Option<User>>"), vec!["User"]);
+        assert_eq!(referenced_type_names("Result<models::Order, AppError>"), vec!["models::Order", "AppError"]);
+        assert_eq!(referenced_type_names("Dict[str, List[Order]]"), vec!["Order"]);
+        assert_eq!(referenced_type_names(": Promise<User[]> | null"), vec!["User"]);
+        assert_eq!(referenced_type_names("const Node*"), vec!["Node"]);
+        assert!(referenced_type_names("int[]").is_empty());
+        assert_eq!(referenced_type_names("HashMap<User, User>"), vec!["User", "User"]);
+    }
+
+    #[test]
+    fn test_routes_count_references_per_entity_and_type() {
+        let source = "class User {\n  merge(other: User): User { return other; }\n}\nclass Order {}\nfunction index(users: Map<User, User>, order: Order): Order { return order; }\nfunction first(users: User[]): User { return users[0]; }\n";
+        let cities = vec![test_support::parsed_file("src/models.ts", source)];
+        let mut table = SymbolTable::new();
+        table.index_cities(&cities);
+
+        let routes: Vec<(String, String, u64)> = type_reference_routes(&cities, &table)
+            .into_iter()
+            .map(|r| {
+                assert!(matches!(r.route_type, RouteType::TypeReference));
+                (r.from_id, r.to_id, r.metadata.unwrap()["count"].as_u64().unwrap())
+            })
+            .collect();
+
+        // One route per (entity, type); `User::merge` mentioning `User` is not reported
+        assert_eq!(routes, [
+            ("src/models.ts::index".to_string(), "src/models.ts::User".to_string(), 2),
+            ("src/models.ts::index".to_string(), "src/models.ts::Order".to_string(), 2),
+            ("src/models.ts::first".to_string(), "src/models.ts::User".to_string(), 2),
+        ]);
+    }
+}
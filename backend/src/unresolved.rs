@@ -0,0 +1,353 @@
+//! Call and import targets the symbol table could not resolve.
+//!
+//! Each one is kept as an `UnresolvedRef`, classified as standard library,
+//! third-party package or unknown, and summarized per District for the
+//! external dependency report.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::hierarchy;
+use crate::languages::paths::{family, file_of, Family};
+use crate::models::{
+    CategoryCounts, GameEntity, DistrictDependencies, PackageUsage, Route, RouteType, UnresolvedCategory,
+    UnresolvedRef, UnresolvedSummary,
+};
+use crate::symbol_table::SymbolTable;
+
+/// Targets listed per package in a summary; the reference count is not capped.
+pub const MAX_TARGETS_PER_PACKAGE: usize = 20;
+
+const PY_STDLIB: &[&str] = &[
+    "abc", "argparse", "array", "ast", "asyncio", "base64", "bisect", "builtins", "calendar",
+    "collections", "concurrent", "configparser", "contextlib", "copy", "csv", "ctypes",
+    "dataclasses", "datetime", "decimal", "difflib", "enum", "errno", "fnmatch", "fractions",
+    "functools", "gc", "getpass", "glob", "gzip", "hashlib", "heapq", "hmac", "html", "http",
+    "importlib", "inspect", "io", "ipaddress", "itertools", "json", "logging", "math",
+    "mimetypes", "multiprocessing", "operator", "os", "pathlib", "pickle", "platform", "pprint",
+    "queue", "random", "re", "secrets", "select", "shlex", "shutil", "signal", "socket",
+    "sqlite3", "ssl", "statistics", "string", "struct", "subprocess", "sys", "tempfile",
+    "textwrap", "threading", "time", "timeit", "traceback", "types", "typing", "unittest",
+    "urllib", "uuid", "warnings", "weakref", "xml", "zipfile", "zlib",
+];
+
+const NODE_BUILTINS: &[&str] = &[
+    "assert", "buffer", "child_process", "cluster", "crypto", "dgram", "dns", "events", "fs",
+    "http", "http2", "https", "net", "os", "path", "perf_hooks", "process", "querystring",
+    "readline", "stream", "string_decoder", "timers", "tls", "tty", "url", "util", "v8", "vm",
+    "worker_threads", "zlib",
+];
+
+const JS_GLOBALS: &[&str] = &[
+    "console", "Math", "JSON", "Object", "Array", "Promise", "Date", "Number", "String",
+    "Reflect", "Symbol", "Intl", "window", "document", "localStorage", "sessionStorage",
+];
+
+const JAVA_LANG: &[&str] = &[
+    "System", "Math", "String", "Integer", "Long", "Double", "Boolean", "Character", "Thread",
+    "Objects", "Object", "StringBuilder", "Runtime", "Class",
+];
+
+const C_STD_HEADERS: &[&str] = &[
+    "assert.h", "ctype.h", "errno.h", "float.h", "limits.h", "locale.h", "math.h", "setjmp.h",
+    "signal.h", "stdarg.h", "stdbool.h", "stddef.h", "stdint.h", "stdio.h", "stdlib.h",
+    "string.h", "time.h", "unistd.h", "fcntl.h", "pthread.h", "dirent.h", "dlfcn.h",
+    "algorithm", "array", "atomic", "chrono", "cmath", "cstdint", "cstdio", "cstdlib",
+    "cstring", "deque", "functional", "fstream", "iomanip", "iostream", "iterator", "list",
+    "map", "memory", "mutex", "numeric", "optional", "queue", "set", "sstream", "stack",
+    "stdexcept", "string", "thread", "tuple", "unordered_map", "unordered_set", "utility",
+    "variant", "vector",
+];
+
+const C_SYSTEM_DIRS: &[&str] = &["sys", "bits", "linux", "arpa", "netinet", "net"];
+
+type Classification = (UnresolvedCategory, Option<String>);
+
+/// Classifies a route whose target could not be resolved.
+pub fn classify_route(route: &Route, symbol_table: &SymbolTable) -> UnresolvedRef {
+    let file = file_of(&route.from_id);
+    let (category, package) = match route.route_type {
+        RouteType::Import => classify_import(family(file), &route.to_id, symbol_table),
+        _ => classify_call(family(file), &route.to_id, file, symbol_table),
+    };
+    UnresolvedRef {
+        from_id: route.from_id.clone(),
+        target: route.to_id.clone(),
+        ref_type: route.route_type.clone(),
+        category,
+        package,
+    }
+}
+
+/// Classifies an import as recorded by the parsers: Rust paths (`serde::Deserialize`),
+/// JS specifiers (`@scope/pkg`, `node:fs`), Python modules (`os.path`, `.models::User`),
+/// C headers (`<openssl/ssl.h>`, `util.h`) and Java types (`java.util.List`, `org.junit.Assert::*`).
+fn classify_import(family: Family, import: &str, symbol_table: &SymbolTable) -> Classification {
+    let unknown = (UnresolvedCategory::Unknown, None);
+    match family {
+        Family::Rust => {
+            let root = import.split("::").next().unwrap_or(import);
+            match root {
+                "std" | "core" | "alloc" | "proc_macro" => (UnresolvedCategory::Std, Some(root.to_string())),
+                // resolvers::rust already tried local modules and workspace crates
+                "crate" | "self" | "super" => unknown,
+                _ => (UnresolvedCategory::ThirdParty, Some(root.to_string())),
+            }
+        }
+        Family::Js => {
+            let import = import.split("::").next().unwrap_or(import);
+            if import.starts_with('.') || import.starts_with('/') {
+                return unknown;
+            }
+            if let Some(builtin) = import.strip_prefix("node:") {
+                return (UnresolvedCategory::Std, Some(builtin.split('/').next().unwrap_or(builtin).to_string()));
+            }
+            let mut segments = import.split('/');
+            let first = segments.next().unwrap_or(import);
+            if NODE_BUILTINS.contains(&first) {
+                return (UnresolvedCategory::Std, Some(first.to_string()));
+            }
+            let package = match (first.starts_with('@'), segments.next()) {
+                (true, Some(name)) => format!("{first}/{name}"),
+                _ => first.to_string(),
+            };
+            (UnresolvedCategory::ThirdParty, Some(package))
+        }
+        Family::Python => {
+            let module = import.split("::").next().unwrap_or(import);
+            let root = module.split('.').next().unwrap_or(module);
+            if root.is_empty() {
+                unknown
+            } else if PY_STDLIB.contains(&root) {
+                (UnresolvedCategory::Std, Some(root.to_string()))
+            } else if symbol_table.has_module(root) {
+                unknown
+            } else {
+                (UnresolvedCategory::ThirdParty, Some(root.to_string()))
+            }
+        }
+        Family::C => {
+            // System includes are recorded in angle brackets
+            let system = import.starts_with('<');
+            let header = import.trim_start_matches('<').trim_end_matches('>');
+            let root = header.split('/').next().unwrap_or(header);
+            if C_STD_HEADERS.contains(&header) || (header.contains('/') && C_SYSTEM_DIRS.contains(&root)) {
+                (UnresolvedCategory::Std, Some(if header.contains('.') { "libc" } else { "std" }.to_string()))
+            } else if system || (header.contains('/') && !symbol_table.has_module(header.trim_end_matches(".h").trim_end_matches(".hpp"))) {
+                let package = root.trim_end_matches(".h").trim_end_matches(".hpp");
+                (UnresolvedCategory::ThirdParty, Some(package.to_string()))
+            } else {
+                unknown
+            }
+        }
+        Family::Java => {
+            // `a.b.Type`, `a.b.*` or static `a.b.Type::member`: the package is
+            // everything before the type (or the `*`)
+            let owner = import.split("::").next().unwrap_or(import);
+            let segments: Vec<&str> = owner.split('.').collect();
+            let package = &segments[..segments.len().saturating_sub(1)];
+            match segments.first().copied() {
+                Some("java" | "javax" | "jdk" | "sun") => (UnresolvedCategory::Std, Some(package.join("."))),
+                _ if package.is_empty() || symbol_table.has_module(&package.join("/")) => unknown,
+                _ => (UnresolvedCategory::ThirdParty, Some(segments[..2.min(package.len())].join("."))),
+            }
+        }
+        Family::Other => unknown,
+    }
+}
+
+/// Classifies a call (`name`, `receiver.name` or `path::name`) through the
+/// caller file's imports, falling back to well-known standard receivers.
+fn classify_call(family: Family, call: &str, file: &str, symbol_table: &SymbolTable) -> Classification {
+    // `collect::<Vec<_>>` is a turbofish, not a path
+    let call = call.split("::<").next().unwrap_or(call);
+    let root = call.split([':', '.']).next().unwrap_or(call);
+
+    if let Some(import) = symbol_table.imports_of(file).iter().find(|import| import_binding(family, import) == root) {
+        return classify_import(family, import, symbol_table);
+    }
+    if root == call {
+        return (UnresolvedCategory::Unknown, None);
+    }
+
+    match family {
+        Family::Rust if matches!(root, "std" | "core" | "alloc") => (UnresolvedCategory::Std, Some(root.to_string())),
+        Family::Rust
+            if call.contains("::")
+                && root.starts_with(char::is_lowercase)
+                && !matches!(root, "crate" | "self" | "super")
+                && !symbol_table.has_module(root) =>
+        {
+            // `serde_json::to_string` without a `use`: a path rooted at an external crate
+            (UnresolvedCategory::ThirdParty, Some(root.to_string()))
+        }
+        Family::Js if JS_GLOBALS.contains(&root) => (UnresolvedCategory::Std, Some(root.to_string())),
+        Family::Python if PY_STDLIB.contains(&root) => (UnresolvedCategory::Std, Some(root.to_string())),
+        Family::Java if JAVA_LANG.contains(&root) => (UnresolvedCategory::Std, Some("java.lang".to_string())),
+        Family::C if root == "std" => (UnresolvedCategory::Std, Some("std".to_string())),
+        _ => (UnresolvedCategory::Unknown, None),
+    }
+}
+
+/// The name an import is usually referred to by in code (`std::collections::HashMap`
+/// -> `HashMap`, `react::useState` -> `useState`, `os.path` -> `os`, `node:fs` -> `fs`,
+/// `java.util.List` -> `List`).
+fn import_binding(family: Family, import: &str) -> &str {
+    if let Some((_, name)) = import.rsplit_once("::") {
+        return name;
+    }
+    let import = [".js", ".ts"].iter().find_map(|ext| import.strip_suffix(ext)).unwrap_or(import);
+    if family == Family::Java {
+        import.rsplit('.').next().unwrap_or(import)
+    } else if import.contains(['/', ':']) {
+        import.rsplit(['/', ':']).next().unwrap_or(import)
+    } else {
+        import.split('.').next().unwrap_or(import)
+    }
+}
+
+/// Groups unresolved references by the District their source entity sits in
+/// within `cities`, with per-category counts and the std and third-party
+/// packages each one uses. References outside any District are grouped
+/// under an empty path.
+pub fn summarize(world_id: String, refs: &[UnresolvedRef], cities: &[GameEntity]) -> UnresolvedSummary {
+    #[derive(Default)]
+    struct DistrictAcc {
+        counts: CategoryCounts,
+        packages: BTreeMap<String, (UnresolvedCategory, u32, BTreeSet<String>)>,
+    }
+
+    let owners = hierarchy::index_owners(cities);
+    let paths = hierarchy::district_paths(cities);
+    let mut totals = CategoryCounts::default();
+    let mut districts: BTreeMap<String, (Option<String>, DistrictAcc)> = BTreeMap::new();
+
+    for r in refs {
+        let district = owners.get(&r.from_id).and_then(|o| o.district.clone());
+        let path = district.as_ref().and_then(|id| paths.get(id)).cloned().unwrap_or_default();
+        let (_, acc) = districts.entry(path).or_insert_with(|| (district, DistrictAcc::default()));
+
+        for counts in [&mut totals, &mut acc.counts] {
+            match r.category {
+                UnresolvedCategory::Std => counts.std += 1,
+                UnresolvedCategory::ThirdParty => counts.third_party += 1,
+                UnresolvedCategory::Unknown => counts.unknown += 1,
+            }
+        }
+
+        if let Some(package) = &r.package {
+            let (_, references, targets) =
+                acc.packages.entry(package.clone()).or_insert((r.category, 0, BTreeSet::new()));
+            *references += 1;
+            targets.insert(r.target.clone());
+        }
+    }
+
+    let districts = districts
+        .into_iter()
+        .map(|(path, (district_id, acc))| {
+            let mut packages: Vec<PackageUsage> = acc
+                .packages
+                .into_iter()
+                .map(|(package, (category, references, targets))| PackageUsage {
+                    package,
+                    category,
+                    references,
+                    targets: targets.into_iter().take(MAX_TARGETS_PER_PACKAGE).collect(),
+                })
+                .collect();
+            packages.sort_by(|a, b| b.references.cmp(&a.references).then_with(|| a.package.cmp(&b.package)));
+
+            DistrictDependencies {
+                district_id,
+                path,
+                counts: acc.counts,
+                packages,
+            }
+        })
+        .collect();
+
+    UnresolvedSummary { world_id, totals, districts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, city, district};
+
+    fn import(from: &str, target: &str) -> Route {
+        Route {
+            id: String::new(),
+            from_id: from.to_string(),
+            to_id: target.to_string(),
+            route_type: RouteType::Import,
+            bidirectional: false,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_classify_imports_by_language() {
+        let table = SymbolTable::new();
+        let cases = [
+            ("src/main.rs", "std::collections::HashMap", UnresolvedCategory::Std, Some("std")),
+            ("src/main.rs", "serde::Deserialize", UnresolvedCategory::ThirdParty, Some("serde")),
+            ("src/main.rs", "crate::missing", UnresolvedCategory::Unknown, None),
+            ("web/app.ts", "@tanstack/react-query::useQuery", UnresolvedCategory::ThirdParty, Some("@tanstack/react-query")),
+            ("web/app.ts", "node:fs/promises", UnresolvedCategory::Std, Some("fs")),
+            ("web/app.ts", "./missing.ts", UnresolvedCategory::Unknown, None),
+            ("api/app.py", "os.path", UnresolvedCategory::Std, Some("os")),
+            ("api/app.py", "requests::get", UnresolvedCategory::ThirdParty, Some("requests")),
+            ("api/app.py", ".models::User", UnresolvedCategory::Unknown, None),
+            ("c/main.c", "<stdio.h>", UnresolvedCategory::Std, Some("libc")),
+            ("c/main.c", "<openssl/ssl.h>", UnresolvedCategory::ThirdParty, Some("openssl")),
+            ("c/main.c", "<zlib.h>", UnresolvedCategory::ThirdParty, Some("zlib")),
+            ("c/main.c", "missing.h", UnresolvedCategory::Unknown, None),
+            ("j/App.java", "java.util.List", UnresolvedCategory::Std, Some("java.util")),
+            ("j/App.java", "java.util.concurrent.*", UnresolvedCategory::Std, Some("java.util.concurrent")),
+            ("j/App.java", "org.springframework.web.bind.annotation.GetMapping", UnresolvedCategory::ThirdParty, Some("org.springframework")),
+            ("j/App.java", "org.junit.Assert::assertEquals", UnresolvedCategory::ThirdParty, Some("org.junit")),
+        ];
+        for (from, target, category, package) in cases {
+            let r = classify_route(&import(from, target), &table);
+            assert_eq!((r.category, r.package.as_deref()), (category, package), "{target}");
+        }
+    }
+
+    #[test]
+    fn test_summary_groups_packages_per_district() {
+        let table = SymbolTable::new();
+        let refs: Vec<UnresolvedRef> = [
+            ("src/db/mod.rs", "mongodb::Client"),
+            ("src/db/mod.rs", "mongodb::IndexModel"),
+            ("src/db/world.rs", "mongodb::Client"),
+            ("main.rs", "std::sync::Arc"),
+            ("java/App.java", "org.junit.Assert"),
+        ]
+        .iter()
+        .map(|(from, target)| classify_route(&import(from, target), &table))
+        .collect();
+
+        let file = |id: &str| test_support::file(id, vec![], &[]);
+        let cities = vec![city("rs", vec![
+            file("main.rs"),
+            district("src", vec![district("src/db", vec![file("src/db/mod.rs"), file("src/db/world.rs")])]),
+            // A Java package District is not the file's directory
+            district("com/acme", vec![file("java/App.java")]),
+        ])];
+
+        let summary = summarize("w".into(), &refs, &cities);
+        assert_eq!(summary.totals.third_party, 4);
+        assert_eq!(summary.districts.len(), 3);
+
+        let root = &summary.districts[0];
+        assert_eq!((root.district_id.as_deref(), root.packages[0].package.as_str()), (None, "std"));
+
+        let java = &summary.districts[1];
+        assert_eq!((java.district_id.as_deref(), java.path.as_str()), (Some("district_com_acme"), "com/acme"));
+
+        let db = &summary.districts[2];
+        assert_eq!(db.district_id.as_deref(), Some("district_src_db"));
+        assert_eq!(db.packages[0].references, 3);
+        assert_eq!(db.packages[0].targets, vec!["mongodb::Client", "mongodb::IndexModel"]);
+    }
+}
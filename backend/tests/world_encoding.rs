@@ -0,0 +1,73 @@
+use backend::encoding::{self, InternedWorld, WorldEncoding};
+use backend::models::WorldResponse;
+
+// ---------------------------------------------------------------------------
+// The interned layout is a wire contract for non-Rust decoders: the fixture
+// in tests/fixtures/interned_world.json is the documented example and must
+// be updated deliberately (and INTERNED_FORMAT_VERSION bumped) on change.
+// ---------------------------------------------------------------------------
+
+fn fixture_world() -> WorldResponse {
+    serde_json::from_str(include_str!("fixtures/world_response.json")).unwrap()
+}
+
+fn expected_interned() -> serde_json::Value {
+    serde_json::from_str(include_str!("fixtures/interned_world.json")).unwrap()
+}
+
+#[test]
+fn interned_world_matches_fixture() {
+    let world = fixture_world();
+
+    let body = encoding::encode_world(&world, WorldEncoding::Interned).unwrap();
+    let actual: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(actual, expected_interned());
+}
+
+#[test]
+fn msgpack_decodes_to_same_document() {
+    let world = fixture_world();
+
+    let body = encoding::encode_world(&world, WorldEncoding::Msgpack).unwrap();
+    let decoded: serde_json::Value = rmp_serde::from_slice(&body).unwrap();
+
+    assert_eq!(decoded, expected_interned());
+}
+
+#[test]
+fn interned_routes_expand_to_original_endpoints() {
+    let world = fixture_world();
+    let body = encoding::encode_world(&world, WorldEncoding::Msgpack).unwrap();
+    let interned: InternedWorld = rmp_serde::from_slice(&body).unwrap();
+
+    let expanded = interned.routes().unwrap();
+
+    assert_eq!(expanded.len(), world.seed.highways.len());
+    for (route, original) in expanded.iter().zip(&world.seed.highways) {
+        assert_eq!(route.id, original.id);
+        assert_eq!(route.from_id, original.from_id);
+        assert_eq!(route.to_id, original.to_id);
+    }
+}
+
+#[test]
+fn interned_routes_reject_unknown_endpoints() {
+    let mut interned = InternedWorld::from_response(&fixture_world());
+    interned.routes[0].to = interned.ids.len() as u32;
+
+    assert!(interned.routes().is_err());
+}
+
+#[test]
+fn negotiation_prefers_explicit_format() {
+    assert_eq!(WorldEncoding::negotiate(None, None), WorldEncoding::Pretty);
+    assert_eq!(
+        WorldEncoding::negotiate(None, Some("application/x-msgpack, application/json;q=0.9")),
+        WorldEncoding::Msgpack
+    );
+    assert_eq!(
+        WorldEncoding::negotiate(Some(WorldEncoding::Compact), Some("application/msgpack")),
+        WorldEncoding::Compact
+    );
+}
@@ -27,6 +27,10 @@ export type RouteType =
   | 'Inheritance'
   | 'NetworkRequest'
   | 'TypeReference'
+  | 'Definition'
+  | 'Ffi'
+  | 'CodeClone'
+  | 'Tests'
 
 export interface Highway {
   id: string
@@ -37,6 +41,19 @@ export interface Highway {
   metadata?: Record<string, unknown> | null
 }
 
+// ----------- Source Location -------------
+
+// Lines are 1-based; columns are 0-based byte offsets within the line
+export interface SourceSpan {
+  file: string
+  start_line: number
+  start_column: number
+  end_line: number
+  end_column: number
+  start_byte: number
+  end_byte: number
+}
+
 // ----------- Parameters -------------
 
 export interface Parameter {
@@ -57,6 +74,7 @@ export interface ArtifactSpec {
   datatype: string
   is_mutable: boolean
   value_hint: string | null
+  span?: SourceSpan // absent on worlds stored before spans were recorded
   metadata?: Record<string, string> | null
 }
 
@@ -73,6 +91,7 @@ export interface RoomSpec {
   room_type: string
   is_main: boolean
   is_async: boolean
+  is_test?: boolean // omitted when false
   visibility: string
   complexity: number
   loc: number
@@ -80,6 +99,7 @@ export interface RoomSpec {
   return_type: string | null
   calls: string[]
   children?: GameEntity[] // Recursive children
+  span?: SourceSpan
   metadata?: Record<string, string> | null
 }
 
@@ -96,9 +116,12 @@ export interface BuildingSpec {
   name: string
   building_type: string
   is_public: boolean
+  is_test?: boolean // omitted when false
   loc: number
   imports: string[]
+  supertypes?: string[] // omitted when empty
   children?: GameEntity[] // Recursive children
+  span?: SourceSpan
   metadata?: Record<string, string> | null
 }
 
@@ -115,6 +138,7 @@ export interface DistrictSpec {
   name: string
   path: string
   children?: GameEntity[] // Recursive children
+  metadata?: Record<string, string> | null
 }
 
 export interface District {
@@ -140,6 +164,7 @@ export interface CitySpec {
   entry_point_id: string | null // Fixed typo: entrypoint_id -> entry_point_id
   stats: CityStats
   children?: GameEntity[] // Recursive children
+  metadata?: Record<string, string> | null
 }
 
 export interface City {
@@ -165,6 +190,10 @@ export interface WorldMeta {
   total_artifacts?: number
   dominant_language?: string
   complexity_score?: number
+  complexity_score_version?: number
+  cycle_count?: number
+  modularity?: number
+  main_sequence_distance?: number
 }
 
 export interface WorldSeed {